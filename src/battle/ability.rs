@@ -0,0 +1,140 @@
+use crate::battle::combat_log::BattleEvent;
+use crate::battle::footprint;
+use crate::battle::state::Side;
+use crate::battle::status::StatusEffect;
+use crate::display::tile::Tile;
+use serde::{Deserialize, Serialize};
+
+/// Innate traits of a creature, taken from its unit definition.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Ability {
+    /// Moves over obstacles and other stacks.
+    Flying,
+    /// Shoots non-adjacent enemies while it has ammo; fights in melee at a penalty.
+    Ranged { ammo: u32 },
+    /// Its strikes are never answered.
+    NoRetaliation,
+    /// Strikes twice per attack.
+    DoubleAttack,
+    /// Heals the most wounded adjacent ally at the start of every round.
+    Heal { amount: u32 },
+    /// Brings fallen creatures of the most depleted adjacent ally back every round.
+    Resurrect { health: u32 },
+    Undead,
+    /// Extra damage against undead stacks.
+    Holy { bonus_percent: u32 },
+    /// A status effect every strike leaves on the target, e.g. an alchemist's potion.
+    OnHit(StatusEffect),
+    /// Built, not recruited: towers and siege engines never win or lose a battle on their own.
+    WarMachine,
+    /// Batters wall segments and the gate of a besieged town from anywhere on the field.
+    Siege { damage: u32 },
+}
+
+/// Damage factor of a shooter forced to fight in melee.
+pub const MELEE_PENALTY: f32 = 0.5;
+
+/// Refreshes retaliations and lets healers and resurrectors tend to their neighbours.
+pub fn start_round(tiles: &mut [Tile]) -> Vec<BattleEvent> {
+    let mut events = Vec::new();
+    for index in 0..tiles.len() {
+        let Some(unit) = tiles[index].unit.as_mut() else {
+            continue;
+        };
+        unit.retaliated = false;
+        let Some(side) = Side::from_tile_type(&tiles[index].tile_type) else {
+            continue;
+        };
+        for ability in tiles[index].unit.clone().map(|unit| unit.abilities).unwrap_or_default() {
+            let allies = || {
+                footprint::adjacent_stacks(tiles, index)
+                    .into_iter()
+                    .filter(|&next| Side::from_tile_type(&tiles[next].tile_type) == Some(side))
+            };
+            match ability {
+                Ability::Heal { amount } => {
+                    let wounded = allies()
+                        .map(|next| (next, tiles[next].get_unit().map_or(0, |unit| unit.health - unit.top_health)))
+                        .filter(|&(_, missing)| missing > 0)
+                        .max_by_key(|&(_, missing)| missing);
+                    if let Some((next, _)) = wounded
+                        && let Some(ally) = tiles[next].unit.as_mut()
+                    {
+                        events.push(BattleEvent::Healed {
+                            unit: ally.name.clone(),
+                            amount: ally.heal(amount),
+                        });
+                    }
+                }
+                Ability::Resurrect { health } => {
+                    let depleted = allies()
+                        .map(|next| (next, tiles[next].get_unit().map_or(0, |unit| unit.initial_count - unit.count)))
+                        .filter(|&(_, fallen)| fallen > 0)
+                        .max_by_key(|&(_, fallen)| fallen);
+                    if let Some((next, _)) = depleted
+                        && let Some(ally) = tiles[next].unit.as_mut()
+                    {
+                        let count = ally.resurrect(health);
+                        if count > 0 {
+                            events.push(BattleEvent::Reborn {
+                                unit: ally.name.clone(),
+                                count,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tile::{TileType, Unit};
+
+    fn board(support: Ability, ally: Unit) -> Vec<Tile> {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(Unit::new(0, "Support", 5, 30, 4, 2).with_abilities(vec![support]), TileType::MyUnit);
+        tiles[1].set_unit(ally, TileType::MyUnit);
+        tiles[2].set_unit(Unit::new(2, "Peasants", 10, 5, 1, 1), TileType::EnemyUnit);
+        tiles
+    }
+
+    #[test]
+    fn test_healer_tends_wounded_neighbour() {
+        let mut knights = Unit::new(1, "Knights", 10, 30, 3, 2);
+        knights.take_damage(20);
+        let mut tiles = board(Ability::Heal { amount: 15 }, knights);
+
+        let events = start_round(&mut tiles);
+
+        assert_eq!(tiles[1].get_unit().unwrap().top_health, 25);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_resurrection_is_capped_at_initial_count() {
+        let mut knights = Unit::new(1, "Knights", 10, 30, 3, 2);
+        knights.take_damage(95);
+        let mut tiles = board(Ability::Resurrect { health: 1000 }, knights);
+
+        start_round(&mut tiles);
+
+        let knights = tiles[1].get_unit().unwrap();
+        assert_eq!((knights.count, knights.top_health), (10, 30));
+    }
+
+    #[test]
+    fn test_start_round_refreshes_retaliation() {
+        let mut knights = Unit::new(1, "Knights", 10, 30, 3, 2);
+        knights.retaliated = true;
+        let mut tiles = board(Ability::Flying, knights);
+
+        start_round(&mut tiles);
+
+        assert!(!tiles[1].get_unit().unwrap().retaliated);
+    }
+}
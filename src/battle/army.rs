@@ -0,0 +1,267 @@
+use crate::battle::ability::Ability;
+use crate::battle::footprint;
+use crate::battle::result::BattleResult;
+use crate::battle::state::Side;
+use crate::battle::status::{Stat, StatusEffect};
+use crate::display::GameState;
+use crate::display::tile::{Tile, TileType, Unit};
+use crate::error::{Error, Result};
+use crate::hero::Hero;
+use std::ops::Range;
+
+/// The stacks one side brings to a battle, optionally led by a hero.
+#[derive(Clone, Debug, Default)]
+pub struct Army {
+    pub hero: Option<Hero>,
+    stacks: Vec<Unit>,
+}
+
+impl Army {
+    pub const MAX_STACKS: usize = 7;
+    /// Columns at each edge of the grid where a side deploys.
+    pub const SPAWN_DEPTH: usize = 2;
+    pub const MORALE_EFFECT_ID: &'static str = "army_morale";
+
+    pub fn new(hero: Option<Hero>) -> Self {
+        Self {
+            hero,
+            stacks: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, unit: Unit) -> Result<()> {
+        if self.stacks.len() >= Self::MAX_STACKS {
+            return Err(Error::rules(format!("An army holds at most {} stacks", Self::MAX_STACKS)));
+        }
+        self.stacks.push(unit);
+        Ok(())
+    }
+
+    /// Adds `unit` to a stack of the same creature if there is one, as a new stack otherwise.
+    pub fn reinforce(&mut self, unit: Unit) -> Result<()> {
+        let same = self.stacks.iter_mut().find(|stack| stack.kind.is_some() && stack.kind == unit.kind);
+        match same {
+            Some(stack) => {
+                stack.count += unit.count;
+                stack.initial_count += unit.count;
+                Ok(())
+            }
+            None => self.add(unit),
+        }
+    }
+
+    pub fn stacks(&self) -> &[Unit] {
+        &self.stacks
+    }
+
+    /// Living stacks lose a point of morale when they march alongside the undead.
+    pub fn morale_effect(&self) -> Option<StatusEffect> {
+        let undead = self.stacks.iter().filter(|unit| unit.has_ability(&Ability::Undead)).count();
+        (undead > 0 && undead < self.stacks.len()).then(|| {
+            StatusEffect::new(Self::MORALE_EFFECT_ID, "Undead in the ranks", None).with_modifier(Stat::Morale, -1)
+        })
+    }
+
+    /// The army as it leaves a battle in which it fought for `side`: every stack keeps the
+    /// creatures `result` counted for it and wiped out stacks are gone.
+    pub fn after_battle(&self, side: Side, result: &BattleResult) -> Army {
+        let remaining = result.remaining.iter().filter(|(fought_for, _)| *fought_for == side);
+        let stacks = self
+            .stacks
+            .iter()
+            .zip(remaining)
+            .filter(|(_, (_, left))| *left > 0)
+            .map(|(unit, (_, left))| Unit {
+                count: *left,
+                initial_count: *left,
+                top_health: unit.health,
+                ..unit.clone()
+            })
+            .collect();
+        Army {
+            hero: self.hero.clone(),
+            stacks,
+        }
+    }
+
+    pub fn into_parts(self) -> (Option<Hero>, Vec<Unit>) {
+        (self.hero, self.stacks)
+    }
+
+    pub fn spawn_columns(side: Side) -> Range<usize> {
+        match side {
+            Side::Player => 0..Self::SPAWN_DEPTH,
+            Side::Enemy => GameState::GRID_SIZE - Self::SPAWN_DEPTH..GameState::GRID_SIZE,
+        }
+    }
+
+    pub fn in_spawn_zone(side: Side, index: usize) -> bool {
+        Self::spawn_columns(side).contains(&(index % GameState::GRID_SIZE))
+    }
+
+    /// Turns the empty tiles of both spawn zones into spawn points.
+    pub fn mark_spawn_zones(tiles: &mut [Tile]) {
+        for (index, tile) in tiles.iter_mut().enumerate() {
+            let in_zone = Self::in_spawn_zone(Side::Player, index) || Self::in_spawn_zone(Side::Enemy, index);
+            if in_zone && tile.tile_type == TileType::Empty && tile.get_unit().is_none() {
+                tile.tile_type = TileType::SpawnPoint;
+            }
+        }
+    }
+
+    /// The side a spawn point on `index` belongs to: the player's on the left half of the
+    /// field, the enemy's on the right, wherever a battle map puts them.
+    pub fn spawn_side(index: usize) -> Side {
+        if index % GameState::GRID_SIZE < GameState::GRID_SIZE / 2 { Side::Player } else { Side::Enemy }
+    }
+
+    /// Free spawn points of `side`.
+    pub fn free_spawn_points(tiles: &[Tile], side: Side) -> Vec<usize> {
+        (0..tiles.len())
+            .filter(|&index| Self::spawn_side(index) == side)
+            .filter(|&index| tiles[index].tile_type == TileType::SpawnPoint && tiles[index].get_unit().is_none())
+            .collect()
+    }
+
+    /// True when a stack of `size` anchored on `anchor` lies on free spawn points of `side`,
+    /// or on cells the stack `unit_id` already holds.
+    pub fn fits_spawn_zone(tiles: &[Tile], side: Side, anchor: usize, size: usize, unit_id: Option<usize>) -> bool {
+        let on_spawn_point = |index: usize| {
+            let owner = tiles[footprint::anchor_of(tiles, index)].get_unit();
+            let held = owner.is_some_and(|unit| Some(unit.id) == unit_id);
+            Self::spawn_side(index) == side && (tiles[index].tile_type == TileType::SpawnPoint || held)
+        };
+        footprint::cells(anchor, size).is_some_and(|cells| cells.into_iter().all(on_spawn_point))
+            && footprint::fits(tiles, anchor, size, unit_id)
+    }
+
+    /// Anchor tiles for stacks of `sizes` on `side`: spread evenly along the back column,
+    /// then whatever spawn points are left. Large stacks reach forward from the back edge.
+    pub fn deployment(tiles: &[Tile], side: Side, sizes: &[usize]) -> Result<Vec<usize>> {
+        let count = sizes.len();
+        if count > Self::MAX_STACKS {
+            return Err(Error::rules(format!("An army holds at most {} stacks", Self::MAX_STACKS)));
+        }
+        let free = Self::free_spawn_points(tiles, side);
+        let columns = Self::spawn_columns(side);
+        let grid = GameState::GRID_SIZE;
+        let mut taken: Vec<usize> = Vec::new();
+        let mut chosen: Vec<usize> = Vec::with_capacity(count);
+        for (slot, &size) in sizes.iter().enumerate() {
+            let back = if side == Side::Player { columns.start } else { columns.end.saturating_sub(size) };
+            let spread = (2 * slot + 1) * grid / (2 * count) * grid + back;
+            let anchor = std::iter::once(spread)
+                .chain(free.iter().copied())
+                .find(|&anchor| {
+                    let cells = footprint::cells(anchor, size).unwrap_or_default();
+                    Self::fits_spawn_zone(tiles, side, anchor, size, None) && cells.iter().all(|cell| !taken.contains(cell))
+                })
+                .ok_or_else(|| Error::rules(format!("Not enough spawn points for {} stacks", count)))?;
+            taken.extend(footprint::cells(anchor, size).unwrap_or_default());
+            chosen.push(anchor);
+        }
+        Ok(chosen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles() -> Vec<Tile> {
+        let mut tiles = Tile::board_for_test();
+        Army::mark_spawn_zones(&mut tiles);
+        tiles
+    }
+
+    #[test]
+    fn test_army_is_capped_at_seven_stacks() {
+        let mut army = Army::new(None);
+        for id in 0..Army::MAX_STACKS {
+            army.add(Unit::new(id, "Peasants", 10, 5, 1, 1)).unwrap();
+        }
+        assert!(army.add(Unit::new(7, "Peasants", 10, 5, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn test_undead_in_the_ranks_lower_morale() {
+        let mut army = Army::new(None);
+        army.add(Unit::new(0, "Knights", 10, 30, 3, 2)).unwrap();
+        assert!(army.morale_effect().is_none());
+
+        army.add(Unit::new(1, "Skeletons", 10, 6, 2, 2).with_abilities(vec![Ability::Undead])).unwrap();
+        let mut knights = army.stacks()[0].clone();
+        knights.add_effect(army.morale_effect().unwrap());
+        assert_eq!(knights.effective_morale(), -1);
+    }
+
+    #[test]
+    fn test_after_battle_keeps_survivors() {
+        let mut army = Army::new(None);
+        army.add(Unit::new(0, "Knights", 10, 30, 3, 2)).unwrap();
+        army.add(Unit::new(1, "Archers", 8, 10, 2, 2)).unwrap();
+        let result = BattleResult {
+            victory: true,
+            remaining: vec![(Side::Player, 6), (Side::Enemy, 0), (Side::Player, 0)],
+            ..BattleResult::default()
+        };
+
+        let after = army.after_battle(Side::Player, &result);
+        assert_eq!(after.stacks().len(), 1);
+        assert_eq!((after.stacks()[0].count, after.stacks()[0].initial_count), (6, 6));
+    }
+
+    #[test]
+    fn test_spawn_zones_sit_on_opposite_edges() {
+        let tiles = tiles();
+        assert_eq!(Army::free_spawn_points(&tiles, Side::Player).len(), 2 * GameState::GRID_SIZE);
+        assert_eq!(tiles[0].tile_type, TileType::SpawnPoint);
+        assert_eq!(tiles[GameState::GRID_SIZE - 1].tile_type, TileType::SpawnPoint);
+        assert_eq!(tiles[5].tile_type, TileType::Empty);
+    }
+
+    #[test]
+    fn test_deployment_spreads_along_back_column() {
+        let tiles = tiles();
+        let player = Army::deployment(&tiles, Side::Player, &[1, 1]).unwrap();
+        let enemy = Army::deployment(&tiles, Side::Enemy, &[1]).unwrap();
+
+        assert_eq!(player, vec![3 * GameState::GRID_SIZE, 9 * GameState::GRID_SIZE]);
+        assert_eq!(enemy, vec![6 * GameState::GRID_SIZE + GameState::GRID_SIZE - 1]);
+    }
+
+    #[test]
+    fn test_large_stacks_deploy_inside_the_zone() {
+        let tiles = tiles();
+        let enemy = Army::deployment(&tiles, Side::Enemy, &[2, 1]).unwrap();
+        assert_eq!(enemy[0] % GameState::GRID_SIZE, GameState::GRID_SIZE - 2);
+
+        let player = Army::deployment(&tiles, Side::Player, &[2; Army::MAX_STACKS]).unwrap();
+        assert!(player.iter().all(|anchor| anchor % GameState::GRID_SIZE == 0));
+        assert!(Army::deployment(&tiles, Side::Player, &[3]).is_err());
+    }
+
+    #[test]
+    fn test_deployment_falls_back_to_free_spawn_points() {
+        let mut tiles = tiles();
+        for row in 0..GameState::GRID_SIZE {
+            tiles[row * GameState::GRID_SIZE].tile_type = TileType::Obstacle;
+        }
+        let chosen = Army::deployment(&tiles, Side::Player, &[1; Army::MAX_STACKS]).unwrap();
+        assert!(chosen.iter().all(|index| index % GameState::GRID_SIZE == 1));
+        assert_eq!(chosen.len(), Army::MAX_STACKS);
+    }
+
+    #[test]
+    fn test_spawn_points_belong_to_the_half_they_stand_on() {
+        let mut tiles = Tile::board_for_test();
+        let (player, enemy) = (3 * GameState::GRID_SIZE + 4, 3 * GameState::GRID_SIZE + 7);
+        tiles[player].tile_type = TileType::SpawnPoint;
+        tiles[enemy].tile_type = TileType::SpawnPoint;
+
+        assert_eq!(Army::free_spawn_points(&tiles, Side::Player), vec![player]);
+        assert_eq!(Army::deployment(&tiles, Side::Enemy, &[1]).unwrap(), vec![enemy]);
+        assert!(!Army::fits_spawn_zone(&tiles, Side::Player, player, 2, None));
+        assert!(Army::deployment(&tiles, Side::Player, &[1, 1]).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::battle::army::Army;
+use crate::battle::movement::neighbours;
+use crate::battle::state::Side;
+use crate::common::rng::GameRng;
+use crate::display::GameState;
+use crate::error::{Error, Result};
+use crate::world::Terrain;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::ops::{Range, RangeInclusive};
+
+/// What stands on one cell of a battlefield.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cover {
+    #[default]
+    Open,
+    Forest,
+    Rock,
+}
+
+impl Cover {
+    /// Cover written as `c` in battle map files; spawn points are open ground.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' | 'p' | 'e' => Some(Cover::Open),
+            'F' => Some(Cover::Forest),
+            '^' => Some(Cover::Rock),
+            _ => None,
+        }
+    }
+
+    /// The cell in `Battlefield::preview`, drawn like the adventure map draws its ground.
+    pub fn symbol(&self) -> char {
+        match self {
+            Cover::Open => '.',
+            Cover::Forest => 'F',
+            Cover::Rock => '^',
+        }
+    }
+}
+
+/// How crowded a field fought on some adventure terrain gets: clumps of cover placed on
+/// either half and the chance, out of ten, that a clump is rock rather than forest.
+struct Profile {
+    clumps: RangeInclusive<u32>,
+    rocks: u32,
+}
+
+impl Profile {
+    fn of(terrain: Terrain) -> Self {
+        let (clumps, rocks) = match terrain {
+            Terrain::Road => (1..=2, 5),
+            Terrain::Grass => (2..=4, 4),
+            Terrain::Forest => (4..=6, 1),
+            Terrain::Mountain => (3..=5, 8),
+            Terrain::Water => (2..=3, 7),
+        };
+        Self { clumps, rocks }
+    }
+}
+
+/// The ground a battle is fought on, cell by cell; stacks and walls come on top of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Battlefield {
+    cells: Vec<Cover>,
+    /// Where a hand-made field lets the sides deploy; empty for the usual spawn zones.
+    #[serde(default)]
+    spawn_points: Vec<usize>,
+}
+
+impl Default for Battlefield {
+    fn default() -> Self {
+        Self::open()
+    }
+}
+
+impl Battlefield {
+    /// Cells a clump of cover grows to at most.
+    pub const MAX_CLUMP: u32 = 3;
+    /// Tries the generator gets to place its clumps before it settles for fewer.
+    const ATTEMPTS: u32 = 50;
+
+    pub fn open() -> Self {
+        Self {
+            cells: vec![Cover::Open; GameState::GRID_SIZE * GameState::GRID_SIZE],
+            spawn_points: Vec::new(),
+        }
+    }
+
+    /// A field laid out by hand, as a battle map file describes it; with no `spawn_points`
+    /// the sides deploy on their usual zones.
+    pub fn new(cells: Vec<Cover>, spawn_points: Vec<usize>) -> Result<Self> {
+        let size = GameState::GRID_SIZE * GameState::GRID_SIZE;
+        if cells.len() != size {
+            return Err(Error::rules(format!("A battlefield has {} cells, not {}", size, cells.len())));
+        }
+        if let Some(index) = spawn_points.iter().find(|&&index| cells.get(index) != Some(&Cover::Open)) {
+            return Err(Error::rules(format!("Spawn point {} is not on open ground", index)));
+        }
+        Ok(Self { cells, spawn_points })
+    }
+
+    /// A field for a fight on `terrain`, the same for the same `seed`. Cover comes in clumps
+    /// mirrored through the centre, so both sides face the same ground, and stays off the
+    /// spawn zones and the column in front of each; a clump that would cut off any open cell
+    /// is dropped.
+    pub fn generate(terrain: Terrain, seed: u64) -> Self {
+        let mut rng = GameRng::new(seed);
+        let profile = Profile::of(terrain);
+        let clumps = rng.range(profile.clumps);
+        let columns = Self::cover_columns();
+        let mut field = Self::open();
+        let mut placed = 0;
+        for _ in 0..Self::ATTEMPTS {
+            if placed == clumps {
+                break;
+            }
+            let row = rng.below(GameState::GRID_SIZE as u32) as usize;
+            let col = columns.start + rng.below(columns.len() as u32) as usize;
+            let mut clump = vec![row * GameState::GRID_SIZE + col];
+            for _ in 1..rng.range(1..=Self::MAX_CLUMP) {
+                let last = clump[clump.len() - 1];
+                let options: Vec<usize> = neighbours(last)
+                    .filter(|next| columns.contains(&(next % GameState::GRID_SIZE)) && !clump.contains(next))
+                    .collect();
+                if options.is_empty() {
+                    break;
+                }
+                clump.push(options[rng.below(options.len() as u32) as usize]);
+            }
+            let cover = if rng.chance(profile.rocks, 10) { Cover::Rock } else { Cover::Forest };
+            let cells: Vec<usize> = clump.iter().flat_map(|&index| [index, Self::mirror(index)]).collect();
+            if cells.iter().any(|&index| field.cells[index] != Cover::Open) {
+                continue;
+            }
+            let mut grown = field.clone();
+            for &index in &cells {
+                grown.cells[index] = cover;
+            }
+            if grown.is_connected() {
+                field = grown;
+                placed += 1;
+            }
+        }
+        field
+    }
+
+    /// Columns the generator may cover: between the spawn zones, one clear column in front
+    /// of each, which also leaves room for a town's walls.
+    pub fn cover_columns() -> Range<usize> {
+        Army::spawn_columns(Side::Player).end + 1..Army::spawn_columns(Side::Enemy).start - 1
+    }
+
+    /// The cell opposite `index` through the centre of the field.
+    pub fn mirror(index: usize) -> usize {
+        GameState::GRID_SIZE * GameState::GRID_SIZE - 1 - index
+    }
+
+    pub fn cover(&self, index: usize) -> Cover {
+        self.cells.get(index).copied().unwrap_or_default()
+    }
+
+    /// Covered cells with what covers them.
+    pub fn obstacles(&self) -> impl Iterator<Item = (usize, Cover)> + '_ {
+        self.cells.iter().copied().enumerate().filter(|(_, cover)| *cover != Cover::Open)
+    }
+
+    /// The side that deploys on `index`, if it is an open spawn point.
+    pub fn spawn_point(&self, index: usize) -> Option<Side> {
+        if self.cover(index) != Cover::Open {
+            return None;
+        }
+        if self.spawn_points.is_empty() {
+            [Side::Player, Side::Enemy].into_iter().find(|&side| Army::in_spawn_zone(side, index))
+        } else {
+            self.spawn_points.contains(&index).then(|| Army::spawn_side(index))
+        }
+    }
+
+    /// The first open cell a walker can't reach from the other open cells, if any.
+    pub fn cut_off(&self) -> Option<usize> {
+        let open = |index: usize| self.cells[index] == Cover::Open;
+        let start = (0..self.cells.len()).find(|&index| open(index))?;
+        let mut reached = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            for next in neighbours(index) {
+                if open(next) && reached.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        (0..self.cells.len()).find(|index| open(*index) && !reached.contains(index))
+    }
+
+    /// True when a walker can get from any open cell to every other, both spawn zones included.
+    pub fn is_connected(&self) -> bool {
+        self.cut_off().is_none()
+    }
+
+    /// The field as text, one line per row, for checking fields by eye: cover symbols, with
+    /// the spawn points drawn as `p` and `e` the way battle map files write them.
+    pub fn preview(&self) -> String {
+        let mut text = String::new();
+        for (index, cover) in self.cells.iter().enumerate() {
+            text.push(match self.spawn_point(index) {
+                Some(Side::Player) => 'p',
+                Some(Side::Enemy) => 'e',
+                None => cover.symbol(),
+            });
+            if index % GameState::GRID_SIZE == GameState::GRID_SIZE - 1 {
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(field: &Battlefield, cover: Cover) -> usize {
+        field.obstacles().filter(|(_, covered)| *covered == cover).count()
+    }
+
+    #[test]
+    fn test_same_seed_same_field() {
+        assert_eq!(Battlefield::generate(Terrain::Grass, 9), Battlefield::generate(Terrain::Grass, 9));
+        let fields: HashSet<String> =
+            (0..10).map(|seed| Battlefield::generate(Terrain::Grass, seed).preview()).collect();
+        assert!(fields.len() > 1);
+    }
+
+    #[test]
+    fn test_generated_fields_are_mirrored_connected_and_keep_lanes_clear() {
+        let columns = Battlefield::cover_columns();
+        for terrain in Terrain::ALL {
+            for seed in 0..40 {
+                let field = Battlefield::generate(terrain, seed);
+                assert!(field.is_connected(), "{:?} {}\n{}", terrain, seed, field.preview());
+                for (index, cover) in field.obstacles() {
+                    assert_eq!(field.cover(Battlefield::mirror(index)), cover);
+                    assert!(columns.contains(&(index % GameState::GRID_SIZE)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_terrain_shapes_the_field() {
+        let total = |terrain: Terrain, cover: Cover| {
+            (0..20).map(|seed| count(&Battlefield::generate(terrain, seed), cover)).sum::<usize>()
+        };
+        assert!(total(Terrain::Forest, Cover::Forest) > total(Terrain::Road, Cover::Forest));
+        assert!(total(Terrain::Mountain, Cover::Rock) > total(Terrain::Forest, Cover::Rock));
+    }
+
+    #[test]
+    fn test_walled_in_pocket_is_not_connected() {
+        let mut field = Battlefield::open();
+        let pocket = 5 * GameState::GRID_SIZE + 5;
+        for next in neighbours(pocket) {
+            field.cells[next] = Cover::Rock;
+        }
+        assert!(!field.is_connected());
+        assert_eq!(field.cut_off(), Some(pocket));
+    }
+
+    #[test]
+    fn test_hand_placed_spawn_points_replace_the_zones() {
+        let mut cells = vec![Cover::Open; GameState::GRID_SIZE * GameState::GRID_SIZE];
+        cells[5] = Cover::Rock;
+        let field = Battlefield::new(cells.clone(), vec![4, 7]).unwrap();
+        assert_eq!(field.spawn_point(4), Some(Side::Player));
+        assert_eq!(field.spawn_point(7), Some(Side::Enemy));
+        assert_eq!(field.spawn_point(0), None);
+        assert_eq!(Battlefield::open().spawn_point(0), Some(Side::Player));
+        assert!(Battlefield::new(cells, vec![5]).is_err());
+    }
+}
@@ -0,0 +1,509 @@
+use crate::battle::ability::{Ability, MELEE_PENALTY};
+use crate::battle::combat_log::BattleEvent;
+use crate::battle::morale::{self, Roll};
+use crate::battle::footprint::{self, anchor_of, touching};
+use crate::battle::movement::{distance, line_of_sight};
+use crate::common::rng::GameRng;
+use crate::battle::state::Side;
+use crate::battle::status::{self, Hook};
+use crate::display::{GameState, Unit};
+use crate::display::tile::Tile;
+use crate::error::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttackOutcome {
+    pub damage: u32,
+    pub perished: u32,
+}
+
+/// Damage bonus per point of attack above the defender's defence.
+pub const ATTACK_BONUS: f32 = 0.05;
+pub const MAX_ATTACK_FACTOR: f32 = 4.0;
+/// Damage reduction per point of defence above the attacker's attack.
+pub const DEFENCE_REDUCTION: f32 = 0.025;
+pub const MIN_DEFENCE_FACTOR: f32 = 0.3;
+/// Shots over an obstacle and shots beyond half the board each deal half damage.
+pub const SHOT_PENALTY: f32 = 0.5;
+pub const LONG_RANGE: usize = GameState::GRID_SIZE / 2;
+
+/// Why a shot loses damage; both halvings apply together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShotPenalty {
+    pub obstructed: bool,
+    pub long_range: bool,
+}
+
+impl ShotPenalty {
+    pub fn factor(&self) -> f32 {
+        [self.obstructed, self.long_range]
+            .iter()
+            .filter(|applies| **applies)
+            .fold(1.0, |factor, _| factor * SHOT_PENALTY)
+    }
+
+    pub fn is_none(&self) -> bool {
+        !self.obstructed && !self.long_range
+    }
+
+    pub fn apply(&self, damage: u32) -> u32 {
+        (damage as f32 * self.factor()).round() as u32
+    }
+}
+
+/// What an attack would do before luck is rolled, for the HUD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttackPreview {
+    pub damage: u32,
+    pub perished: u32,
+    /// `Some` for a shot, with whatever halves it.
+    pub shot: Option<ShotPenalty>,
+}
+
+/// Every creature in the attacking stack strikes once, scaled by attack against defence.
+/// Shooters caught in melee deal half damage, holy stacks deal extra to the undead.
+pub fn damage_dealt(attacker: &Unit, defender: &Unit, shooting: bool) -> u32 {
+    let base = attacker.count * attacker.effective_damage();
+    let attack = attacker.effective_attack() as f32;
+    let defence = defender.effective_defence() as f32;
+    let mut factor = if attack >= defence {
+        (1.0 + (attack - defence) * ATTACK_BONUS).min(MAX_ATTACK_FACTOR)
+    } else {
+        (1.0 - (defence - attack) * DEFENCE_REDUCTION).max(MIN_DEFENCE_FACTOR)
+    };
+    if attacker.is_ranged() && !shooting {
+        factor *= MELEE_PENALTY;
+    }
+    if defender.has_ability(&Ability::Undead) {
+        for ability in &attacker.abilities {
+            if let Ability::Holy { bonus_percent } = ability {
+                factor *= 1.0 + *bonus_percent as f32 / 100.0;
+            }
+        }
+    }
+    (base as f32 * factor).round() as u32
+}
+
+pub fn resolve_attack(attacker: &Unit, defender: &mut Unit, shooting: bool) -> AttackOutcome {
+    let damage = damage_dealt(attacker, defender, shooting);
+    let perished = defender.take_damage(damage);
+    AttackOutcome { damage, perished }
+}
+
+/// True when the stack on `index` has ammo left and no enemy standing next to it.
+pub fn can_shoot(tiles: &[Tile], index: usize) -> bool {
+    let Some(tile) = tiles.get(index) else {
+        return false;
+    };
+    let Some(side) = Side::from_tile_type(&tile.tile_type) else {
+        return false;
+    };
+    tile.get_unit().is_some_and(|unit| unit.can_shoot())
+        && !footprint::adjacent_stacks(tiles, index)
+            .into_iter()
+            .any(|next| Side::from_tile_type(&tiles[next].tile_type) == Some(side.opponent()))
+}
+
+pub fn shot_penalty(tiles: &[Tile], from: usize, to: usize) -> ShotPenalty {
+    ShotPenalty {
+        obstructed: !line_of_sight(tiles, from, to),
+        long_range: distance(from, to) > LONG_RANGE,
+    }
+}
+
+/// The first strike of an attack from `attacker_index` on `defender_index`, or `None` when
+/// the attacker can't reach the target this turn.
+pub fn preview(tiles: &[Tile], attacker_index: usize, defender_index: usize) -> Option<AttackPreview> {
+    let (attacker_index, defender_index) = (anchor_of(tiles, attacker_index), anchor_of(tiles, defender_index));
+    let attacker = tiles.get(attacker_index)?.get_unit()?;
+    let mut defender = tiles.get(defender_index)?.get_unit()?.clone();
+    let shooting = !touching(tiles, attacker_index, defender_index);
+    if shooting && !can_shoot(tiles, attacker_index) {
+        return None;
+    }
+    let shot = shooting.then(|| shot_penalty(tiles, attacker_index, defender_index));
+    let damage = damage_dealt(attacker, &defender, shooting);
+    let damage = shot.map_or(damage, |penalty| penalty.apply(damage));
+    let perished = defender.take_damage(damage);
+    Some(AttackPreview { damage, perished, shot })
+}
+
+/// Runs the death hooks of the stack on `index` once it lost its last creature and clears
+/// its footprint unless one of them brought it back.
+pub fn settle_death(tiles: &mut [Tile], index: usize) -> Vec<BattleEvent> {
+    let Some(unit) = tiles.get_mut(index).and_then(|tile| tile.unit.as_mut()).filter(|unit| !unit.is_alive()) else {
+        return Vec::new();
+    };
+    let mut events = status::fire(unit, Hook::Death).events;
+    if !unit.is_alive() {
+        events.push(BattleEvent::Destroyed {
+            unit: unit.name.clone(),
+        });
+        footprint::lift(tiles, index);
+    }
+    events
+}
+
+/// The stack on `attacker_index` attacks the one on `defender_index`: a shot when they are
+/// apart, otherwise melee answered by one retaliation per round. Shots lose damage to
+/// obstacles and long range, double attackers strike again after the retaliation.
+/// Every strike rolls the striker's luck on `rng`.
+pub fn attack(
+    tiles: &mut [Tile],
+    attacker_index: usize,
+    defender_index: usize,
+    rng: &mut GameRng,
+) -> Result<Vec<BattleEvent>> {
+    let (attacker_index, defender_index) = (anchor_of(tiles, attacker_index), anchor_of(tiles, defender_index));
+    let attacker = tiles
+        .get(attacker_index)
+        .and_then(|tile| tile.get_unit().cloned())
+        .ok_or_else(|| Error::rules(format!("No attacker on tile {}", attacker_index)))?;
+    tiles
+        .get(defender_index)
+        .and_then(|tile| tile.get_unit())
+        .ok_or_else(|| Error::rules(format!("No defender on tile {}", defender_index)))?;
+    let shooting = !touching(tiles, attacker_index, defender_index);
+    if shooting && !can_shoot(tiles, attacker_index) {
+        return Err(Error::rules(format!("{} can't reach tile {}", attacker.name, defender_index)));
+    }
+
+    let shot = shooting.then(|| shot_penalty(tiles, attacker_index, defender_index));
+    let strikes = if attacker.has_ability(&Ability::DoubleAttack) { 2 } else { 1 };
+    let mut events = Vec::new();
+    for strike_number in 0..strikes {
+        if tiles[attacker_index].get_unit().is_none() || tiles[defender_index].get_unit().is_none() {
+            break;
+        }
+        if shooting {
+            let Some(shooter) = tiles[attacker_index].unit.as_mut().filter(|unit| unit.ammo > 0) else {
+                break;
+            };
+            shooter.ammo -= 1;
+        }
+        events.extend(strike(tiles, attacker_index, defender_index, shot, false, rng));
+        if strike_number == 0 && !shooting && !attacker.has_ability(&Ability::NoRetaliation) {
+            let retaliates = tiles[attacker_index].get_unit().is_some()
+                && tiles[defender_index].unit.as_mut().is_some_and(|defender| {
+                    let retaliates = !defender.retaliated;
+                    defender.retaliated = true;
+                    retaliates
+                });
+            if retaliates {
+                events.extend(strike(tiles, defender_index, attacker_index, None, true, rng));
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// One strike of the stack on `from` against the one on `to` (a shot when `shot` is set),
+/// with luck, on-hit effects and the target's on-attacked and on-death hooks.
+fn strike(
+    tiles: &mut [Tile],
+    from: usize,
+    to: usize,
+    shot: Option<ShotPenalty>,
+    retaliation: bool,
+    rng: &mut GameRng,
+) -> Vec<BattleEvent> {
+    let Some(striker) = tiles[from].get_unit().cloned() else {
+        return Vec::new();
+    };
+    let Some(target) = tiles[to].unit.as_mut() else {
+        return Vec::new();
+    };
+    let luck = morale::roll_luck(&striker, rng);
+    let mut events = match luck {
+        Roll::Good => vec![BattleEvent::Lucky {
+            unit: striker.name.clone(),
+        }],
+        Roll::Bad => vec![BattleEvent::Unlucky {
+            unit: striker.name.clone(),
+        }],
+        Roll::Neutral => Vec::new(),
+    };
+    let damage = damage_dealt(&striker, target, shot.is_some());
+    let damage = morale::apply_luck(shot.map_or(damage, |penalty| penalty.apply(damage)), luck);
+    let perished = target.take_damage(damage);
+    let (attacker, count, defender) = (striker.name.clone(), striker.count, target.name.clone());
+    events.push(if retaliation {
+        BattleEvent::Retaliated {
+            attacker,
+            count,
+            defender,
+            damage,
+            perished,
+        }
+    } else {
+        BattleEvent::Attacked {
+            attacker,
+            count,
+            defender,
+            damage,
+            perished,
+        }
+    });
+    if !target.is_alive() {
+        events.extend(settle_death(tiles, to));
+        return events;
+    }
+    for ability in &striker.abilities {
+        if let Ability::OnHit(effect) = ability {
+            target.add_effect(effect.clone());
+            events.push(BattleEvent::Affected {
+                spell: effect.name.clone(),
+                unit: target.name.clone(),
+            });
+        }
+    }
+    let hook = status::fire(target, Hook::Attacked);
+    events.extend(hook.events);
+    for (effect, damage) in hook.reflected {
+        let Some(striker) = tiles[from].unit.as_mut() else {
+            break;
+        };
+        let perished = striker.take_damage(damage);
+        events.push(BattleEvent::SpellDamage {
+            spell: effect,
+            unit: striker.name.clone(),
+            damage,
+            perished,
+        });
+        events.extend(settle_death(tiles, from));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::status::{Stat, StatusEffect, Trigger};
+    use crate::display::GameState;
+    use crate::display::tile::TileType;
+
+    fn board(attacker: Unit, defender: Unit) -> Vec<Tile> {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(attacker, TileType::MyUnit);
+        tiles[1].set_unit(defender, TileType::EnemyUnit);
+        tiles
+    }
+
+    #[test]
+    fn test_attack_kills_whole_creatures_only() {
+        let knights = Unit::new(0, "Knights", 12, 30, 3, 2);
+        let mut peasants = Unit::new(1, "Peasants", 20, 5, 1, 1);
+
+        let outcome = resolve_attack(&knights, &mut peasants, false);
+
+        assert_eq!(outcome, AttackOutcome { damage: 36, perished: 7 });
+        assert_eq!(peasants.count, 13);
+        assert_eq!(peasants.top_health, 4);
+    }
+
+    #[test]
+    fn test_wounded_top_creature_dies_first() {
+        let archers = Unit::new(0, "Archers", 1, 10, 2, 2);
+        let mut peasants = Unit::new(1, "Peasants", 2, 5, 1, 1);
+        peasants.top_health = 1;
+
+        let outcome = resolve_attack(&archers, &mut peasants, false);
+
+        assert_eq!(outcome.perished, 1);
+        assert_eq!(peasants.top_health, 4);
+    }
+
+    #[test]
+    fn test_overkill_destroys_stack() {
+        let knights = Unit::new(0, "Knights", 12, 30, 3, 2);
+        let mut peasants = Unit::new(1, "Peasants", 3, 5, 1, 1);
+
+        let outcome = resolve_attack(&knights, &mut peasants, false);
+
+        assert_eq!(outcome.perished, 3);
+        assert!(!peasants.is_alive());
+    }
+
+    #[test]
+    fn test_attack_and_defence_scale_damage() {
+        let knights = Unit::new(0, "Knights", 10, 30, 3, 2).with_skills(10, 0);
+        let peasants = Unit::new(1, "Peasants", 100, 5, 1, 1);
+        assert_eq!(damage_dealt(&knights, &peasants, false), 45);
+
+        let mut guards = peasants.with_skills(0, 18);
+        guards.add_effect(StatusEffect::defend(18));
+        assert_eq!(damage_dealt(&knights, &guards, false), 21);
+    }
+
+    #[test]
+    fn test_attacked_hook_reflects_damage() {
+        let mut defender = Unit::new(1, "Peasants", 20, 5, 1, 1);
+        defender.add_effect(StatusEffect::new("fire_shield", "Fire Shield", Some(1))
+            .with_trigger(Hook::Attacked, Trigger::DamageAttacker(30)));
+        let mut tiles = board(Unit::new(0, "Knights", 2, 30, 3, 2), defender);
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+
+        assert_eq!(tiles[0].get_unit().unwrap().count, 1);
+        assert!(matches!(events[1], BattleEvent::SpellDamage { damage: 30, perished: 1, .. }));
+    }
+
+    #[test]
+    fn test_death_clears_tile_unless_reborn() {
+        let knights = Unit::new(0, "Knights", 12, 30, 3, 2);
+        let mut tiles = board(knights.clone(), Unit::new(1, "Peasants", 3, 5, 1, 1));
+        attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+        assert!(tiles[1].get_unit().is_none());
+        assert_eq!(tiles[1].tile_type, TileType::Empty);
+
+        let mut phoenix = Unit::new(1, "Phoenixes", 3, 5, 1, 1);
+        phoenix.add_effect(StatusEffect::new("rebirth", "Rebirth", None)
+            .with_trigger(Hook::Death, Trigger::Rebirth(1)));
+        let mut tiles = board(knights, phoenix);
+        attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+        assert_eq!(tiles[1].get_unit().unwrap().count, 1);
+    }
+
+    #[test]
+    fn test_defender_retaliates_once_per_round() {
+        let mut tiles = board(Unit::new(0, "Knights", 10, 30, 3, 2), Unit::new(1, "Peasants", 40, 5, 1, 1));
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+        assert!(matches!(events[1], BattleEvent::Retaliated { damage: 34, .. }));
+        assert!(tiles[1].get_unit().unwrap().retaliated);
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_no_retaliation_strike() {
+        let paladins = Unit::new(0, "Paladins", 5, 60, 8, 3).with_abilities(vec![Ability::NoRetaliation]);
+        let mut tiles = board(paladins, Unit::new(1, "Peasants", 40, 5, 1, 1));
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(!tiles[1].get_unit().unwrap().retaliated);
+    }
+
+    #[test]
+    fn test_double_attack_strikes_after_retaliation() {
+        let royal_knights = Unit::new(0, "Royal Knights", 5, 40, 2, 3).with_abilities(vec![Ability::DoubleAttack]);
+        let mut tiles = board(royal_knights, Unit::new(1, "Peasants", 40, 5, 1, 1));
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+
+        assert!(matches!(
+            events[..],
+            [BattleEvent::Attacked { .. }, BattleEvent::Retaliated { .. }, BattleEvent::Attacked { .. }]
+        ));
+        assert_eq!(tiles[1].get_unit().unwrap().count, 36);
+    }
+
+    #[test]
+    fn test_shooter_uses_ammo_and_avoids_retaliation() {
+        let archers = Unit::new(0, "Archers", 10, 10, 2, 2).with_abilities(vec![Ability::Ranged { ammo: 1 }]);
+        let mut tiles = board(archers, Unit::new(1, "Peasants", 40, 5, 1, 1));
+        let peasants = tiles[1].unit.take().unwrap();
+        tiles[1].tile_type = TileType::Empty;
+        tiles[5].set_unit(peasants, TileType::EnemyUnit);
+
+        let events = attack(&mut tiles, 0, 5, &mut GameRng::new(0)).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(tiles[0].get_unit().unwrap().ammo, 0);
+        assert!(attack(&mut tiles, 0, 5, &mut GameRng::new(0)).is_err());
+    }
+
+    #[test]
+    fn test_blocked_shooter_fights_at_melee_penalty() {
+        let archers = Unit::new(0, "Archers", 10, 10, 2, 2).with_abilities(vec![Ability::Ranged { ammo: 12 }]);
+        let mut tiles = board(archers, Unit::new(1, "Peasants", 40, 5, 1, 1));
+        tiles[5].set_unit(Unit::new(2, "Peasants", 40, 5, 1, 1), TileType::EnemyUnit);
+
+        assert!(!can_shoot(&tiles, 0));
+        assert!(attack(&mut tiles, 0, 5, &mut GameRng::new(0)).is_err());
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+        assert!(matches!(events[0], BattleEvent::Attacked { damage: 10, .. }));
+        assert_eq!(tiles[0].get_unit().unwrap().ammo, 12);
+    }
+
+    #[test]
+    fn test_holy_bonus_against_undead() {
+        let paladins = Unit::new(0, "Paladins", 10, 60, 8, 3).with_abilities(vec![Ability::Holy { bonus_percent: 50 }]);
+        let skeletons = Unit::new(1, "Skeletons", 100, 6, 2, 2).with_abilities(vec![Ability::Undead]);
+        let peasants = Unit::new(2, "Peasants", 100, 5, 1, 1);
+
+        assert_eq!(damage_dealt(&paladins, &skeletons, false), 120);
+        assert_eq!(damage_dealt(&paladins, &peasants, false), 80);
+    }
+
+    #[test]
+    fn test_on_hit_effect_lands_on_survivor() {
+        let poison = StatusEffect::new("poison", "Poison", Some(3)).with_trigger(Hook::TurnStart, Trigger::Damage(5));
+        let alchemists = Unit::new(0, "Alchemists", 2, 20, 3, 2).with_abilities(vec![Ability::OnHit(poison)]);
+        let mut tiles = board(alchemists, Unit::new(1, "Peasants", 40, 5, 1, 1));
+
+        attack(&mut tiles, 0, 1, &mut GameRng::new(0)).unwrap();
+
+        assert!(tiles[1].get_unit().unwrap().has_effect("poison"));
+    }
+
+    #[test]
+    fn test_good_luck_doubles_the_strike() {
+        let seed = (0..).find(|&seed| GameRng::new(seed).chance(3, morale::CHANCE_DENOMINATOR)).unwrap();
+        let mut knights = Unit::new(0, "Knights", 10, 30, 3, 2).with_abilities(vec![Ability::NoRetaliation]);
+        knights.add_effect(StatusEffect::new("clover", "Clover", None).with_modifier(Stat::Luck, 3));
+        let mut tiles = board(knights, Unit::new(1, "Peasants", 100, 5, 1, 1));
+
+        let events = attack(&mut tiles, 0, 1, &mut GameRng::new(seed)).unwrap();
+
+        assert!(matches!(events[0], BattleEvent::Lucky { .. }));
+        assert!(matches!(events[1], BattleEvent::Attacked { damage: 60, .. }));
+    }
+
+    #[test]
+    fn test_obstacles_and_range_halve_shots() {
+        let archers = Unit::new(0, "Archers", 10, 10, 2, 2).with_abilities(vec![Ability::Ranged { ammo: 12 }]);
+        let mut tiles = board(archers, Unit::new(1, "Peasants", 100, 5, 1, 1));
+        let peasants = tiles[1].unit.take().unwrap();
+        tiles[1].tile_type = TileType::Obstacle;
+        tiles[3].set_unit(peasants.clone(), TileType::EnemyUnit);
+        tiles[GameState::GRID_SIZE - 1].set_unit(Unit { id: 2, ..peasants }, TileType::EnemyUnit);
+
+        let over_wall = preview(&tiles, 0, 3).unwrap();
+        assert_eq!(over_wall.damage, 10);
+        assert!(over_wall.shot.is_some_and(|penalty| penalty.obstructed && !penalty.long_range));
+        assert_eq!(preview(&tiles, 0, GameState::GRID_SIZE - 1).unwrap().damage, 5);
+
+        let events = attack(&mut tiles, 0, 3, &mut GameRng::new(0)).unwrap();
+        assert!(matches!(events[0], BattleEvent::Attacked { damage: 10, .. }));
+    }
+
+    #[test]
+    fn test_preview_needs_reach() {
+        let tiles = board(Unit::new(0, "Knights", 10, 30, 3, 2), Unit::new(1, "Peasants", 20, 5, 1, 1));
+        let strike = preview(&tiles, 0, 1).unwrap();
+        assert_eq!((strike.damage, strike.perished, strike.shot), (30, 6, None));
+
+        let mut tiles = tiles;
+        let peasants = tiles[1].unit.take().unwrap();
+        tiles[5].set_unit(peasants, TileType::EnemyUnit);
+        assert!(preview(&tiles, 0, 5).is_none());
+    }
+
+    #[test]
+    fn test_large_stack_is_struck_through_any_cell_and_dies_whole() {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(Unit::new(0, "Knights", 20, 30, 3, 2), TileType::MyUnit);
+        let angel = Unit::new(1, "Angels", 1, 50, 15, 4).with_size(2);
+        footprint::place(&mut tiles, GameState::GRID_SIZE + 1, angel, TileType::EnemyUnit);
+
+        let events = attack(&mut tiles, 0, GameState::GRID_SIZE + 2, &mut GameRng::new(0)).unwrap();
+
+        assert!(matches!(events[1], BattleEvent::Destroyed { .. }));
+        assert!(tiles[GameState::GRID_SIZE + 1].get_unit().is_none());
+        assert_eq!(tiles[GameState::GRID_SIZE + 2].tile_type, TileType::Empty);
+        assert_eq!(tiles[GameState::GRID_SIZE + 2].anchor, None);
+    }
+}
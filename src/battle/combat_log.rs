@@ -0,0 +1,387 @@
+use crate::common::locale::Locale;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BattleEvent {
+    Moved {
+        unit: String,
+        count: u32,
+        to: (usize, usize),
+    },
+    Attacked {
+        attacker: String,
+        count: u32,
+        defender: String,
+        damage: u32,
+        perished: u32,
+    },
+    Retaliated {
+        attacker: String,
+        count: u32,
+        defender: String,
+        damage: u32,
+        perished: u32,
+    },
+    Defended {
+        unit: String,
+        count: u32,
+    },
+    Waited {
+        unit: String,
+        count: u32,
+    },
+    Destroyed {
+        unit: String,
+    },
+    BattleEnded {
+        victory: bool,
+    },
+    RoundStarted {
+        round: u32,
+    },
+    SpellCast {
+        spell: String,
+    },
+    SpellDamage {
+        spell: String,
+        unit: String,
+        damage: u32,
+        perished: u32,
+    },
+    Healed {
+        unit: String,
+        amount: u32,
+    },
+    Affected {
+        spell: String,
+        unit: String,
+    },
+    Summoned {
+        unit: String,
+        count: u32,
+    },
+    EffectExpired {
+        effect: String,
+        unit: String,
+    },
+    Reborn {
+        unit: String,
+        count: u32,
+    },
+    Inspired {
+        unit: String,
+        count: u32,
+    },
+    Frozen {
+        unit: String,
+        count: u32,
+    },
+    Lucky {
+        unit: String,
+    },
+    Unlucky {
+        unit: String,
+    },
+    WallHit {
+        attacker: String,
+        at: (usize, usize),
+        damage: u32,
+    },
+    WallBreached {
+        at: (usize, usize),
+        gate: bool,
+    },
+}
+
+impl BattleEvent {
+    /// The log line in the language of `locale`; `Display` keeps the English one for exports.
+    pub fn localize(&self, locale: &Locale) -> String {
+        let damage = |damage: u32| locale.plural("combat.damage", damage, &[]);
+        let perished = |perished: u32| locale.plural("combat.perished", perished, &[]);
+        let stack = |unit: &String, count: &u32| vec![("unit", unit.clone()), ("count", count.to_string())];
+        let strike = |attacker: &String, count: &u32, defender: &String, dealt: u32, lost: u32| {
+            vec![
+                ("attacker", attacker.clone()),
+                ("count", count.to_string()),
+                ("defender", defender.clone()),
+                ("damage", damage(dealt)),
+                ("perished", perished(lost)),
+            ]
+        };
+        let (key, args): (&str, Vec<(&str, String)>) = match self {
+            BattleEvent::Moved { unit, count, to } => (
+                "combat.moved",
+                vec![
+                    ("unit", unit.clone()),
+                    ("count", count.to_string()),
+                    ("row", to.0.to_string()),
+                    ("col", to.1.to_string()),
+                ],
+            ),
+            BattleEvent::Attacked {
+                attacker,
+                count,
+                defender,
+                damage: dealt,
+                perished: lost,
+            } => ("combat.attacked", strike(attacker, count, defender, *dealt, *lost)),
+            BattleEvent::Retaliated {
+                attacker,
+                count,
+                defender,
+                damage: dealt,
+                perished: lost,
+            } => ("combat.retaliated", strike(attacker, count, defender, *dealt, *lost)),
+            BattleEvent::Defended { unit, count } => ("combat.defended", stack(unit, count)),
+            BattleEvent::Waited { unit, count } => ("combat.waited", stack(unit, count)),
+            BattleEvent::Destroyed { unit } => ("combat.destroyed", vec![("unit", unit.clone())]),
+            BattleEvent::BattleEnded { victory: true } => ("combat.victory", vec![]),
+            BattleEvent::BattleEnded { victory: false } => ("combat.defeat", vec![]),
+            BattleEvent::RoundStarted { round } => ("combat.round", vec![("round", round.to_string())]),
+            BattleEvent::SpellCast { spell } => ("combat.spell_cast", vec![("spell", spell.clone())]),
+            BattleEvent::SpellDamage {
+                spell,
+                unit,
+                damage: dealt,
+                perished: lost,
+            } => (
+                "combat.spell_damage",
+                vec![
+                    ("spell", spell.clone()),
+                    ("unit", unit.clone()),
+                    ("damage", damage(*dealt)),
+                    ("perished", perished(*lost)),
+                ],
+            ),
+            BattleEvent::Healed { unit, amount } => {
+                ("combat.healed", vec![("unit", unit.clone()), ("amount", amount.to_string())])
+            }
+            BattleEvent::Affected { spell, unit } => {
+                ("combat.affected", vec![("spell", spell.clone()), ("unit", unit.clone())])
+            }
+            BattleEvent::Summoned { unit, count } => ("combat.summoned", stack(unit, count)),
+            BattleEvent::EffectExpired { effect, unit } => {
+                ("combat.expired", vec![("effect", effect.clone()), ("unit", unit.clone())])
+            }
+            BattleEvent::Reborn { unit, count } => ("combat.reborn", stack(unit, count)),
+            BattleEvent::Inspired { unit, count } => ("combat.inspired", stack(unit, count)),
+            BattleEvent::Frozen { unit, count } => ("combat.frozen", stack(unit, count)),
+            BattleEvent::Lucky { unit } => ("combat.lucky", vec![("unit", unit.clone())]),
+            BattleEvent::Unlucky { unit } => ("combat.unlucky", vec![("unit", unit.clone())]),
+            BattleEvent::WallHit { attacker, at, damage: dealt } => (
+                "combat.wall_hit",
+                vec![
+                    ("attacker", attacker.clone()),
+                    ("row", at.0.to_string()),
+                    ("col", at.1.to_string()),
+                    ("damage", damage(*dealt)),
+                ],
+            ),
+            BattleEvent::WallBreached { at, gate } => (
+                if *gate { "combat.gate_breached" } else { "combat.wall_breached" },
+                vec![("row", at.0.to_string()), ("col", at.1.to_string())],
+            ),
+        };
+        locale.format(key, &args)
+    }
+}
+
+impl fmt::Display for BattleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BattleEvent::Moved { unit, count, to } => {
+                write!(f, "{} ({}) move to {}, {}", unit, count, to.0, to.1)
+            }
+            BattleEvent::Attacked {
+                attacker,
+                count,
+                defender,
+                damage,
+                perished,
+            } => write!(
+                f,
+                "{} ({}) attack {}, {} damage, {} perish",
+                attacker, count, defender, damage, perished
+            ),
+            BattleEvent::Retaliated {
+                attacker,
+                count,
+                defender,
+                damage,
+                perished,
+            } => write!(
+                f,
+                "{} ({}) retaliate against {}, {} damage, {} perish",
+                attacker, count, defender, damage, perished
+            ),
+            BattleEvent::Defended { unit, count } => write!(f, "{} ({}) defend", unit, count),
+            BattleEvent::Waited { unit, count } => write!(f, "{} ({}) wait", unit, count),
+            BattleEvent::Destroyed { unit } => write!(f, "{} are destroyed", unit),
+            BattleEvent::BattleEnded { victory: true } => write!(f, "Victory!"),
+            BattleEvent::BattleEnded { victory: false } => write!(f, "Defeat!"),
+            BattleEvent::RoundStarted { round } => write!(f, "Round {}", round),
+            BattleEvent::SpellCast { spell } => write!(f, "{} is cast", spell),
+            BattleEvent::SpellDamage {
+                spell,
+                unit,
+                damage,
+                perished,
+            } => write!(f, "{} deals {} damage to {}, {} perish", spell, damage, unit, perished),
+            BattleEvent::Healed { unit, amount } => write!(f, "{} healed for {}", unit, amount),
+            BattleEvent::Affected { spell, unit } => write!(f, "{} affects {}", spell, unit),
+            BattleEvent::Summoned { unit, count } => write!(f, "{} {} are summoned", count, unit),
+            BattleEvent::EffectExpired { effect, unit } => write!(f, "{} wears off {}", effect, unit),
+            BattleEvent::Reborn { unit, count } => write!(f, "{} {} rise again", count, unit),
+            BattleEvent::Inspired { unit, count } => write!(f, "{} ({}) are inspired and act again", unit, count),
+            BattleEvent::Frozen { unit, count } => write!(f, "{} ({}) freeze in fear", unit, count),
+            BattleEvent::Lucky { unit } => write!(f, "Luck smiles on {}: double damage", unit),
+            BattleEvent::Unlucky { unit } => write!(f, "Bad luck haunts {}: half damage", unit),
+            BattleEvent::WallHit { attacker, at, damage } => {
+                write!(f, "{} batter the wall at {}, {}: {} damage", attacker, at.0, at.1, damage)
+            }
+            BattleEvent::WallBreached { at, gate: true } => write!(f, "The gate at {}, {} is broken", at.0, at.1),
+            BattleEvent::WallBreached { at, gate: false } => write!(f, "The wall at {}, {} crumbles", at.0, at.1),
+        }
+    }
+}
+
+/// Battle events in the order they happened, plus how far the panel is scrolled back.
+#[derive(Clone, Debug, Default)]
+pub struct CombatLog {
+    events: Vec<BattleEvent>,
+    scroll: usize,
+}
+
+impl CombatLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an event; a panel scrolled back in history stays on the lines it shows.
+    pub fn push(&mut self, event: BattleEvent) {
+        self.events.push(event);
+        if self.scroll > 0 {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn events(&self) -> &[BattleEvent] {
+        &self.events
+    }
+
+    /// Positive `lines` scrolls back towards older events, negative towards the newest.
+    pub fn scroll_by(&mut self, lines: isize, rows: usize) {
+        let max_scroll = self.events.len().saturating_sub(rows);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max_scroll);
+    }
+
+    /// The `rows` events the panel shows, oldest first.
+    pub fn visible(&self, rows: usize) -> &[BattleEvent] {
+        let end = self.events.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(rows);
+        &self.events[start..end]
+    }
+
+    pub fn export(&self) -> String {
+        self.events
+            .iter()
+            .enumerate()
+            .map(|(number, event)| format!("{:>4}. {}\n", number + 1, event))
+            .collect()
+    }
+
+    pub fn export_to(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.export())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::settings::Language;
+
+    fn waited(count: u32) -> BattleEvent {
+        BattleEvent::Waited {
+            unit: "Peasants".to_string(),
+            count,
+        }
+    }
+
+    fn log_with(events: u32) -> CombatLog {
+        let mut log = CombatLog::new();
+        for count in 0..events {
+            log.push(waited(count));
+        }
+        log
+    }
+
+    #[test]
+    fn test_attack_message() {
+        let event = BattleEvent::Attacked {
+            attacker: "Knights".to_string(),
+            count: 12,
+            defender: "Peasants".to_string(),
+            damage: 37,
+            perished: 7,
+        };
+        assert_eq!(event.to_string(), "Knights (12) attack Peasants, 37 damage, 7 perish");
+    }
+
+    #[test]
+    fn test_localized_messages() {
+        let mut locale = Locale::load(Locale::PATH, Language::Polish).unwrap();
+        let event = BattleEvent::SpellDamage {
+            spell: "Lightning Bolt".to_string(),
+            unit: "Peasants".to_string(),
+            damage: 22,
+            perished: 7,
+        };
+        assert_eq!(
+            event.localize(&locale),
+            "Lightning Bolt zadaje Peasants 22 obrażenia, 7 jednostek zginęło"
+        );
+        locale.set_language(Language::English);
+        assert_eq!(event.localize(&locale), event.to_string());
+    }
+
+    #[test]
+    fn test_visible_follows_newest() {
+        let log = log_with(10);
+        assert_eq!(log.visible(3), &[waited(7), waited(8), waited(9)]);
+    }
+
+    #[test]
+    fn test_scroll_is_clamped() {
+        let mut log = log_with(10);
+        log.scroll_by(100, 3);
+        assert_eq!(log.visible(3), &[waited(0), waited(1), waited(2)]);
+        log.scroll_by(-100, 3);
+        assert_eq!(log.visible(3), &[waited(7), waited(8), waited(9)]);
+    }
+
+    #[test]
+    fn test_scrolled_panel_keeps_position_on_push() {
+        let mut log = log_with(10);
+        log.scroll_by(2, 3);
+        log.push(waited(10));
+        assert_eq!(log.visible(3), &[waited(5), waited(6), waited(7)]);
+    }
+
+    #[test]
+    fn test_export_numbers_every_event() {
+        let log = log_with(2);
+        assert_eq!(log.export(), "   1. Peasants (0) wait\n   2. Peasants (1) wait\n");
+    }
+}
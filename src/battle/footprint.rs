@@ -0,0 +1,152 @@
+use crate::battle::movement::{is_adjacent, is_free, neighbours};
+use crate::display::GameState;
+use crate::display::tile::{Tile, TileType, Unit};
+
+/// Cells a stack of `size` covers when anchored on `anchor`: the anchor and the ones to its
+/// right. `None` when the stack would stick out of the board.
+pub fn cells(anchor: usize, size: usize) -> Option<Vec<usize>> {
+    let col = anchor % GameState::GRID_SIZE;
+    if anchor >= GameState::GRID_SIZE * GameState::GRID_SIZE || col + size > GameState::GRID_SIZE {
+        return None;
+    }
+    Some((anchor..anchor + size.max(1)).collect())
+}
+
+/// The tile holding the stack that covers `index`; `index` itself for anything else.
+pub fn anchor_of(tiles: &[Tile], index: usize) -> usize {
+    tiles.get(index).and_then(|tile| tile.anchor).unwrap_or(index)
+}
+
+/// Cells covered by the stack anchored on `anchor`.
+pub fn occupied(tiles: &[Tile], anchor: usize) -> Vec<usize> {
+    let size = tiles.get(anchor).and_then(|tile| tile.get_unit()).map_or(1, |unit| unit.size);
+    cells(anchor, size).unwrap_or_else(|| vec![anchor])
+}
+
+/// True when a stack of `size` anchored on `anchor` would cover only free cells or cells
+/// already held by the stack `unit_id`.
+pub fn fits(tiles: &[Tile], anchor: usize, size: usize, unit_id: Option<usize>) -> bool {
+    let Some(cells) = cells(anchor, size) else {
+        return false;
+    };
+    cells.into_iter().all(|index| {
+        let owner = tiles.get(anchor_of(tiles, index)).and_then(|tile| tile.get_unit());
+        tiles.get(index).is_some_and(is_free) || owner.is_some_and(|unit| Some(unit.id) == unit_id)
+    })
+}
+
+/// Puts `unit` on `anchor` and marks the rest of its footprint as pointing back to it.
+/// Callers check `fits` first.
+pub fn place(tiles: &mut [Tile], anchor: usize, unit: Unit, tile_type: TileType) {
+    let cells = cells(anchor, unit.size).unwrap_or_else(|| vec![anchor]);
+    for &index in cells.iter().skip(1) {
+        tiles[index].set_part_of(anchor, tile_type.clone());
+    }
+    tiles[anchor].set_unit(unit, tile_type);
+}
+
+/// Takes the stack off `anchor` and leaves its whole footprint as the ground it stood on.
+pub fn lift(tiles: &mut [Tile], anchor: usize) -> Option<Unit> {
+    let cells = occupied(tiles, anchor);
+    tiles.get(anchor)?.get_unit()?;
+    for &index in cells.iter().skip(1) {
+        tiles[index].clear();
+    }
+    tiles[anchor].clear()
+}
+
+/// Moves the stack on `from` to `to`, keeping its side, and returns it where it now stands.
+pub fn relocate(tiles: &mut [Tile], from: usize, to: usize) -> Option<&Unit> {
+    let tile_type = tiles.get(from)?.tile_type.clone();
+    let unit = lift(tiles, from)?;
+    place(tiles, to, unit, tile_type);
+    tiles[to].get_unit()
+}
+
+/// True when any cell of one stack touches any cell of the other.
+pub fn touching(tiles: &[Tile], first: usize, second: usize) -> bool {
+    let (first, second) = (occupied(tiles, first), occupied(tiles, second));
+    first.iter().any(|a| second.iter().any(|b| is_adjacent(*a, *b)))
+}
+
+/// Anchors of the stacks touching the stack on `anchor`.
+pub fn adjacent_stacks(tiles: &[Tile], anchor: usize) -> Vec<usize> {
+    let own = occupied(tiles, anchor);
+    let mut stacks: Vec<usize> = own
+        .iter()
+        .flat_map(|&index| neighbours(index))
+        .filter(|index| !own.contains(index))
+        .map(|index| anchor_of(tiles, index))
+        .filter(|&index| tiles[index].get_unit().is_some())
+        .collect();
+    stacks.sort_unstable();
+    stacks.dedup();
+    stacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knights() -> Unit {
+        Unit::new(0, "Royal Knights", 5, 40, 5, 3).with_size(2)
+    }
+
+    #[test]
+    fn test_footprint_stays_on_the_board() {
+        assert_eq!(cells(0, 2), Some(vec![0, 1]));
+        assert_eq!(cells(GameState::GRID_SIZE - 1, 2), None);
+        assert_eq!(cells(GameState::GRID_SIZE - 1, 1), Some(vec![GameState::GRID_SIZE - 1]));
+    }
+
+    #[test]
+    fn test_every_cell_points_back_to_the_stack() {
+        let mut tiles = Tile::board_for_test();
+        place(&mut tiles, 0, knights(), TileType::MyUnit);
+
+        assert_eq!(anchor_of(&tiles, 1), 0);
+        assert_eq!(tiles[1].tile_type, TileType::MyUnit);
+        assert!(tiles[1].get_unit().is_none());
+        assert_eq!(occupied(&tiles, 0), vec![0, 1]);
+
+        let unit = lift(&mut tiles, 0).unwrap();
+        assert_eq!(unit.size, 2);
+        assert_eq!((tiles[1].anchor, &tiles[1].tile_type), (None, &TileType::Empty));
+    }
+
+    #[test]
+    fn test_lifted_stack_leaves_the_spawn_points_it_stood_on() {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].tile_type = TileType::SpawnPoint;
+        tiles[1].tile_type = TileType::SpawnPoint;
+        place(&mut tiles, 0, knights(), TileType::MyUnit);
+        let unit = lift(&mut tiles, 0).unwrap();
+        place(&mut tiles, 1, unit, TileType::MyUnit);
+
+        assert_eq!(tiles[0].tile_type, TileType::SpawnPoint);
+        lift(&mut tiles, 1).unwrap();
+        assert_eq!((&tiles[1].tile_type, &tiles[2].tile_type), (&TileType::SpawnPoint, &TileType::Empty));
+    }
+
+    #[test]
+    fn test_fits_checks_the_whole_footprint() {
+        let mut tiles = Tile::board_for_test();
+        place(&mut tiles, 0, knights(), TileType::MyUnit);
+        tiles[4].tile_type = TileType::Obstacle;
+
+        assert!(fits(&tiles, 1, 2, Some(0)));
+        assert!(!fits(&tiles, 1, 2, Some(7)));
+        assert!(!fits(&tiles, 3, 2, None));
+        assert!(fits(&tiles, 5, 2, None));
+    }
+
+    #[test]
+    fn test_far_cell_touches_neighbours() {
+        let mut tiles = Tile::board_for_test();
+        place(&mut tiles, 0, knights(), TileType::MyUnit);
+        tiles[2].set_unit(Unit::new(1, "Peasants", 10, 5, 1, 1), TileType::EnemyUnit);
+
+        assert!(touching(&tiles, 0, 2));
+        assert_eq!(adjacent_stacks(&tiles, 2), vec![0]);
+    }
+}
@@ -0,0 +1,30 @@
+pub mod ability;
+pub mod army;
+pub mod battlefield;
+pub mod combat;
+pub mod combat_log;
+pub mod footprint;
+pub mod morale;
+pub mod movement;
+pub mod result;
+pub mod roster;
+pub mod save;
+pub mod scenario;
+pub mod siege;
+pub mod spell;
+pub mod state;
+pub mod status;
+
+pub use ability::Ability;
+pub use army::Army;
+pub use battlefield::{Battlefield, Cover};
+pub use combat::{AttackOutcome, resolve_attack};
+pub use combat_log::{BattleEvent, CombatLog};
+pub use result::BattleResult;
+pub use roster::{Roster, UnitDefinition};
+pub use save::BattleSave;
+pub use scenario::{PlacedStack, Scenario};
+pub use siege::Siege;
+pub use spell::{Spell, Spellbook};
+pub use state::{BattleState, Phase, Side};
+pub use status::{Hook, Stat, StatusEffect, Trigger};
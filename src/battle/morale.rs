@@ -0,0 +1,96 @@
+use crate::common::rng::GameRng;
+use crate::display::tile::Unit;
+
+/// Morale and luck count from -3 to +3.
+pub const MAX_POINTS: i32 = 3;
+/// Each point of morale or luck is a 1 in 24 chance.
+pub const CHANCE_DENOMINATOR: u32 = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Roll {
+    Neutral,
+    /// High morale: the stack acts again. Good luck: double damage.
+    Good,
+    /// Low morale: the stack freezes for the round. Bad luck: half damage.
+    Bad,
+}
+
+/// Positive `points` may roll `Good`, negative ones `Bad`; zero never draws from `rng`.
+pub fn roll(points: i32, rng: &mut GameRng) -> Roll {
+    let points = points.clamp(-MAX_POINTS, MAX_POINTS);
+    if points == 0 || !rng.chance(points.unsigned_abs(), CHANCE_DENOMINATOR) {
+        Roll::Neutral
+    } else if points > 0 {
+        Roll::Good
+    } else {
+        Roll::Bad
+    }
+}
+
+pub fn roll_morale(unit: &Unit, rng: &mut GameRng) -> Roll {
+    roll(unit.effective_morale(), rng)
+}
+
+pub fn roll_luck(unit: &Unit, rng: &mut GameRng) -> Roll {
+    roll(unit.effective_luck(), rng)
+}
+
+/// Damage after a luck roll; bad luck still deals at least one point.
+pub fn apply_luck(damage: u32, luck: Roll) -> u32 {
+    match luck {
+        Roll::Neutral => damage,
+        Roll::Good => damage * 2,
+        Roll::Bad => damage.div_ceil(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::ability::Ability;
+    use crate::battle::status::{Stat, StatusEffect};
+
+    #[test]
+    fn test_neutral_morale_never_rolls() {
+        let mut rng = GameRng::new(1);
+        let before = rng.clone();
+        assert_eq!(roll(0, &mut rng), Roll::Neutral);
+        assert_eq!(rng, before);
+    }
+
+    #[test]
+    fn test_rolls_follow_sign_and_frequency() {
+        let mut rng = GameRng::new(2024);
+        let good = (0..2400).filter(|_| roll(3, &mut rng) == Roll::Good).count();
+        assert!((200..400).contains(&good), "{} good rolls", good);
+        assert!((0..100).all(|_| roll(-3, &mut rng) != Roll::Good));
+    }
+
+    #[test]
+    fn test_same_seed_same_rolls() {
+        let rolls = |seed| {
+            let mut rng = GameRng::new(seed);
+            (0..50).map(|_| roll(2, &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(9), rolls(9));
+    }
+
+    #[test]
+    fn test_undead_ignore_morale_and_points_are_capped() {
+        let zeal = StatusEffect::new("zeal", "Zeal", None).with_modifier(Stat::Morale, 5);
+        let mut knights = Unit::new(0, "Knights", 10, 30, 3, 2);
+        knights.add_effect(zeal.clone());
+        let mut ghosts = Unit::new(1, "Ghosts", 10, 15, 2, 3).with_abilities(vec![Ability::Undead]);
+        ghosts.add_effect(zeal);
+
+        assert_eq!(knights.effective_morale(), MAX_POINTS);
+        assert_eq!(ghosts.effective_morale(), 0);
+    }
+
+    #[test]
+    fn test_luck_scales_damage() {
+        assert_eq!(apply_luck(15, Roll::Good), 30);
+        assert_eq!(apply_luck(15, Roll::Bad), 8);
+        assert_eq!(apply_luck(15, Roll::Neutral), 15);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::battle::footprint;
+use crate::display::GameState;
+use crate::display::tile::{Tile, TileType};
+use std::collections::{HashSet, VecDeque};
+
+pub fn is_adjacent(first: usize, second: usize) -> bool {
+    let (first_row, first_col) = (first / GameState::GRID_SIZE, first % GameState::GRID_SIZE);
+    let (second_row, second_col) = (second / GameState::GRID_SIZE, second % GameState::GRID_SIZE);
+    first != second && first_row.abs_diff(second_row) <= 1 && first_col.abs_diff(second_col) <= 1
+}
+
+/// Indices of the up to eight tiles touching `index`.
+pub fn neighbours(index: usize) -> impl Iterator<Item = usize> {
+    let size = GameState::GRID_SIZE as isize;
+    let (row, col) = ((index as isize) / size, (index as isize) % size);
+    (-1..=1)
+        .flat_map(move |d_row| (-1..=1).map(move |d_col| (row + d_row, col + d_col)))
+        .filter(move |&(r, c)| (r, c) != (row, col) && (0..size).contains(&r) && (0..size).contains(&c))
+        .map(move |(r, c)| (r * size + c) as usize)
+}
+
+/// Steps a king would need between two tiles.
+pub fn distance(first: usize, second: usize) -> usize {
+    let (first_row, first_col) = (first / GameState::GRID_SIZE, first % GameState::GRID_SIZE);
+    let (second_row, second_col) = (second / GameState::GRID_SIZE, second % GameState::GRID_SIZE);
+    first_row.abs_diff(second_row).max(first_col.abs_diff(second_col))
+}
+
+/// Tiles a straight line from `from` to `to` crosses, both ends excluded (Bresenham).
+pub fn line_between(from: usize, to: usize) -> Vec<usize> {
+    let size = GameState::GRID_SIZE as isize;
+    let (mut row, mut col) = ((from as isize) / size, (from as isize) % size);
+    let (to_row, to_col) = ((to as isize) / size, (to as isize) % size);
+    let (d_row, d_col) = ((to_row - row).abs(), (to_col - col).abs());
+    let (step_row, step_col) = ((to_row - row).signum(), (to_col - col).signum());
+    let mut error = d_col - d_row;
+    let mut crossed = Vec::new();
+    while (row, col) != (to_row, to_col) {
+        let doubled = 2 * error;
+        if doubled > -d_row {
+            error -= d_row;
+            col += step_col;
+        }
+        if doubled < d_col {
+            error += d_col;
+            row += step_row;
+        }
+        if (row, col) != (to_row, to_col) {
+            crossed.push((row * size + col) as usize);
+        }
+    }
+    crossed
+}
+
+/// True when no obstacle or wall stands between the two tiles; stacks don't block the view.
+pub fn line_of_sight(tiles: &[Tile], from: usize, to: usize) -> bool {
+    line_between(from, to).into_iter().all(|index| {
+        tiles
+            .get(index)
+            .is_some_and(|tile| !matches!(tile.tile_type, TileType::Obstacle | TileType::Wall | TileType::Gate))
+    })
+}
+
+pub(crate) fn is_free(tile: &Tile) -> bool {
+    tile.get_unit().is_none() && matches!(tile.tile_type, TileType::Empty | TileType::SpawnPoint)
+}
+
+/// Anchor tiles a stack on `from` can end its move on, its whole footprint free. Walkers go
+/// around obstacles and other stacks one step at a time; flyers land anywhere within `range`.
+/// Defenders of a besieged town walk through the gate, though never stop in it.
+pub fn reachable(tiles: &[Tile], from: usize, range: usize, flying: bool) -> HashSet<usize> {
+    let unit = tiles.get(from).and_then(|tile| tile.get_unit());
+    let (size, unit_id) = (unit.map_or(1, |unit| unit.size), unit.map(|unit| unit.id));
+    let fits = |index: usize| footprint::fits(tiles, index, size, unit_id);
+    let defender = tiles.get(from).is_some_and(|tile| tile.tile_type == TileType::EnemyUnit);
+    let through_gate = |index: usize| defender && tiles.get(index).is_some_and(|tile| tile.tile_type == TileType::Gate);
+    if flying {
+        return (0..tiles.len())
+            .filter(|&index| index != from && distance(from, index) <= range)
+            .filter(|&index| fits(index))
+            .collect();
+    }
+    let mut reached = HashSet::new();
+    let mut passed = HashSet::new();
+    let mut queue = VecDeque::from([(from, 0)]);
+    while let Some((index, steps)) = queue.pop_front() {
+        if steps == range {
+            continue;
+        }
+        for next in neighbours(index) {
+            if next == from {
+                continue;
+            }
+            let step = if fits(next) { reached.insert(next) } else { through_gate(next) && passed.insert(next) };
+            if step {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tile::Unit;
+
+    fn walled_board() -> Vec<Tile> {
+        let mut tiles = Tile::board_for_test();
+        for row in 0..3 {
+            tiles[row * GameState::GRID_SIZE + 1].tile_type = TileType::Obstacle;
+        }
+        tiles
+    }
+
+    #[test]
+    fn test_neighbours_of_corner() {
+        let mut around: Vec<usize> = neighbours(0).collect();
+        around.sort();
+        assert_eq!(around, vec![1, GameState::GRID_SIZE, GameState::GRID_SIZE + 1]);
+    }
+
+    #[test]
+    fn test_line_between_skips_ends() {
+        assert_eq!(line_between(0, 3), vec![1, 2]);
+        assert_eq!(line_between(0, 2 * GameState::GRID_SIZE + 2), vec![GameState::GRID_SIZE + 1]);
+        assert!(line_between(0, 1).is_empty());
+        assert_eq!(distance(0, 2 * GameState::GRID_SIZE + 5), 5);
+    }
+
+    #[test]
+    fn test_obstacles_block_line_of_sight() {
+        let tiles = walled_board();
+        assert!(!line_of_sight(&tiles, 0, 3));
+        assert!(line_of_sight(&tiles, 3 * GameState::GRID_SIZE, 3 * GameState::GRID_SIZE + 4));
+    }
+
+    #[test]
+    fn test_walker_goes_around_obstacles() {
+        let tiles = walled_board();
+        let reached = reachable(&tiles, 0, 2, false);
+        assert!(!reached.contains(&1));
+        assert!(!reached.contains(&2));
+        assert!(reached.contains(&(2 * GameState::GRID_SIZE)));
+    }
+
+    #[test]
+    fn test_flyer_ignores_obstacles() {
+        let tiles = walled_board();
+        let reached = reachable(&tiles, 0, 2, true);
+        assert!(!reached.contains(&1));
+        assert!(reached.contains(&2));
+    }
+
+    #[test]
+    fn test_only_defenders_pass_the_gate() {
+        let mut tiles = Tile::board_for_test();
+        for index in 0..GameState::GRID_SIZE {
+            tiles[index * GameState::GRID_SIZE + 5].tile_type = TileType::Wall;
+        }
+        let gate = 6 * GameState::GRID_SIZE + 5;
+        tiles[gate].tile_type = TileType::Gate;
+        tiles[gate - 1].set_unit(Unit::new(0, "Knights", 5, 30, 3, 2), TileType::MyUnit);
+        tiles[gate + 1].set_unit(Unit::new(1, "Pikemen", 5, 10, 2, 2), TileType::EnemyUnit);
+
+        let attackers = reachable(&tiles, gate - 1, 2, false);
+        assert!(!attackers.contains(&gate) && !attackers.contains(&(gate + 1)));
+        assert!(attackers.iter().all(|index| index % GameState::GRID_SIZE < 5));
+
+        let defenders = reachable(&tiles, gate + 1, 2, false);
+        assert!(!defenders.contains(&gate));
+        assert!(!defenders.contains(&(gate - 1)));
+        assert!(defenders.contains(&(gate - GameState::GRID_SIZE - 1)));
+        assert!(!line_of_sight(&tiles, gate - 1, gate + 1));
+    }
+
+    #[test]
+    fn test_large_stack_needs_room_for_its_footprint() {
+        let mut tiles = walled_board();
+        let row = 3 * GameState::GRID_SIZE;
+        footprint::place(&mut tiles, row, Unit::new(0, "Royal Knights", 5, 40, 5, 2).with_size(2), TileType::MyUnit);
+
+        let reached = reachable(&tiles, row, 2, false);
+        assert!(reached.contains(&(row + 1)));
+        assert!(reached.contains(&(row + GameState::GRID_SIZE)));
+        // The tile above is free, but the wall stands where the second cell would go.
+        assert!(is_free(&tiles[row - GameState::GRID_SIZE]));
+        assert!(!reached.contains(&(row - GameState::GRID_SIZE)));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::battle::state::Side;
+use crate::display::tile::{Tile, Unit};
+
+/// What a finished battle hands back to the campaign: who won, what it cost and
+/// the experience the winning hero earned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BattleResult {
+    pub victory: bool,
+    /// Creatures each side lost, indexed like `Side`: player first.
+    pub losses: [u32; 2],
+    /// Total health of the enemy creatures slain; only a victory pays it out.
+    pub experience: u32,
+    /// Creatures left in every deployed stack, in deployment order.
+    pub remaining: Vec<(Side, u32)>,
+}
+
+impl BattleResult {
+    /// Compares the stacks each side deployed with what is left on `tiles`.
+    pub fn from_tiles(victory: bool, deployed: &[(Side, Unit)], tiles: &[Tile]) -> Self {
+        let mut result = Self {
+            victory,
+            ..Self::default()
+        };
+        for (side, unit) in deployed {
+            let left = tiles
+                .iter()
+                .filter_map(|tile| tile.get_unit())
+                .find(|survivor| survivor.id == unit.id)
+                .map_or(0, |survivor| survivor.count);
+            let lost = unit.count.saturating_sub(left);
+            result.remaining.push((*side, left));
+            match side {
+                Side::Player => result.losses[0] += lost,
+                Side::Enemy => {
+                    result.losses[1] += lost;
+                    result.experience += lost * unit.health;
+                }
+            }
+        }
+        if !victory {
+            result.experience = 0;
+        }
+        result
+    }
+
+    pub fn losses(&self, side: Side) -> u32 {
+        match side {
+            Side::Player => self.losses[0],
+            Side::Enemy => self.losses[1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tile::TileType;
+
+    #[test]
+    fn test_losses_and_experience() {
+        let knights = Unit::new(0, "Knights", 10, 30, 3, 2);
+        let peasants = Unit::new(1, "Peasants", 40, 5, 1, 1);
+        let deployed = vec![(Side::Player, knights.clone()), (Side::Enemy, peasants)];
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(Unit { count: 7, ..knights }, TileType::MyUnit);
+
+        let result = BattleResult::from_tiles(true, &deployed, &tiles);
+
+        assert_eq!((result.losses(Side::Player), result.losses(Side::Enemy)), (3, 40));
+        assert_eq!(result.experience, 200);
+        assert_eq!(result.remaining, vec![(Side::Player, 7), (Side::Enemy, 0)]);
+        assert_eq!(BattleResult::from_tiles(false, &deployed, &tiles).experience, 0);
+    }
+}
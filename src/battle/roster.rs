@@ -0,0 +1,95 @@
+use crate::battle::ability::Ability;
+use crate::display::tile::Unit;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Stats and abilities shared by every stack of one creature type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnitDefinition {
+    pub id: String,
+    pub name: String,
+    pub level: u8,
+    pub health: u32,
+    pub attack: u32,
+    pub defence: u32,
+    pub damage: u32,
+    pub move_range: usize,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    /// Cells the creature covers; large creatures take two.
+    #[serde(default = "UnitDefinition::one")]
+    pub size: usize,
+}
+
+impl UnitDefinition {
+    fn one() -> usize {
+        1
+    }
+
+    pub fn spawn(&self, unit_id: usize, count: u32) -> Unit {
+        Unit::new(unit_id, &self.name, count, self.health, self.damage, self.move_range)
+            .with_kind(&self.id)
+            .with_skills(self.attack, self.defence)
+            .with_abilities(self.abilities.clone())
+            .with_size(self.size)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Roster {
+    units: Vec<UnitDefinition>,
+}
+
+impl Roster {
+    pub const KINGDOM: &'static str = "data/units/kingdom.ron";
+    /// Arrow towers and siege engines; see `battle::siege`.
+    pub const WAR_MACHINES: &'static str = "data/units/war_machines.ron";
+
+    pub fn from_ron(source: &str) -> std::result::Result<Self, ron::error::SpannedError> {
+        ron::from_str(source)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn units(&self) -> &[UnitDefinition] {
+        &self.units
+    }
+
+    pub fn get(&self, id: &str) -> Option<&UnitDefinition> {
+        self.units.iter().find(|unit| unit.id == id)
+    }
+
+    pub fn spawn(&self, id: &str, unit_id: usize, count: u32) -> Result<Unit> {
+        self.get(id)
+            .map(|definition| definition.spawn(unit_id, count))
+            .ok_or_else(|| Error::rules(format!("Unknown unit {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kingdom_roster_parses() {
+        let roster = Roster::load(Roster::KINGDOM).unwrap();
+        assert_eq!(roster.units().len(), 8);
+        assert!(roster.get("angel").unwrap().abilities.contains(&Ability::Flying));
+        assert_eq!(roster.spawn("royal_knight", 0, 1).unwrap().size, 2);
+        assert_eq!(roster.spawn("peasant", 0, 1).unwrap().size, 1);
+    }
+
+    #[test]
+    fn test_spawned_shooter_has_full_quiver() {
+        let roster = Roster::load(Roster::KINGDOM).unwrap();
+        let archers = roster.spawn("archer", 3, 10).unwrap();
+        assert_eq!((archers.id, archers.count), (3, 10));
+        assert_eq!(archers.kind.as_deref(), Some("archer"));
+        assert!(archers.can_shoot());
+        assert!(roster.spawn("dragon", 4, 1).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::battle::battlefield::{Battlefield, Cover};
+use crate::battle::siege::Siege;
+use crate::battle::{BattleEvent, BattleState, Side};
+use crate::display::tile::{Tile, TileType, Unit};
+use crate::error::{Error, Result};
+use crate::hero::Hero;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// First word of every save file; anything else is not a battle save.
+pub const MAGIC: &str = "audax-battle-save";
+/// Schema written by this build. A change to `BattleSave` bumps it, and the schema it
+/// replaces gets a migration in `BattleSave::parse`.
+pub const VERSION: u32 = 3;
+/// Number of save slots the System menu offers.
+pub const SLOTS: usize = 5;
+
+/// File behind save slot `slot`, counted from zero.
+pub fn slot_path(slot: usize) -> String {
+    format!("saves/slot{}.ron", slot + 1)
+}
+
+/// A one-line description of every slot; `None` for the empty ones.
+pub fn slot_summaries() -> Vec<Option<String>> {
+    (0..SLOTS)
+        .map(|slot| {
+            let path = slot_path(slot);
+            if !Path::new(&path).exists() {
+                return None;
+            }
+            Some(match BattleSave::read_from(&path) {
+                Ok(save) => save.summary(),
+                Err(error) => {
+                    log::warn!("Can't read {}: {}", path, error);
+                    "damaged".to_string()
+                }
+            })
+        })
+        .collect()
+}
+
+/// One grid cell without its texture, which is picked again from the tile type on load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedTile {
+    pub tile_type: TileType,
+    pub unit: Option<Unit>,
+    pub anchor: Option<usize>,
+    pub ground: Option<TileType>,
+}
+
+impl From<&Tile> for SavedTile {
+    fn from(tile: &Tile) -> Self {
+        Self {
+            tile_type: tile.tile_type.clone(),
+            unit: tile.unit.clone(),
+            anchor: tile.anchor,
+            ground: tile.ground.clone(),
+        }
+    }
+}
+
+impl SavedTile {
+    /// Fills `template`, which already carries the right texture, with the saved contents.
+    pub fn into_tile(self, template: &Tile) -> Tile {
+        let mut tile = template.clone();
+        tile.tile_type = self.tile_type;
+        tile.unit = self.unit;
+        tile.anchor = self.anchor;
+        tile.ground = self.ground;
+        tile.back_light = false;
+        tile
+    }
+}
+
+/// Everything needed to resume a battle exactly where it was left: the grid with its stacks
+/// and their effects, the turn queue, mana, the random source, the hero and the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BattleSave {
+    pub tiles: Vec<SavedTile>,
+    pub battle_state: BattleState,
+    pub hero: Option<Hero>,
+    /// Stacks as they entered the battle, for counting casualties at the end.
+    pub deployed: Vec<(Side, Unit)>,
+    pub combat_log: Vec<BattleEvent>,
+    /// Cover of the field under the stacks and walls.
+    pub battlefield: Battlefield,
+    /// The walls of a siege, if the battle is fought at a town.
+    pub siege: Option<Siege>,
+}
+
+/// Version 1: battles before sieges.
+#[derive(Deserialize)]
+struct BattleSaveV1 {
+    tiles: Vec<SavedTile>,
+    battle_state: BattleState,
+    hero: Option<Hero>,
+    deployed: Vec<(Side, Unit)>,
+    combat_log: Vec<BattleEvent>,
+}
+
+impl From<BattleSaveV1> for BattleSaveV2 {
+    fn from(old: BattleSaveV1) -> Self {
+        Self {
+            tiles: old.tiles,
+            battle_state: old.battle_state,
+            hero: old.hero,
+            deployed: old.deployed,
+            combat_log: old.combat_log,
+            siege: None,
+        }
+    }
+}
+
+/// Version 2: battles before generated fields, when every obstacle was a forest.
+#[derive(Deserialize)]
+struct BattleSaveV2 {
+    tiles: Vec<SavedTile>,
+    battle_state: BattleState,
+    hero: Option<Hero>,
+    deployed: Vec<(Side, Unit)>,
+    combat_log: Vec<BattleEvent>,
+    siege: Option<Siege>,
+}
+
+impl TryFrom<BattleSaveV2> for BattleSave {
+    type Error = Error;
+
+    fn try_from(mut old: BattleSaveV2) -> Result<Self> {
+        let cells = old
+            .tiles
+            .iter()
+            .map(|tile| if tile.tile_type == TileType::Obstacle { Cover::Forest } else { Cover::Open })
+            .collect();
+        let battlefield = Battlefield::new(cells, Vec::new())?;
+        // Tiles didn't keep the ground under their stacks yet; it was a spawn point on the
+        // spawn zones and plain ground elsewhere.
+        for (index, tile) in old.tiles.iter_mut().enumerate() {
+            if tile.ground.is_none() && (tile.unit.is_some() || tile.anchor.is_some()) {
+                let spawn = battlefield.spawn_point(index).is_some();
+                tile.ground = Some(if spawn { TileType::SpawnPoint } else { TileType::Empty });
+            }
+        }
+        Ok(Self {
+            battlefield,
+            tiles: old.tiles,
+            battle_state: old.battle_state,
+            hero: old.hero,
+            deployed: old.deployed,
+            combat_log: old.combat_log,
+            siege: old.siege,
+        })
+    }
+}
+
+impl BattleSave {
+    /// E.g. "Round 3, 4 vs 2 stacks", for the slot list.
+    pub fn summary(&self) -> String {
+        let stacks = |tile_type: TileType| {
+            self.tiles
+                .iter()
+                .filter(|tile| tile.tile_type == tile_type && tile.unit.is_some())
+                .count()
+        };
+        format!(
+            "Round {}, {} vs {} stacks",
+            self.battle_state.round(),
+            stacks(TileType::MyUnit),
+            stacks(TileType::EnemyUnit)
+        )
+    }
+
+    /// The header line followed by the RON body.
+    pub fn to_string(&self) -> Result<String> {
+        let body = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| Error::Serialization(error.to_string()))?;
+        Ok(format!("{} {}\n{}", MAGIC, VERSION, body))
+    }
+
+    /// Reads a save of any known version, migrating older ones to the current schema.
+    pub fn parse(source: &str) -> Result<Self> {
+        let (header, body) = source.split_once('\n').unwrap_or((source, ""));
+        let version = match header.trim().split_once(' ') {
+            Some((MAGIC, version)) => version
+                .parse::<u32>()
+                .map_err(|_| Error::Serialization(format!("bad save version {:?}", version)))?,
+            _ => return Err(Error::Serialization("not a battle save".to_string())),
+        };
+        let decode_error =
+            |error: ron::error::SpannedError| Error::Serialization(format!("version {}: {}", version, error));
+        match version {
+            1 => ron::from_str::<BattleSaveV1>(body)
+                .map_err(decode_error)
+                .and_then(|old| BattleSaveV2::from(old).try_into()),
+            2 => ron::from_str::<BattleSaveV2>(body).map_err(decode_error).and_then(Self::try_from),
+            VERSION => ron::from_str(body).map_err(decode_error),
+            _ => Err(Error::Serialization(format!(
+                "save version {} is not supported (newest is {})",
+                version, VERSION
+            ))),
+        }
+    }
+
+    pub fn write_to(&self, path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|error| Error::asset(path, error))?;
+        }
+        fs::write(path, self.to_string()?).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn read_from(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::parse(&source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::footprint;
+    use crate::battle::status::{Stat, StatusEffect};
+    use crate::display::GameState;
+    use crate::hero::PrimaryStats;
+    use crate::world::Terrain;
+
+    fn battle() -> BattleSave {
+        let mut tiles = Tile::board_for_test();
+        tiles[5].tile_type = TileType::Obstacle;
+        let mut knights = Unit::new(0, "Royal Knights", 4, 40, 5, 3).with_size(2);
+        knights.add_effect(StatusEffect::new("bless", "Bless", Some(2)).with_modifier(Stat::Attack, 3));
+        footprint::place(&mut tiles, 0, knights.clone(), TileType::MyUnit);
+        let peasants = Unit::new(1, "Peasants", 30, 1, 1, 1);
+        tiles[11].set_unit(peasants.clone(), TileType::EnemyUnit);
+
+        let mut battle_state = BattleState::with_seed(7);
+        battle_state.rng_mut().next_u64();
+        battle_state.unit_acted(0, &[0]);
+        BattleSave {
+            tiles: tiles.iter().map(SavedTile::from).collect(),
+            battle_state,
+            hero: Some(Hero::new("Roland", PrimaryStats::default())),
+            deployed: vec![(Side::Player, knights), (Side::Enemy, peasants)],
+            combat_log: vec![BattleEvent::RoundStarted { round: 1 }],
+            battlefield: Battlefield::generate(Terrain::Mountain, 3),
+            siege: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_restores_everything() {
+        let save = battle();
+        let text = save.to_string().unwrap();
+        assert!(text.starts_with(&format!("{} {}\n", MAGIC, VERSION)));
+
+        assert_eq!(save.summary(), format!("Round {}, 1 vs 1 stacks", save.battle_state.round()));
+
+        let mut loaded = BattleSave::parse(&text).unwrap();
+        assert_eq!(loaded, save);
+        let mut original = save.battle_state.clone();
+        assert_eq!(loaded.battle_state.rng_mut().next_u64(), original.rng_mut().next_u64());
+    }
+
+    /// `save` as a version `version` build wrote it, followed by the `extra` fields later ones added.
+    fn older(save: &BattleSave, version: u32, extra: &str) -> String {
+        let tiles: Vec<SavedTile> = save.tiles.iter().map(|tile| SavedTile { ground: None, ..tile.clone() }).collect();
+        format!(
+            "{} {}\n(tiles: {}, battle_state: {}, hero: {}, deployed: {}, combat_log: {}{})",
+            MAGIC,
+            version,
+            ron::to_string(&tiles).unwrap(),
+            ron::to_string(&save.battle_state).unwrap(),
+            ron::to_string(&save.hero).unwrap(),
+            ron::to_string(&save.deployed).unwrap(),
+            ron::to_string(&save.combat_log).unwrap(),
+            extra,
+        )
+    }
+
+    /// `battle` as a version 1 or 2 save could hold it, with its cover drawn as obstacles and
+    /// its stacks standing on the spawn zones.
+    fn forest_battle() -> BattleSave {
+        let mut save = battle();
+        let mut cells = vec![Cover::Open; GameState::GRID_SIZE * GameState::GRID_SIZE];
+        cells[5] = Cover::Forest;
+        save.battlefield = Battlefield::new(cells, Vec::new()).unwrap();
+        for index in [0, 1, 11] {
+            save.tiles[index].ground = Some(TileType::SpawnPoint);
+        }
+        save
+    }
+
+    #[test]
+    fn test_version_one_is_migrated() {
+        let save = forest_battle();
+        assert_eq!(BattleSave::parse(&older(&save, 1, "")).unwrap(), save);
+    }
+
+    #[test]
+    fn test_version_two_rebuilds_the_field_from_obstacles() {
+        let save = forest_battle();
+        let loaded = BattleSave::parse(&older(&save, 2, ", siege: None")).unwrap();
+        assert_eq!(loaded.battlefield.cover(5), Cover::Forest);
+        assert_eq!(loaded, save);
+    }
+
+    #[test]
+    fn test_migrated_stacks_leave_their_spawn_points() {
+        let loaded = BattleSave::parse(&older(&forest_battle(), 2, ", siege: None")).unwrap();
+        let template = Tile::new_for_test(TileType::Empty);
+        let mut tiles: Vec<Tile> = loaded.tiles.into_iter().map(|tile| tile.into_tile(&template)).collect();
+
+        footprint::lift(&mut tiles, 0).unwrap();
+        footprint::lift(&mut tiles, 11).unwrap();
+        assert!([0, 1, 11].iter().all(|&index| tiles[index].tile_type == TileType::SpawnPoint));
+    }
+
+    #[test]
+    fn test_siege_survives_a_round_trip() {
+        let mut save = battle();
+        let mut tiles = Tile::board_for_test();
+        let wall = Tile::new_for_test(TileType::Wall);
+        save.siege = Some(Siege::build(&mut tiles, &wall, &Tile::new_for_test(TileType::Gate)).unwrap());
+        save.tiles = tiles.iter().map(SavedTile::from).collect();
+
+        assert_eq!(BattleSave::parse(&save.to_string().unwrap()).unwrap(), save);
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_future_files() {
+        assert!(BattleSave::parse("(tiles: [])").is_err());
+        let future = format!("{} {}\n()", MAGIC, VERSION + 1);
+        let error = BattleSave::parse(&future).unwrap_err();
+        assert!(error.to_string().contains("not supported"), "{}", error);
+    }
+}
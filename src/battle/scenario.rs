@@ -0,0 +1,327 @@
+use crate::battle::army::Army;
+use crate::battle::battlefield::{Battlefield, Cover};
+use crate::battle::footprint;
+use crate::battle::roster::Roster;
+use crate::battle::state::Side;
+use crate::display::GameState;
+use crate::display::tile::Unit;
+use crate::error::{Error, Result};
+use ron::error::Position;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+
+#[derive(Clone, Debug, Deserialize)]
+struct StackDefinition {
+    side: Side,
+    at: (usize, usize),
+    unit: String,
+    count: u32,
+}
+
+/// A battle map file: one string per row of cells, stacks anchored at (row, col). Every map
+/// is `GameState::GRID_SIZE` cells square, 12 by 12, like the board it is fought on.
+#[derive(Clone, Debug, Deserialize)]
+struct ScenarioDefinition {
+    name: String,
+    field: Vec<String>,
+    #[serde(default)]
+    stacks: Vec<StackDefinition>,
+}
+
+/// A stack a battle map puts on the field before anyone deploys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacedStack {
+    pub side: Side,
+    pub anchor: usize,
+    pub unit: Unit,
+}
+
+/// A hand-made battle: the field, where the sides may deploy and the stacks already on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    field: Battlefield,
+    stacks: Vec<PlacedStack>,
+}
+
+impl Scenario {
+    /// The field every battle was fought on before fields were generated.
+    pub const CLASSIC: &'static str = "data/battles/classic.ron";
+    pub const TUTORIAL: &'static str = "data/battles/tutorial.ron";
+
+    /// Reads a battle map, taking its creatures from `roster`. Every mistake is reported at
+    /// the line and column of the file it was made, counted from 1 like ron's own syntax
+    /// errors; cells and stacks are also named by their place on the grid and in the list,
+    /// counted from 0 like `at`.
+    pub fn from_ron(source: &str, roster: &Roster) -> Result<Self> {
+        let definition: ScenarioDefinition =
+            ron::from_str(source).map_err(|error| Error::Serialization(error.to_string()))?;
+        let grid = GameState::GRID_SIZE;
+        let at_cell = |index: usize, reason: String| {
+            let (row, col) = (index / grid, index % grid);
+            located(source, "field", Some(row), 1 + col, format!("Row {}, column {}: {}", row, col, reason))
+        };
+        if definition.field.len() != grid {
+            let reason = format!("The field has {} rows, battle maps have {}", definition.field.len(), grid);
+            return Err(located(source, "field", None, 0, reason));
+        }
+        let mut cells = Vec::with_capacity(grid * grid);
+        let mut spawn_points = Vec::new();
+        for (row, line) in definition.field.iter().enumerate() {
+            let width = line.chars().count();
+            if width != grid {
+                let reason = format!("Row {} is {} cells wide, battle maps are {}", row, width, grid);
+                return Err(located(source, "field", Some(row), 0, reason));
+            }
+            for (col, c) in line.chars().enumerate() {
+                let index = row * grid + col;
+                let cover = Cover::from_char(c).ok_or_else(|| at_cell(index, format!("Unknown cell {:?}", c)))?;
+                let side = match c {
+                    'p' => Some(Side::Player),
+                    'e' => Some(Side::Enemy),
+                    _ => None,
+                };
+                if let Some(side) = side {
+                    if Army::spawn_side(index) != side {
+                        return Err(at_cell(index, format!("{:?} spawn point on the other half of the field", side)));
+                    }
+                    spawn_points.push(index);
+                }
+                cells.push(cover);
+            }
+        }
+        let field = Battlefield::new(cells, spawn_points)?;
+        if let Some(index) = field.cut_off() {
+            return Err(at_cell(index, "Open cell walled off from the rest of the field".to_string()));
+        }
+
+        let mut taken = HashSet::new();
+        let mut stacks = Vec::with_capacity(definition.stacks.len());
+        for (number, placed) in definition.stacks.into_iter().enumerate() {
+            let at_stack = |reason: String| {
+                let reason = format!("Stack {} ({}): {}", number, placed.unit, reason);
+                located(source, "stacks", Some(number), 0, reason)
+            };
+            if placed.count == 0 {
+                return Err(at_stack("A stack needs at least one creature".to_string()));
+            }
+            let unit = roster
+                .spawn(&placed.unit, 0, placed.count)
+                .map_err(|_| at_stack("Unknown unit".to_string()))?;
+            let (row, col) = placed.at;
+            let anchor = row * grid + col;
+            let cells = (row < grid && col < grid)
+                .then(|| footprint::cells(anchor, unit.size))
+                .flatten()
+                .ok_or_else(|| at_stack(format!("{} at {}, {} stick out of the field", unit.name, row, col)))?;
+            if cells.iter().any(|index| field.cover(*index) != Cover::Open || taken.contains(index)) {
+                return Err(at_stack(format!("{} at {}, {} stand on cover or another stack", unit.name, row, col)));
+            }
+            taken.extend(cells);
+            stacks.push(PlacedStack {
+                side: placed.side,
+                anchor,
+                unit,
+            });
+        }
+        Ok(Self {
+            name: definition.name,
+            field,
+            stacks,
+        })
+    }
+
+    pub fn load(path: &str, roster: &Roster) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source, roster).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn field(&self) -> &Battlefield {
+        &self.field
+    }
+
+    pub fn stacks(&self) -> &[PlacedStack] {
+        &self.stacks
+    }
+}
+
+/// A mistake `offset` characters into the list under `key` of `source`, or into its item
+/// `item`, prefixed with the line and column it is at.
+fn located(source: &str, key: &'static str, item: Option<usize>, offset: usize, reason: String) -> Error {
+    match position_of(source, key, item) {
+        Some(at) => Error::rules(format!("{}:{}: {}", at.line, at.col + offset, reason)),
+        None => Error::rules(reason),
+    }
+}
+
+/// Where the list under `key`, or its item `item`, starts in `source`. ron only tells
+/// positions through its errors, so a `Probe` reads the file again and fails right in
+/// front of the value.
+fn position_of(source: &str, key: &'static str, item: Option<usize>) -> Option<Position> {
+    match ron::Options::default().from_str_seed(source, Probe { key, item }) {
+        Err(error) if error.code == ron::Error::Message(Probe::HERE.to_string()) => Some(error.span.end),
+        _ => None,
+    }
+}
+
+struct Probe {
+    key: &'static str,
+    item: Option<usize>,
+}
+
+impl Probe {
+    const HERE: &'static str = "probe reached its value";
+}
+
+impl<'de> DeserializeSeed<'de> for Probe {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_struct("ScenarioDefinition", &[], self)
+    }
+}
+
+impl<'de> Visitor<'de> for Probe {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a battle map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key != self.key {
+                map.next_value::<IgnoredAny>()?;
+            } else if let Some(item) = self.item {
+                return map.next_value_seed(Item(item));
+            } else {
+                return map.next_value_seed(Here);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Skips to item `.0` of a list and stops there.
+struct Item(usize);
+
+impl<'de> DeserializeSeed<'de> for Item {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Item {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        for _ in 0..self.0 {
+            seq.next_element::<IgnoredAny>()?;
+        }
+        seq.next_element_seed(Here).map(|_| ())
+    }
+}
+
+/// Fails before reading anything, which makes ron report where the value starts.
+struct Here;
+
+impl<'de> DeserializeSeed<'de> for Here {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, _: D) -> std::result::Result<(), D::Error> {
+        Err(de::Error::custom(Probe::HERE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"// A test map.
+(
+    name: "Ambush",
+    field: [
+        "pp........ee",
+        "p.........ee",
+        "............",
+        "....F..F....",
+        "....^..^....",
+        "............",
+        "............",
+        "....^..^....",
+        "....F..F....",
+        "............",
+        "p.........ee",
+        "pp........ee",
+    ],
+    stacks: [
+        (side: Player, at: (5, 2), unit: "knight", count: 10),
+        (side: Enemy, at: (6, 8), unit: "royal_knight", count: 2),
+    ],
+)"#;
+
+    fn roster() -> Roster {
+        Roster::load(Roster::KINGDOM).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        Scenario::from_ron(source, &roster()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_battle_map_parses() {
+        let scenario = Scenario::from_ron(MAP, &roster()).unwrap();
+        assert_eq!(scenario.name, "Ambush");
+        assert_eq!(scenario.field().cover(3 * GameState::GRID_SIZE + 4), Cover::Forest);
+        assert_eq!(scenario.field().spawn_point(GameState::GRID_SIZE), Some(Side::Player));
+        assert_eq!(scenario.field().spawn_point(2 * GameState::GRID_SIZE), None);
+        assert_eq!(scenario.stacks().len(), 2);
+        assert_eq!(scenario.stacks()[1].side, Side::Enemy);
+        assert_eq!(scenario.stacks()[1].unit.size, 2);
+        assert_eq!(scenario.field().preview().lines().next(), Some("pp........ee"));
+    }
+
+    #[test]
+    fn test_shipped_battle_maps_load() {
+        for path in [Scenario::CLASSIC, Scenario::TUTORIAL] {
+            let scenario = Scenario::load(path, &roster()).unwrap();
+            assert!(scenario.field().is_connected(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_mistakes_point_at_line_and_column() {
+        let cell = MAP.replace("\"....^..^....\",\n        \"....F", "\"....^..X....\",\n        \"....F");
+        assert!(error(&cell).contains("12:17: Row 7, column 7: Unknown cell 'X'"));
+        let row = MAP.replace("\"p.........ee\",\n        \"...", "\"p.........e\",\n        \"...");
+        assert!(error(&row).contains("6:9: Row 1 is 11 cells wide"));
+        let spawn = MAP.replace("pp........ee\",\n        \"p.", "pp........ep\",\n        \"p.");
+        assert!(error(&spawn).contains("5:21: Row 0, column 11: Player spawn point"));
+        let short = MAP.replace("        \"pp........ee\",\n        \"p.", "        \"p.");
+        assert!(error(&short).contains("4:12: The field has 11 rows"));
+        assert!(error(&MAP.replace("\"knight\"", "\"dragon\"")).contains("19:9: Stack 0 (dragon): Unknown unit"));
+        let outside = error(&MAP.replace("at: (6, 8)", "at: (6, 11)"));
+        assert!(outside.contains("20:9: Stack 1 (royal_knight): Royal Knights at 6, 11 stick out"));
+        let covered = error(&MAP.replace("at: (6, 8)", "at: (3, 3)"));
+        assert!(covered.contains("20:9: Stack 1 (royal_knight): Royal Knights at 3, 3 stand on cover"));
+        assert!(error(&MAP.replace("name:", "name")).starts_with("serialization: 3:"));
+    }
+
+    #[test]
+    fn test_walled_off_cells_are_rejected() {
+        let walled = MAP
+            .replacen("\"....F..F....\"", "\"FF..F..F....\"", 1)
+            .replacen("\"....^..^....\"", "\".F..^..^....\"", 1)
+            .replacen("\"............\",\n        \"......", "\"FF..........\",\n        \"......", 1);
+        assert!(error(&walled).contains("9:10: Row 4, column 0: Open cell walled off"));
+    }
+}
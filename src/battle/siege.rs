@@ -0,0 +1,200 @@
+use crate::battle::army::Army;
+use crate::battle::combat;
+use crate::battle::combat_log::BattleEvent;
+use crate::battle::movement::is_free;
+use crate::battle::state::Side;
+use crate::common::rng::GameRng;
+use crate::display::GameState;
+use crate::display::tile::Tile;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// One stretch of a town's wall, or its gate, with the hit points it has left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    pub gate: bool,
+    pub health: u32,
+}
+
+impl Segment {
+    fn new(gate: bool) -> Self {
+        let health = if gate { Siege::GATE_HEALTH } else { Siege::WALL_HEALTH };
+        Self { gate, health }
+    }
+
+    pub fn full_health(&self) -> u32 {
+        if self.gate { Siege::GATE_HEALTH } else { Siege::WALL_HEALTH }
+    }
+}
+
+/// The walls of a besieged town: a column in front of the defenders' spawn zone with the
+/// gate in its middle and an arrow tower at either end.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Siege {
+    segments: BTreeMap<usize, Segment>,
+}
+
+impl Siege {
+    pub const WALL_COLUMN: usize = GameState::GRID_SIZE - Army::SPAWN_DEPTH - 1;
+    pub const GATE_ROW: usize = GameState::GRID_SIZE / 2;
+    pub const TOWER_ROWS: [usize; 2] = [0, GameState::GRID_SIZE - 1];
+    /// The attackers' catapult stands in the bottom corner of their back column.
+    pub const CATAPULT_TILE: usize = (GameState::GRID_SIZE - 1) * GameState::GRID_SIZE;
+    pub const WALL_HEALTH: u32 = 60;
+    pub const GATE_HEALTH: u32 = 90;
+
+    /// Raises the wall on `tiles` from the `wall` and `gate` templates, leaving the tower
+    /// rows free. Fails when a stack or an obstacle stands in the way.
+    pub fn build(tiles: &mut [Tile], wall: &Tile, gate: &Tile) -> Result<Self> {
+        let mut segments = BTreeMap::new();
+        for row in (0..GameState::GRID_SIZE).filter(|row| !Self::TOWER_ROWS.contains(row)) {
+            let index = row * GameState::GRID_SIZE + Self::WALL_COLUMN;
+            if !tiles.get(index).is_some_and(is_free) {
+                return Err(Error::rules(format!("Tile {}, {} is in the way of the walls", row, Self::WALL_COLUMN)));
+            }
+            let segment = Segment::new(row == Self::GATE_ROW);
+            tiles[index] = if segment.gate { gate.clone() } else { wall.clone() };
+            segments.insert(index, segment);
+        }
+        Ok(Self { segments })
+    }
+
+    /// Tiles the arrow towers stand on.
+    pub fn tower_tiles() -> impl Iterator<Item = usize> {
+        Self::TOWER_ROWS.into_iter().map(|row| row * GameState::GRID_SIZE + Self::WALL_COLUMN)
+    }
+
+    pub fn segment(&self, index: usize) -> Option<&Segment> {
+        self.segments.get(&index)
+    }
+
+    /// Segments still standing, by tile.
+    pub fn segments(&self) -> impl Iterator<Item = (usize, &Segment)> {
+        self.segments.iter().map(|(index, segment)| (*index, segment))
+    }
+
+    /// `attacker` batters the segment on `index` for `damage`; one brought down leaves
+    /// `rubble` behind, which anyone may cross.
+    pub fn hit(
+        &mut self,
+        tiles: &mut [Tile],
+        index: usize,
+        attacker: &str,
+        damage: u32,
+        rubble: &Tile,
+    ) -> Result<Vec<BattleEvent>> {
+        let segment = self
+            .segments
+            .get_mut(&index)
+            .ok_or_else(|| Error::rules(format!("No wall on tile {}", index)))?;
+        let at = (index / GameState::GRID_SIZE, index % GameState::GRID_SIZE);
+        let dealt = damage.min(segment.health);
+        segment.health -= dealt;
+        let mut events = vec![BattleEvent::WallHit {
+            attacker: attacker.to_string(),
+            at,
+            damage: dealt,
+        }];
+        if segment.health == 0 {
+            events.push(BattleEvent::WallBreached { at, gate: segment.gate });
+            self.segments.remove(&index);
+            tiles[index] = rubble.clone();
+        }
+        Ok(events)
+    }
+}
+
+/// War machines that shoot fire on their own, each at the enemy stack it hurts most.
+/// Returns the ids of those that fired and what happened.
+pub fn fire_towers(tiles: &mut [Tile], rng: &mut GameRng) -> (Vec<usize>, Vec<BattleEvent>) {
+    let (mut fired, mut events) = (Vec::new(), Vec::new());
+    for index in 0..tiles.len() {
+        let Some(tower) = tiles[index].get_unit().filter(|unit| unit.is_war_machine() && unit.is_ranged()) else {
+            continue;
+        };
+        let tower_id = tower.id;
+        let Some(side) = Side::from_tile_type(&tiles[index].tile_type) else {
+            continue;
+        };
+        let target = (0..tiles.len())
+            .filter(|&next| tiles[next].get_unit().is_some())
+            .filter(|&next| Side::from_tile_type(&tiles[next].tile_type) == Some(side.opponent()))
+            .filter_map(|next| combat::preview(tiles, index, next).map(|preview| (next, preview)))
+            .max_by_key(|&(next, preview)| (preview.perished, preview.damage, Reverse(next)));
+        let Some((target, _)) = target else {
+            continue;
+        };
+        match combat::attack(tiles, index, target, rng) {
+            Ok(outcome) => {
+                events.extend(outcome);
+                fired.push(tower_id);
+            }
+            Err(error) => log::warn!("Tower {} can't fire: {}", tower_id, error),
+        }
+    }
+    (fired, events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::Roster;
+    use crate::display::tile::{TileType, Unit};
+
+    fn besieged() -> (Vec<Tile>, Siege) {
+        let mut tiles = Tile::board_for_test();
+        let siege = Siege::build(
+            &mut tiles,
+            &Tile::new_for_test(TileType::Wall),
+            &Tile::new_for_test(TileType::Gate),
+        )
+        .unwrap();
+        (tiles, siege)
+    }
+
+    #[test]
+    fn test_walls_close_the_column_but_the_tower_rows() {
+        let (tiles, siege) = besieged();
+        assert_eq!(siege.segments().count(), GameState::GRID_SIZE - 2);
+        let gate = Siege::GATE_ROW * GameState::GRID_SIZE + Siege::WALL_COLUMN;
+        assert_eq!(tiles[gate].tile_type, TileType::Gate);
+        assert_eq!(siege.segment(gate).map(|segment| segment.health), Some(Siege::GATE_HEALTH));
+        assert!(Siege::tower_tiles().all(|index| tiles[index].tile_type == TileType::Empty));
+    }
+
+    #[test]
+    fn test_battered_wall_crumbles_into_rubble() {
+        let (mut tiles, mut siege) = besieged();
+        let wall = GameState::GRID_SIZE + Siege::WALL_COLUMN;
+        let rubble = Tile::new_for_test(TileType::Empty);
+
+        let events = siege.hit(&mut tiles, wall, "Catapult", 40, &rubble).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(siege.segment(wall).unwrap().health, Siege::WALL_HEALTH - 40);
+
+        let events = siege.hit(&mut tiles, wall, "Catapult", 40, &rubble).unwrap();
+        assert_eq!(events[1], BattleEvent::WallBreached { at: (1, Siege::WALL_COLUMN), gate: false });
+        assert_eq!(tiles[wall].tile_type, TileType::Empty);
+        assert!(siege.segment(wall).is_none());
+        assert!(siege.hit(&mut tiles, wall, "Catapult", 40, &rubble).is_err());
+    }
+
+    #[test]
+    fn test_towers_shoot_the_stack_they_hurt_most() {
+        let (mut tiles, _) = besieged();
+        let roster = Roster::load(Roster::WAR_MACHINES).unwrap();
+        let tower = Siege::tower_tiles().next().unwrap();
+        tiles[tower].set_unit(roster.spawn("arrow_tower", 0, 1).unwrap(), TileType::EnemyUnit);
+        tiles[GameState::GRID_SIZE].set_unit(Unit::new(1, "Peasants", 50, 5, 1, 1), TileType::MyUnit);
+        tiles[2 * GameState::GRID_SIZE].set_unit(Unit::new(2, "Knights", 1, 30, 3, 2), TileType::MyUnit);
+
+        let (fired, events) = fire_towers(&mut tiles, &mut GameRng::new(3));
+
+        assert_eq!(fired, vec![0]);
+        assert!(events.iter().any(|event| matches!(event, BattleEvent::Attacked { defender, .. } if defender == "Peasants")));
+        assert!(tiles[GameState::GRID_SIZE].get_unit().unwrap().count < 50);
+        assert_eq!(tiles[2 * GameState::GRID_SIZE].get_unit().unwrap().count, 1);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::battle::combat::settle_death;
+use crate::battle::footprint::anchor_of;
+use crate::battle::combat_log::BattleEvent;
+use crate::battle::status::StatusEffect;
+use crate::battle::state::Side;
+use crate::display::GameState;
+use crate::display::tile::{Tile, TileType, Unit};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum School {
+    Air,
+    Earth,
+    Fire,
+    Water,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Targeting {
+    /// One stack, friendly or hostile to the caster.
+    SingleUnit { friendly: bool },
+    /// Every stack, of either side, within `radius` tiles of the target.
+    Area { radius: usize },
+    /// Every stack of the caster's side; the target tile is ignored.
+    AllAllies,
+    /// An empty tile.
+    Tile,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpellEffect {
+    Damage(u32),
+    Heal(u32),
+    /// Puts a status effect on every target, e.g. a blessing or a slow.
+    Status(StatusEffect),
+    Summon {
+        name: String,
+        count: u32,
+        health: u32,
+        damage: u32,
+        move_range: usize,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spell {
+    pub id: String,
+    pub name: String,
+    pub school: School,
+    pub level: u8,
+    pub mana_cost: u32,
+    pub targeting: Targeting,
+    pub effect: SpellEffect,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Spellbook {
+    spells: Vec<Spell>,
+}
+
+impl Spellbook {
+    pub const PATH: &'static str = "data/spells.ron";
+
+    pub fn new(spells: Vec<Spell>) -> Self {
+        Self { spells }
+    }
+
+    pub fn from_ron(source: &str) -> std::result::Result<Self, ron::error::SpannedError> {
+        ron::from_str(source)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn spells(&self) -> &[Spell] {
+        &self.spells
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Spell> {
+        self.spells.iter().find(|spell| spell.id == id)
+    }
+}
+
+impl Spell {
+    /// Extra damage or healing per point of the caster's spell power.
+    pub const POWER_PER_POINT: u32 = 10;
+
+    /// Tile indices of the stacks this spell lands on when aimed at `target`; a large stack
+    /// counts once, by its anchor tile.
+    pub fn targets(&self, caster: Side, target: usize, tiles: &[Tile]) -> Result<Vec<usize>> {
+        let side_of = |index: usize| tiles.get(index).and_then(|tile| Side::from_tile_type(&tile.tile_type));
+        let has_unit = |index: usize| tiles.get(index).is_some_and(|tile| tile.get_unit().is_some());
+        match self.targeting {
+            Targeting::SingleUnit { friendly } => {
+                let wanted = if friendly { caster } else { caster.opponent() };
+                let target = anchor_of(tiles, target);
+                if has_unit(target) && side_of(target) == Some(wanted) {
+                    Ok(vec![target])
+                } else {
+                    Err(Error::rules(format!("{} needs a {} stack", self.name, if friendly { "friendly" } else { "hostile" })))
+                }
+            }
+            Targeting::Area { radius } => {
+                if target >= tiles.len() {
+                    return Err(Error::rules(format!("No tile {}", target)));
+                }
+                let (row, col) = (target / GameState::GRID_SIZE, target % GameState::GRID_SIZE);
+                let mut hit: Vec<usize> = (0..tiles.len())
+                    .filter(|&index| {
+                        let (other_row, other_col) = (index / GameState::GRID_SIZE, index % GameState::GRID_SIZE);
+                        row.abs_diff(other_row) <= radius && col.abs_diff(other_col) <= radius
+                    })
+                    .map(|index| anchor_of(tiles, index))
+                    .filter(|&index| has_unit(index))
+                    .collect();
+                hit.sort_unstable();
+                hit.dedup();
+                Ok(hit)
+            }
+            Targeting::AllAllies => Ok((0..tiles.len())
+                .filter(|&index| has_unit(index) && side_of(index) == Some(caster))
+                .collect()),
+            Targeting::Tile => {
+                let empty = tiles
+                    .get(target)
+                    .is_some_and(|tile| tile.tile_type == TileType::Empty && tile.get_unit().is_none());
+                if empty {
+                    Ok(vec![target])
+                } else {
+                    Err(Error::rules(format!("{} needs an empty tile", self.name)))
+                }
+            }
+        }
+    }
+
+    /// Resolves the spell against `tiles` and returns what happened, for the combat log.
+    /// Spell power adds damage and healing, and a round per point to status effects.
+    pub fn cast(&self, caster: Side, power: u32, target: usize, tiles: &mut [Tile]) -> Result<Vec<BattleEvent>> {
+        let targets = self.targets(caster, target, tiles)?;
+        let mut events = vec![BattleEvent::SpellCast {
+            spell: self.name.clone(),
+        }];
+        let next_id = tiles
+            .iter()
+            .filter_map(|tile| tile.get_unit().map(|unit| unit.id + 1))
+            .max()
+            .unwrap_or_default();
+
+        for index in targets {
+            let tile = &mut tiles[index];
+            if let SpellEffect::Summon {
+                name,
+                count,
+                health,
+                damage,
+                move_range,
+            } = &self.effect
+            {
+                tile.set_unit(Unit::new(next_id, name, *count, *health, *damage, *move_range), caster.tile_type());
+                events.push(BattleEvent::Summoned {
+                    unit: name.clone(),
+                    count: *count,
+                });
+                continue;
+            }
+            let Some(unit) = tile.unit.as_mut() else {
+                continue;
+            };
+            match &self.effect {
+                SpellEffect::Damage(damage) => {
+                    let damage = damage + power * Self::POWER_PER_POINT;
+                    let perished = unit.take_damage(damage);
+                    events.push(BattleEvent::SpellDamage {
+                        spell: self.name.clone(),
+                        unit: unit.name.clone(),
+                        damage,
+                        perished,
+                    });
+                }
+                SpellEffect::Heal(amount) => {
+                    let healed = unit.heal(amount + power * Self::POWER_PER_POINT);
+                    events.push(BattleEvent::Healed {
+                        unit: unit.name.clone(),
+                        amount: healed,
+                    });
+                }
+                SpellEffect::Status(effect) => {
+                    let mut effect = effect.clone();
+                    effect.rounds = effect.rounds.map(|rounds| rounds + power);
+                    unit.add_effect(effect);
+                    events.push(BattleEvent::Affected {
+                        spell: self.name.clone(),
+                        unit: unit.name.clone(),
+                    });
+                }
+                SpellEffect::Summon { .. } => {}
+            }
+            events.extend(settle_death(tiles, index));
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::status::Stat;
+
+    fn board() -> Vec<Tile> {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(Unit::new(0, "Knights", 10, 30, 3, 2), TileType::MyUnit);
+        tiles[1].set_unit(Unit::new(1, "Archers", 10, 10, 2, 2), TileType::MyUnit);
+        tiles[26].set_unit(Unit::new(2, "Peasants", 20, 5, 1, 1), TileType::EnemyUnit);
+        tiles[27].set_unit(Unit::new(3, "Peasants", 20, 5, 1, 1), TileType::EnemyUnit);
+        tiles
+    }
+
+    fn spell(targeting: Targeting, effect: SpellEffect) -> Spell {
+        Spell {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            school: School::Fire,
+            level: 1,
+            mana_cost: 5,
+            targeting,
+            effect,
+        }
+    }
+
+    #[test]
+    fn test_bundled_spellbook_parses() {
+        let spellbook = Spellbook::load(Spellbook::PATH).unwrap();
+        assert!(!spellbook.spells().is_empty());
+        assert!(spellbook.get("magic_arrow").is_some());
+    }
+
+    #[test]
+    fn test_single_enemy_damage() {
+        let mut tiles = board();
+        let arrow = spell(Targeting::SingleUnit { friendly: false }, SpellEffect::Damage(12));
+
+        let events = arrow.cast(Side::Player, 0, 26, &mut tiles).unwrap();
+
+        assert_eq!(tiles[26].get_unit().unwrap().count, 18);
+        assert_eq!(events.len(), 2);
+        assert!(arrow.cast(Side::Player, 0, 0, &mut tiles).is_err());
+    }
+
+    #[test]
+    fn test_area_hits_every_stack_in_radius() {
+        let mut tiles = board();
+        let fireball = spell(Targeting::Area { radius: 1 }, SpellEffect::Damage(100));
+
+        fireball.cast(Side::Player, 0, 14, &mut tiles).unwrap();
+
+        assert!(tiles[0].get_unit().is_some());
+        assert!(tiles[26].get_unit().is_none());
+        assert!(tiles[27].get_unit().is_none());
+        assert_eq!(tiles[26].tile_type, TileType::Empty);
+    }
+
+    #[test]
+    fn test_all_allies_buff() {
+        let mut tiles = board();
+        let bless = spell(
+            Targeting::AllAllies,
+            SpellEffect::Status(StatusEffect::new("bless", "Bless", Some(2)).with_modifier(Stat::Damage, 1)),
+        );
+
+        bless.cast(Side::Player, 0, 100, &mut tiles).unwrap();
+        bless.cast(Side::Player, 0, 100, &mut tiles).unwrap();
+
+        assert_eq!(tiles[0].get_unit().unwrap().effective_damage(), 4);
+        assert_eq!(tiles[1].get_unit().unwrap().effective_damage(), 3);
+        assert_eq!(tiles[26].get_unit().unwrap().effective_damage(), 1);
+    }
+
+    #[test]
+    fn test_debuff_lowers_stat() {
+        let mut tiles = board();
+        let slow = spell(
+            Targeting::SingleUnit { friendly: false },
+            SpellEffect::Status(StatusEffect::new("slow", "Slow", Some(2)).with_modifier(Stat::Speed, -1)),
+        );
+
+        slow.cast(Side::Player, 0, 26, &mut tiles).unwrap();
+
+        assert_eq!(tiles[26].get_unit().unwrap().effective_move_range(), 0);
+    }
+
+    #[test]
+    fn test_spell_power_strengthens_effects() {
+        let mut tiles = board();
+        let arrow = spell(Targeting::SingleUnit { friendly: false }, SpellEffect::Damage(10));
+        let slow = spell(
+            Targeting::SingleUnit { friendly: false },
+            SpellEffect::Status(StatusEffect::new("slow", "Slow", Some(1)).with_modifier(Stat::Speed, -1)),
+        );
+
+        arrow.cast(Side::Player, 2, 26, &mut tiles).unwrap();
+        slow.cast(Side::Player, 2, 26, &mut tiles).unwrap();
+
+        let peasants = tiles[26].get_unit().unwrap();
+        assert_eq!(peasants.count, 14);
+        assert_eq!(peasants.effects[0].rounds, Some(3));
+    }
+
+    #[test]
+    fn test_heal_restores_top_creature() {
+        let mut tiles = board();
+        tiles[0].unit.as_mut().unwrap().take_damage(40);
+        let cure = spell(Targeting::SingleUnit { friendly: true }, SpellEffect::Heal(100));
+
+        cure.cast(Side::Player, 0, 0, &mut tiles).unwrap();
+
+        let knights = tiles[0].get_unit().unwrap();
+        assert_eq!((knights.count, knights.top_health), (9, 30));
+    }
+
+    #[test]
+    fn test_summon_on_empty_tile() {
+        let mut tiles = board();
+        let summon = spell(
+            Targeting::Tile,
+            SpellEffect::Summon {
+                name: "Air Elementals".to_string(),
+                count: 3,
+                health: 25,
+                damage: 4,
+                move_range: 3,
+            },
+        );
+
+        summon.cast(Side::Player, 0, 5, &mut tiles).unwrap();
+
+        let summoned = tiles[5].get_unit().unwrap();
+        assert_eq!((summoned.id, summoned.count), (4, 3));
+        assert_eq!(tiles[5].tile_type, TileType::MyUnit);
+        assert!(summon.cast(Side::Player, 0, 0, &mut tiles).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::common::rng::GameRng;
+use crate::display::tile::TileType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Player,
+    Enemy,
+}
+
+impl Side {
+    pub fn from_tile_type(tile_type: &TileType) -> Option<Side> {
+        match tile_type {
+            TileType::MyUnit => Some(Side::Player),
+            TileType::EnemyUnit => Some(Side::Enemy),
+            _ => None,
+        }
+    }
+
+    pub fn tile_type(&self) -> TileType {
+        match self {
+            Side::Player => TileType::MyUnit,
+            Side::Enemy => TileType::EnemyUnit,
+        }
+    }
+
+    pub fn opponent(&self) -> Side {
+        match self {
+            Side::Player => Side::Enemy,
+            Side::Enemy => Side::Player,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Side::Player => 0,
+            Side::Enemy => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Phase {
+    /// Before round one: the player may rearrange stacks inside the spawn zone.
+    Tactics,
+    Combat,
+}
+
+/// Round bookkeeping shared by every action: who already acted and who already cast,
+/// plus the random number source behind every roll of the battle.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BattleState {
+    phase: Phase,
+    round: u32,
+    acted: HashSet<usize>,
+    spells_cast: HashSet<Side>,
+    mana: [u32; 2],
+    /// Stacks that already got a morale turn this round.
+    extra_turns: HashSet<usize>,
+    rng: GameRng,
+}
+
+impl BattleState {
+    pub const STARTING_MANA: u32 = 30;
+    pub const DEFAULT_SEED: u64 = 0;
+
+    pub fn new() -> Self {
+        Self::with_seed(Self::DEFAULT_SEED)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(GameRng::new(seed))
+    }
+
+    /// A battle drawing from `rng`, typically forked from the game's own generator.
+    pub fn with_rng(rng: GameRng) -> Self {
+        Self {
+            phase: Phase::Combat,
+            round: 1,
+            acted: HashSet::new(),
+            spells_cast: HashSet::new(),
+            mana: [Self::STARTING_MANA, 0],
+            extra_turns: HashSet::new(),
+            rng,
+        }
+    }
+
+    /// Where the battle's random sequence stands, for saves and replays.
+    pub fn rng(&self) -> &GameRng {
+        &self.rng
+    }
+
+    /// Every random draw of the battle goes through here.
+    pub fn rng_mut(&mut self) -> &mut GameRng {
+        &mut self.rng
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Tactics only happen before anyone has acted in round one.
+    pub fn begin_tactics(&mut self) -> bool {
+        if self.round == 1 && self.acted.is_empty() {
+            self.phase = Phase::Tactics;
+        }
+        self.phase == Phase::Tactics
+    }
+
+    pub fn end_tactics(&mut self) {
+        self.phase = Phase::Combat;
+    }
+
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn has_acted(&self, unit_id: usize) -> bool {
+        self.acted.contains(&unit_id)
+    }
+
+    /// Records that `unit_id` finished its turn. Returns true when that closed the round,
+    /// i.e. every id in `side_units` has acted.
+    pub fn unit_acted(&mut self, unit_id: usize, side_units: &[usize]) -> bool {
+        self.acted.insert(unit_id);
+        self.close_round_if_done(side_units)
+    }
+
+    /// A frozen stack loses its turn without acting; the round only closes through
+    /// `close_round_if_done`.
+    pub fn skip_turn(&mut self, unit_id: usize) {
+        self.acted.insert(unit_id);
+    }
+
+    pub fn close_round_if_done(&mut self, side_units: &[usize]) -> bool {
+        if side_units.iter().all(|id| self.acted.contains(id)) {
+            self.next_round();
+            return true;
+        }
+        false
+    }
+
+    /// High morale: `unit_id` acts again, at most once per round. Returns whether it may.
+    pub fn grant_extra_turn(&mut self, unit_id: usize) -> bool {
+        self.extra_turns.insert(unit_id)
+    }
+
+    pub fn next_round(&mut self) {
+        self.round += 1;
+        self.acted.clear();
+        self.spells_cast.clear();
+        self.extra_turns.clear();
+    }
+
+    pub fn mana(&self, side: Side) -> u32 {
+        self.mana[side.index()]
+    }
+
+    pub fn set_mana(&mut self, side: Side, mana: u32) {
+        self.mana[side.index()] = mana;
+    }
+
+    pub fn can_cast(&self, side: Side, mana_cost: u32) -> bool {
+        !self.spells_cast.contains(&side) && self.mana(side) >= mana_cost
+    }
+
+    pub fn has_cast(&self, side: Side) -> bool {
+        self.spells_cast.contains(&side)
+    }
+
+    pub fn spend_cast(&mut self, side: Side, mana_cost: u32) {
+        self.mana[side.index()] -= mana_cost.min(self.mana(side));
+        self.spells_cast.insert(side);
+    }
+}
+
+impl Default for BattleState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_ends_when_every_unit_acted() {
+        let mut state = BattleState::new();
+        assert!(!state.unit_acted(0, &[0, 1]));
+        assert!(state.has_acted(0));
+        assert!(state.unit_acted(1, &[0, 1]));
+        assert_eq!(state.round(), 2);
+        assert!(!state.has_acted(0));
+    }
+
+    #[test]
+    fn test_one_cast_per_side_per_round() {
+        let mut state = BattleState::new();
+        assert!(state.can_cast(Side::Player, 5));
+        state.spend_cast(Side::Player, 5);
+        assert_eq!(state.mana(Side::Player), BattleState::STARTING_MANA - 5);
+        assert!(!state.can_cast(Side::Player, 5));
+
+        state.next_round();
+        assert!(state.can_cast(Side::Player, 5));
+    }
+
+    #[test]
+    fn test_tactics_only_before_first_action() {
+        let mut state = BattleState::new();
+        assert!(state.begin_tactics());
+        state.end_tactics();
+        assert_eq!(state.phase(), Phase::Combat);
+
+        state.unit_acted(0, &[0, 1]);
+        assert!(!state.begin_tactics());
+    }
+
+    #[test]
+    fn test_one_morale_turn_per_round() {
+        let mut state = BattleState::new();
+        assert!(state.grant_extra_turn(0));
+        assert!(!state.grant_extra_turn(0));
+        state.skip_turn(1);
+        assert!(state.close_round_if_done(&[1]));
+        assert!(state.grant_extra_turn(0));
+    }
+
+    #[test]
+    fn test_cast_needs_mana() {
+        let state = BattleState::new();
+        assert!(!state.can_cast(Side::Enemy, 1));
+        assert!(!state.can_cast(Side::Player, BattleState::STARTING_MANA + 1));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::battle::combat_log::BattleEvent;
+use crate::display::tile::Unit;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    Attack,
+    Defence,
+    Damage,
+    Speed,
+    /// Chance of an extra turn, or of freezing when negative; see `battle::morale`.
+    Morale,
+    /// Chance of double damage, or of half damage when negative.
+    Luck,
+}
+
+/// What happens when the same effect lands on a stack that already carries it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stacking {
+    /// The duration restarts, modifiers stay as they are.
+    #[default]
+    Refresh,
+    /// Modifiers add up to `max` stacks, the duration restarts.
+    Stack { max: u32 },
+    /// The new application is dropped.
+    Ignore,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Hook {
+    /// Fired for every stack when a new round starts.
+    TurnStart,
+    /// Fired for the defender after a strike lands and it survives.
+    Attacked,
+    /// Fired when the last creature of the stack falls.
+    Death,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// Damages the bearer, per stack of the effect.
+    Damage(u32),
+    /// Heals the top creature of the bearer, per stack of the effect.
+    Heal(u32),
+    /// Damages whoever struck the bearer; only meaningful on `Hook::Attacked`.
+    DamageAttacker(u32),
+    /// Brings back this many creatures; only meaningful on `Hook::Death`. One use.
+    Rebirth(u32),
+    /// Removes the effect, e.g. a paralysis that breaks when the stack is hit.
+    Expire,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub id: String,
+    pub name: String,
+    /// Rounds left; `None` lasts until the battle ends or a trigger removes it.
+    pub rounds: Option<u32>,
+    #[serde(default)]
+    pub stacking: Stacking,
+    #[serde(default = "StatusEffect::one")]
+    pub stacks: u32,
+    #[serde(default)]
+    pub modifiers: Vec<(Stat, i32)>,
+    #[serde(default)]
+    pub triggers: Vec<(Hook, Trigger)>,
+}
+
+/// Events a hook produced and damage it reflects back to the attacker, by effect name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HookOutcome {
+    pub events: Vec<BattleEvent>,
+    pub reflected: Vec<(String, u32)>,
+}
+
+impl StatusEffect {
+    pub const DEFEND_ID: &'static str = "defend";
+
+    fn one() -> u32 {
+        1
+    }
+
+    pub fn new(id: &str, name: &str, rounds: Option<u32>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            rounds,
+            stacking: Stacking::Refresh,
+            stacks: 1,
+            modifiers: Vec::new(),
+            triggers: Vec::new(),
+        }
+    }
+
+    pub fn with_modifier(mut self, stat: Stat, amount: i32) -> Self {
+        self.modifiers.push((stat, amount));
+        self
+    }
+
+    pub fn with_trigger(mut self, hook: Hook, trigger: Trigger) -> Self {
+        self.triggers.push((hook, trigger));
+        self
+    }
+
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
+    /// The Defend action: a fifth more defence, at least one point, until the next round starts.
+    pub fn defend(defence: u32) -> Self {
+        let bonus = defence.div_ceil(5).max(1) as i32;
+        Self::new(Self::DEFEND_ID, "Defend", Some(1)).with_modifier(Stat::Defence, bonus)
+    }
+
+    /// Harmful effects lower a stat or hurt their bearer; the UI marks them in red.
+    pub fn is_harmful(&self) -> bool {
+        self.modifiers.iter().any(|(_, amount)| *amount < 0)
+            || self
+                .triggers
+                .iter()
+                .any(|(_, trigger)| matches!(trigger, Trigger::Damage(_)))
+    }
+
+    pub fn modifier(&self, stat: Stat) -> i32 {
+        self.modifiers
+            .iter()
+            .filter(|(modified, _)| *modified == stat)
+            .map(|(_, amount)| amount * self.stacks as i32)
+            .sum()
+    }
+
+    /// Adds `effect` to `effects` following the stacking rule of the one already there.
+    pub fn apply(effects: &mut Vec<StatusEffect>, effect: StatusEffect) {
+        let Some(existing) = effects.iter_mut().find(|existing| existing.id == effect.id) else {
+            effects.push(effect);
+            return;
+        };
+        match existing.stacking {
+            Stacking::Refresh => existing.rounds = effect.rounds,
+            Stacking::Stack { max } => {
+                existing.stacks = (existing.stacks + effect.stacks).min(max);
+                existing.rounds = effect.rounds;
+            }
+            Stacking::Ignore => {}
+        }
+    }
+
+    pub fn total(base: u32, effects: &[StatusEffect], stat: Stat) -> u32 {
+        base.saturating_add_signed(Self::sum(effects, stat))
+    }
+
+    /// Net modifier of `effects` to `stat`, for stats without a base value.
+    pub fn sum(effects: &[StatusEffect], stat: Stat) -> i32 {
+        effects.iter().map(|effect| effect.modifier(stat)).sum()
+    }
+
+    /// Counts one round off every timed effect and returns the ones that ran out.
+    pub fn tick(effects: &mut Vec<StatusEffect>) -> Vec<StatusEffect> {
+        for effect in effects.iter_mut() {
+            if let Some(rounds) = effect.rounds.as_mut() {
+                *rounds = rounds.saturating_sub(1);
+            }
+        }
+        let (expired, active) = effects.drain(..).partition(|effect| effect.rounds == Some(0));
+        *effects = active;
+        expired
+    }
+}
+
+/// Runs every trigger the effects on `unit` hold for `hook`.
+pub fn fire(unit: &mut Unit, hook: Hook) -> HookOutcome {
+    let mut outcome = HookOutcome::default();
+    let mut expired = Vec::new();
+    let effects = unit.effects.clone();
+    for effect in &effects {
+        for (_, trigger) in effect.triggers.iter().filter(|(on, _)| *on == hook) {
+            match *trigger {
+                Trigger::Damage(damage) => {
+                    let damage = damage * effect.stacks;
+                    let perished = unit.take_damage(damage);
+                    outcome.events.push(BattleEvent::SpellDamage {
+                        spell: effect.name.clone(),
+                        unit: unit.name.clone(),
+                        damage,
+                        perished,
+                    });
+                }
+                Trigger::Heal(amount) => {
+                    let amount = unit.heal(amount * effect.stacks);
+                    outcome.events.push(BattleEvent::Healed {
+                        unit: unit.name.clone(),
+                        amount,
+                    });
+                }
+                Trigger::DamageAttacker(damage) => {
+                    outcome.reflected.push((effect.name.clone(), damage * effect.stacks));
+                }
+                Trigger::Rebirth(count) if !unit.is_alive() => {
+                    unit.count = count;
+                    unit.top_health = unit.health;
+                    expired.push(effect.id.clone());
+                    outcome.events.push(BattleEvent::Reborn {
+                        unit: unit.name.clone(),
+                        count,
+                    });
+                }
+                Trigger::Rebirth(_) => {}
+                Trigger::Expire => expired.push(effect.id.clone()),
+            }
+        }
+    }
+    unit.effects.retain(|effect| !expired.contains(&effect.id));
+    outcome
+}
+
+/// Starts a new round for one stack: timed effects count down, then turn-start triggers run.
+/// A stack this kills is left for `combat::settle_death`.
+pub fn start_round(unit: &mut Unit) -> Vec<BattleEvent> {
+    let mut events: Vec<BattleEvent> = StatusEffect::tick(&mut unit.effects)
+        .into_iter()
+        .map(|effect| BattleEvent::EffectExpired {
+            effect: effect.name,
+            unit: unit.name.clone(),
+        })
+        .collect();
+    events.extend(fire(unit, Hook::TurnStart).events);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knights() -> Unit {
+        Unit::new(0, "Knights", 10, 30, 3, 2)
+    }
+
+    #[test]
+    fn test_modifiers_add_to_stat() {
+        let mut unit = knights();
+        unit.add_effect(StatusEffect::new("bless", "Bless", Some(2)).with_modifier(Stat::Damage, 1));
+        unit.add_effect(StatusEffect::new("slow", "Slow", Some(2)).with_modifier(Stat::Speed, -5));
+        assert_eq!(unit.effective_damage(), 4);
+        assert_eq!(unit.effective_move_range(), 0);
+    }
+
+    #[test]
+    fn test_refresh_restarts_duration_without_stacking() {
+        let mut effects = vec![];
+        let bless = StatusEffect::new("bless", "Bless", Some(2)).with_modifier(Stat::Damage, 1);
+        StatusEffect::apply(&mut effects, bless.clone());
+        StatusEffect::tick(&mut effects);
+        StatusEffect::apply(&mut effects, bless);
+        assert_eq!(effects.len(), 1);
+        assert_eq!(effects[0].rounds, Some(2));
+        assert_eq!(StatusEffect::total(3, &effects, Stat::Damage), 4);
+    }
+
+    #[test]
+    fn test_stack_is_capped() {
+        let mut effects = vec![];
+        let poison = StatusEffect::new("poison", "Poison", Some(3))
+            .with_stacking(Stacking::Stack { max: 2 })
+            .with_modifier(Stat::Attack, -1);
+        for _ in 0..3 {
+            StatusEffect::apply(&mut effects, poison.clone());
+        }
+        assert_eq!(effects[0].stacks, 2);
+        assert_eq!(StatusEffect::total(5, &effects, Stat::Attack), 3);
+    }
+
+    #[test]
+    fn test_ignore_keeps_first_application() {
+        let mut effects = vec![];
+        let shield = StatusEffect::new("shield", "Shield", Some(1)).with_stacking(Stacking::Ignore);
+        StatusEffect::apply(&mut effects, shield.clone());
+        StatusEffect::apply(&mut effects, StatusEffect { rounds: Some(5), ..shield });
+        assert_eq!(effects[0].rounds, Some(1));
+    }
+
+    #[test]
+    fn test_tick_expires_timed_effects_only() {
+        let mut effects = vec![StatusEffect::defend(2), StatusEffect::new("aura", "Aura", None)];
+        let expired = StatusEffect::tick(&mut effects);
+        assert_eq!(expired[0].id, StatusEffect::DEFEND_ID);
+        assert_eq!(effects[0].id, "aura");
+    }
+
+    #[test]
+    fn test_poison_and_regeneration_on_turn_start() {
+        let mut unit = knights();
+        unit.take_damage(20);
+        unit.add_effect(StatusEffect::new("regeneration", "Regeneration", None)
+            .with_trigger(Hook::TurnStart, Trigger::Heal(50)));
+        start_round(&mut unit);
+        assert_eq!(unit.top_health, 30);
+
+        unit.add_effect(StatusEffect::new("poison", "Poison", Some(3))
+            .with_trigger(Hook::TurnStart, Trigger::Damage(30)));
+        let events = start_round(&mut unit);
+        assert_eq!(unit.count, 9);
+        assert!(events.iter().any(|event| matches!(event, BattleEvent::SpellDamage { perished: 1, .. })));
+    }
+
+    #[test]
+    fn test_attacked_hook_reflects_damage_and_expires() {
+        let mut unit = knights();
+        unit.add_effect(StatusEffect::new("fire_shield", "Fire Shield", Some(2))
+            .with_trigger(Hook::Attacked, Trigger::DamageAttacker(7)));
+        unit.add_effect(StatusEffect::new("paralysis", "Paralysis", Some(2))
+            .with_trigger(Hook::Attacked, Trigger::Expire));
+
+        let outcome = fire(&mut unit, Hook::Attacked);
+
+        assert_eq!(outcome.reflected, vec![("Fire Shield".to_string(), 7)]);
+        assert_eq!(unit.effects.len(), 1);
+    }
+
+    #[test]
+    fn test_death_hook_rebirth_is_one_use() {
+        let mut unit = knights();
+        unit.add_effect(StatusEffect::new("rebirth", "Rebirth", None).with_trigger(Hook::Death, Trigger::Rebirth(2)));
+        unit.take_damage(1000);
+
+        fire(&mut unit, Hook::Death);
+        assert_eq!(unit.count, 2);
+        assert!(unit.effects.is_empty());
+    }
+
+    #[test]
+    fn test_harmful_effects() {
+        assert!(!StatusEffect::defend(2).is_harmful());
+        assert!(StatusEffect::new("slow", "Slow", Some(1)).with_modifier(Stat::Speed, -1).is_harmful());
+    }
+}
@@ -0,0 +1,57 @@
+//! Prints generated battlefields for checking the generator by eye:
+//! `cargo run --bin battlefield -- [terrain] [seed] [count]`, e.g. `forest 42 3`.
+//! The seed of every battle is logged when it starts. Given a battle map file instead,
+//! e.g. `data/battles/tutorial.ron`, it checks the file and prints the field it describes.
+
+use audax::battle::{Battlefield, Roster, Scenario};
+use audax::world::Terrain;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(path) = args.first().filter(|arg| arg.ends_with(".ron")) {
+        let scenario = Roster::load(Roster::KINGDOM).and_then(|roster| Scenario::load(path, &roster));
+        return match scenario {
+            Ok(scenario) => {
+                println!("{}:", scenario.name);
+                println!("{}", scenario.field().preview());
+                for stack in scenario.stacks() {
+                    println!("{:?}: {} {} on tile {}", stack.side, stack.unit.count, stack.unit.name, stack.anchor);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let named = |name: &String| {
+        Terrain::ALL
+            .into_iter()
+            .find(|terrain| format!("{:?}", terrain).eq_ignore_ascii_case(name))
+    };
+    let terrain = match args.first() {
+        Some(name) => match named(name) {
+            Some(terrain) => terrain,
+            None => {
+                eprintln!("Unknown terrain {:?}, expected one of {:?}", name, Terrain::ALL);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Terrain::Grass,
+    };
+    let numbers: Result<Vec<u64>, _> = args.iter().skip(1).map(|arg| arg.parse::<u64>()).collect();
+    let Ok(numbers) = numbers else {
+        eprintln!("Usage: battlefield [terrain] [seed] [count]");
+        return ExitCode::FAILURE;
+    };
+    let seed = numbers.first().copied().unwrap_or_default();
+    let count = numbers.get(1).copied().unwrap_or(1);
+    for seed in seed..seed.saturating_add(count) {
+        let field = Battlefield::generate(terrain, seed);
+        println!("{:?}, seed {}:", terrain, seed);
+        println!("{}", field.preview());
+    }
+    ExitCode::SUCCESS
+}
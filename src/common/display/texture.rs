@@ -1,12 +1,13 @@
+use crate::error::{Error, Result};
 use macroquad::prelude::{Image, Texture2D};
 use std::fs::File;
 use std::io::Read;
 
-pub fn load_texture_sync(path: &str) -> Texture2D {
-    let mut file = File::open(path).expect("Can't open file.");
+pub fn load_texture_sync(path: &str) -> Result<Texture2D> {
+    let mut file = File::open(path).map_err(|error| Error::asset(path, error))?;
     let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).expect("Read error.");
+    file.read_to_end(&mut buffer).map_err(|error| Error::asset(path, error))?;
 
-    let image = Image::from_file_with_format(&buffer, None).unwrap();
-    Texture2D::from_image(&image)
+    let image = Image::from_file_with_format(&buffer, None).map_err(|error| Error::asset(path, error))?;
+    Ok(Texture2D::from_image(&image))
 }
@@ -0,0 +1,258 @@
+use crate::common::settings::Language;
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fs;
+
+/// Plural forms a message may come in, named after the CLDR categories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PluralCategory {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+impl Language {
+    /// File name of the catalog, e.g. `data/locale/pl.ron`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Polish => "pl",
+            Language::English => "en",
+        }
+    }
+
+    /// Forms every plural message needs in this language.
+    pub fn plural_categories(&self) -> &'static [PluralCategory] {
+        match self {
+            Language::Polish => &[PluralCategory::One, PluralCategory::Few, PluralCategory::Many],
+            Language::English => &[PluralCategory::One, PluralCategory::Other],
+        }
+    }
+
+    /// Polish: 1 jednostka, 2-4 (but not 12-14) jednostki, everything else jednostek.
+    pub fn plural_category(&self, count: u32) -> PluralCategory {
+        match self {
+            Language::Polish if count == 1 => PluralCategory::One,
+            Language::Polish if (2..=4).contains(&(count % 10)) && !(12..=14).contains(&(count % 100)) => {
+                PluralCategory::Few
+            }
+            Language::Polish => PluralCategory::Many,
+            Language::English if count == 1 => PluralCategory::One,
+            Language::English => PluralCategory::Other,
+        }
+    }
+}
+
+/// Message catalogs for every language with the one currently shown. Messages name their
+/// parameters in braces, `{unit}`; plural messages keep one entry per form, `key.one`,
+/// `key.few` and so on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Locale {
+    language: Language,
+    catalogs: HashMap<Language, HashMap<String, String>>,
+}
+
+impl Locale {
+    pub const PATH: &'static str = "data/locale";
+    /// Used for keys the current language lacks.
+    pub const FALLBACK: Language = Language::English;
+
+    pub fn from_ron(
+        language: Language,
+        catalogs: &[(Language, &str)],
+    ) -> std::result::Result<Self, ron::error::SpannedError> {
+        let catalogs = catalogs
+            .iter()
+            .map(|(language, source)| ron::from_str(source).map(|catalog| (*language, catalog)))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self { language, catalogs })
+    }
+
+    /// Reads the catalog of every language from `dir`.
+    pub fn load(dir: &str, language: Language) -> Result<Self> {
+        let mut sources = Vec::new();
+        for catalog in Language::ALL {
+            let path = format!("{}/{}.ron", dir, catalog.code());
+            let source = fs::read_to_string(&path).map_err(|error| Error::asset(&path, error))?;
+            sources.push((catalog, source));
+        }
+        let sources: Vec<(Language, &str)> =
+            sources.iter().map(|(language, source)| (*language, source.as_str())).collect();
+        Self::from_ron(language, &sources).map_err(|error| Error::asset(dir, error))
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        [self.language, Self::FALLBACK]
+            .iter()
+            .find_map(|language| self.catalogs.get(language)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// True when `language` has `key`, in every plural form if it is a plural message.
+    pub fn has(&self, language: Language, key: &str) -> bool {
+        let Some(catalog) = self.catalogs.get(&language) else {
+            return false;
+        };
+        catalog.contains_key(key)
+            || language
+                .plural_categories()
+                .iter()
+                .all(|category| catalog.contains_key(&format!("{}.{}", key, category.suffix())))
+    }
+
+    pub fn keys(&self, language: Language) -> Vec<&str> {
+        self.catalogs
+            .get(&language)
+            .map(|catalog| catalog.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn text(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    /// The message under `key` with every `{name}` replaced; a missing key shows as itself.
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        match self.lookup(key) {
+            Some(message) => substitute(message, args),
+            None => {
+                log::warn!("Missing message {} for {:?}", key, self.language);
+                key.to_string()
+            }
+        }
+    }
+
+    /// The form of `key` that fits `count`, which also fills `{count}`.
+    pub fn plural(&self, key: &str, count: u32, args: &[(&str, String)]) -> String {
+        let category = self.language.plural_category(count);
+        let own = format!("{}.{}", key, category.suffix());
+        let key = if self.catalogs.get(&self.language).is_some_and(|catalog| catalog.contains_key(&own)) {
+            own
+        } else {
+            format!("{}.{}", key, Self::FALLBACK.plural_category(count).suffix())
+        };
+        let mut args = args.to_vec();
+        args.push(("count", count.to_string()));
+        self.format(&key, &args)
+    }
+}
+
+fn substitute(message: &str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(message.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    fn locale(language: Language) -> Locale {
+        Locale::load(Locale::PATH, language).unwrap()
+    }
+
+    /// Every `"area.name"` literal under `src/` that looks like a message key of one of the
+    /// `areas`.
+    fn keys_used_in_code(areas: &HashSet<&str>) -> Vec<String> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("src")];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let source = fs::read_to_string(&path).unwrap();
+                for literal in source.split('"').skip(1).step_by(2) {
+                    let (area, rest) = literal.split_once('.').unwrap_or_default();
+                    let is_key = areas.contains(area)
+                        && !rest.is_empty()
+                        && rest.chars().all(|c| c.is_ascii_lowercase() || c == '_' || c == '.');
+                    if is_key {
+                        keys.push(literal.to_string());
+                    }
+                }
+            }
+        }
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    #[test]
+    fn test_no_missing_keys() {
+        let locale = locale(Language::Polish);
+        let areas: HashSet<&str> = Language::ALL
+            .iter()
+            .flat_map(|&language| locale.keys(language))
+            .filter_map(|key| key.split_once('.').map(|(area, _)| area))
+            .collect();
+        assert!(areas.contains("level_up") && areas.contains("skill"), "{:?}", areas);
+        let used = keys_used_in_code(&areas);
+        assert!(used.len() > 20, "only found {:?}", used);
+        for language in Language::ALL {
+            let missing: Vec<&String> = used.iter().filter(|key| !locale.has(language, key)).collect();
+            assert!(missing.is_empty(), "{:?} lacks {:?}", language, missing);
+        }
+    }
+
+    #[test]
+    fn test_catalogs_cover_each_other() {
+        let locale = locale(Language::Polish);
+        let base = |key: &str| {
+            let plural = ["one", "few", "many", "other"].iter().any(|form| key.ends_with(&format!(".{}", form)));
+            if plural { key.rsplit_once('.').unwrap().0.to_string() } else { key.to_string() }
+        };
+        for (language, other) in [(Language::Polish, Language::English), (Language::English, Language::Polish)] {
+            for key in locale.keys(language) {
+                assert!(locale.has(other, &base(key)), "{} is in {:?} but not {:?}", key, language, other);
+            }
+        }
+    }
+
+    #[test]
+    fn test_polish_plurals() {
+        let categories: Vec<PluralCategory> = [1, 2, 4, 5, 7, 12, 14, 22, 25, 0]
+            .into_iter()
+            .map(|count| Language::Polish.plural_category(count))
+            .collect();
+        use PluralCategory::*;
+        assert_eq!(categories, [One, Few, Few, Many, Many, Many, Many, Few, Many, Many]);
+
+        let locale = locale(Language::Polish);
+        assert_eq!(locale.plural("combat.perished", 7, &[]), "7 jednostek zginęło");
+        assert_eq!(locale.plural("combat.perished", 3, &[]), "3 jednostki zginęły");
+        assert_eq!(locale.plural("combat.perished", 1, &[]), "1 jednostka zginęła");
+    }
+
+    #[test]
+    fn test_runtime_switch_and_fallback() {
+        let mut locale = locale(Language::Polish);
+        assert_eq!(locale.text("action.magic"), "Czary");
+        locale.set_language(Language::English);
+        assert_eq!(locale.text("action.magic"), "Magic");
+        assert_eq!(locale.plural("combat.perished", 1, &[]), "1 perishes");
+        assert_eq!(locale.text("missing.message"), "missing.message");
+    }
+}
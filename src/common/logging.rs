@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Environment variable holding filter directives, e.g. `warn,audax::game::event_loop=trace`.
+pub const LOG_ENV: &str = "AUDAX_LOG";
+/// Same directives as `AUDAX_LOG`, one or more per line, `#` starts a comment.
+pub const LOG_CONFIG_FILE: &str = "log.conf";
+
+#[cfg(debug_assertions)]
+pub const DEFAULT_FILTER: &str = "info";
+#[cfg(not(debug_assertions))]
+pub const DEFAULT_FILTER: &str = "warn";
+
+pub fn parse_config(contents: &str) -> Option<String> {
+    let directives: Vec<&str> = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if directives.is_empty() {
+        None
+    } else {
+        Some(directives.join(","))
+    }
+}
+
+/// The environment wins over the config file, which wins over the build default.
+pub fn resolve_filter(from_env: Option<String>, from_file: Option<String>) -> String {
+    from_env
+        .filter(|filter| !filter.trim().is_empty())
+        .or(from_file)
+        .unwrap_or_else(|| DEFAULT_FILTER.to_string())
+}
+
+pub fn init() {
+    let from_file = fs::read_to_string(Path::new(LOG_CONFIG_FILE))
+        .ok()
+        .and_then(|contents| parse_config(&contents));
+    let filter = resolve_filter(env::var(LOG_ENV).ok(), from_file);
+    if env_logger::Builder::new().parse_filters(&filter).try_init().is_err() {
+        log::warn!("Logger already initialised, ignoring filter {}", filter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_skips_comments_and_blank_lines() {
+        let contents = "# quiet by default\nwarn\n\naudax::game::event_loop=trace # click tracing\n";
+        assert_eq!(parse_config(contents), Some("warn,audax::game::event_loop=trace".to_string()));
+    }
+
+    #[test]
+    fn test_parse_empty_config() {
+        assert_eq!(parse_config("# nothing here\n\n"), None);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let filter = resolve_filter(Some("debug".to_string()), Some("error".to_string()));
+        assert_eq!(filter, "debug");
+    }
+
+    #[test]
+    fn test_blank_env_falls_back_to_file() {
+        let filter = resolve_filter(Some(" ".to_string()), Some("error".to_string()));
+        assert_eq!(filter, "error");
+    }
+
+    #[test]
+    fn test_default_filter() {
+        assert_eq!(resolve_filter(None, None), DEFAULT_FILTER);
+    }
+}
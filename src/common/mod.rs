@@ -1,2 +1,7 @@
 pub mod display;
-pub mod io;
\ No newline at end of file
+pub mod io;
+pub mod locale;
+pub mod logging;
+pub mod rng;
+pub mod settings;
+pub mod sync;
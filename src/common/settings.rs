@@ -0,0 +1,391 @@
+use crate::common::locale::Locale;
+use crate::error::{Error, Result};
+use macroquad::input::KeyCode;
+use macroquad::window::Conf;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Languages the interface can be shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    Polish,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Polish, Language::English];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Polish => "Polski",
+            Language::English => "English",
+        }
+    }
+}
+
+/// How hard computer-controlled sides play.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "option.difficulty.easy",
+            Difficulty::Normal => "option.difficulty.normal",
+            Difficulty::Hard => "option.difficulty.hard",
+        }
+    }
+}
+
+/// Key names that may appear in `KeyBindings`.
+const KEYS: [(&str, KeyCode); 20] = [
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("A", KeyCode::A),
+    ("D", KeyCode::D),
+    ("W", KeyCode::W),
+    ("S", KeyCode::S),
+    ("Q", KeyCode::Q),
+    ("E", KeyCode::E),
+    ("Z", KeyCode::Z),
+    ("X", KeyCode::X),
+    ("Equal", KeyCode::Equal),
+    ("Minus", KeyCode::Minus),
+    ("KpAdd", KeyCode::KpAdd),
+    ("KpSubtract", KeyCode::KpSubtract),
+    ("Escape", KeyCode::Escape),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Backspace", KeyCode::Backspace),
+];
+
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    KEYS.iter().find(|(key, _)| *key == name).map(|(_, code)| *code)
+}
+
+/// Keys for each camera and menu action, by name; any of them triggers the action.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub pan_left: Vec<String>,
+    pub pan_right: Vec<String>,
+    pub pan_up: Vec<String>,
+    pub pan_down: Vec<String>,
+    pub zoom_in: Vec<String>,
+    pub zoom_out: Vec<String>,
+    /// Closes the spellbook or the System menu and drops a picked spell.
+    pub cancel: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            pan_left: keys(&["Left", "A"]),
+            pan_right: keys(&["Right", "D"]),
+            pan_up: keys(&["Up", "W"]),
+            pan_down: keys(&["Down", "S"]),
+            zoom_in: keys(&["KpAdd", "Equal"]),
+            zoom_out: keys(&["KpSubtract", "Minus"]),
+            cancel: keys(&["Escape"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    fn actions(&mut self) -> [(&'static str, &mut Vec<String>); 7] {
+        [
+            ("pan_left", &mut self.pan_left),
+            ("pan_right", &mut self.pan_right),
+            ("pan_up", &mut self.pan_up),
+            ("pan_down", &mut self.pan_down),
+            ("zoom_in", &mut self.zoom_in),
+            ("zoom_out", &mut self.zoom_out),
+            ("cancel", &mut self.cancel),
+        ]
+    }
+
+    /// Drops unknown key names; an action left without keys gets its default ones back.
+    fn validated(mut self) -> Self {
+        let mut defaults = KeyBindings::default();
+        for ((action, keys), (_, default)) in self.actions().into_iter().zip(defaults.actions()) {
+            keys.retain(|name| {
+                let known = key_code(name).is_some();
+                if !known {
+                    log::warn!("Unknown key {:?} bound to {}", name, action);
+                }
+                known
+            });
+            if keys.is_empty() {
+                *keys = default.clone();
+            }
+        }
+        self
+    }
+}
+
+/// One step of a setting as offered by the System menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingChange {
+    Resolution,
+    Fullscreen,
+    AnimationSpeed,
+    MusicVolume,
+    SoundVolume,
+    Language,
+    Difficulty,
+}
+
+impl SettingChange {
+    pub const ALL: [SettingChange; 7] = [
+        SettingChange::Resolution,
+        SettingChange::Fullscreen,
+        SettingChange::AnimationSpeed,
+        SettingChange::MusicVolume,
+        SettingChange::SoundVolume,
+        SettingChange::Language,
+        SettingChange::Difficulty,
+    ];
+}
+
+/// What the player can tune, kept in `Settings::PATH` between runs. Missing fields take
+/// their defaults and fields this build does not know are skipped, so older and newer
+/// files both load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    /// Multiplier for unit animations; 1.0 is the designed speed.
+    pub animation_speed: f32,
+    /// 0.0 is muted, 1.0 full volume.
+    pub music_volume: f32,
+    pub sound_volume: f32,
+    pub language: Language,
+    pub difficulty: Difficulty,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: Settings::RESOLUTIONS[0],
+            fullscreen: false,
+            animation_speed: 1.0,
+            music_volume: 0.8,
+            sound_volume: 0.8,
+            language: Language::default(),
+            difficulty: Difficulty::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub const PATH: &'static str = "settings.ron";
+    pub const WINDOW_TITLE: &'static str = "Audax";
+    pub const MIN_RESOLUTION: (u32, u32) = (320, 240);
+    pub const MAX_RESOLUTION: (u32, u32) = (7680, 4320);
+    pub const RESOLUTIONS: [(u32, u32); 4] = [(800, 600), (1024, 768), (1280, 720), (1920, 1080)];
+    pub const ANIMATION_SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+    pub const VOLUME_STEP: f32 = 0.2;
+
+    pub fn from_ron(source: &str) -> std::result::Result<Self, ron::error::SpannedError> {
+        ron::from_str::<Settings>(source).map(Settings::validated)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    /// The saved settings, or the defaults when there are none yet or they can't be read.
+    pub fn load_or_default(path: &str) -> Self {
+        match Self::load(path) {
+            Ok(settings) => settings,
+            Err(error) => {
+                log::info!("Using default settings: {}", error);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let source = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| Error::Serialization(error.to_string()))?;
+        fs::write(path, source).map_err(|error| Error::asset(path, error))
+    }
+
+    /// Brings every value back into its allowed range; broken ones fall back to the defaults.
+    pub fn validated(self) -> Self {
+        let defaults = Settings::default();
+        let (min, max) = (Settings::MIN_RESOLUTION, Settings::MAX_RESOLUTION);
+        let resolution = (self.resolution.0.clamp(min.0, max.0), self.resolution.1.clamp(min.1, max.1));
+        let animation_speed = if self.animation_speed.is_finite() {
+            let (slowest, fastest) = (Settings::ANIMATION_SPEEDS[0], Settings::ANIMATION_SPEEDS[3]);
+            self.animation_speed.clamp(slowest, fastest)
+        } else {
+            defaults.animation_speed
+        };
+        let volume = |volume: f32, default: f32| if volume.is_finite() { volume.clamp(0.0, 1.0) } else { default };
+        let validated = Self {
+            resolution,
+            animation_speed,
+            music_volume: volume(self.music_volume, defaults.music_volume),
+            sound_volume: volume(self.sound_volume, defaults.sound_volume),
+            key_bindings: self.key_bindings.clone().validated(),
+            ..self.clone()
+        };
+        if validated != self {
+            log::warn!("Some settings were out of range and have been adjusted");
+        }
+        validated
+    }
+
+    /// Window configuration for `#[macroquad::main]`.
+    pub fn conf(&self) -> Conf {
+        Conf {
+            window_title: Settings::WINDOW_TITLE.to_string(),
+            window_width: self.resolution.0 as i32,
+            window_height: self.resolution.1 as i32,
+            fullscreen: self.fullscreen,
+            window_resizable: true,
+            ..Default::default()
+        }
+    }
+
+    /// Moves `change` one step forward, wrapping around at the end of its range.
+    pub fn cycle(&mut self, change: SettingChange) {
+        match change {
+            SettingChange::Resolution => self.resolution = next(&Settings::RESOLUTIONS, &self.resolution),
+            SettingChange::Fullscreen => self.fullscreen = !self.fullscreen,
+            SettingChange::AnimationSpeed => {
+                self.animation_speed = next(&Settings::ANIMATION_SPEEDS, &self.animation_speed)
+            }
+            SettingChange::MusicVolume => self.music_volume = next_volume(self.music_volume),
+            SettingChange::SoundVolume => self.sound_volume = next_volume(self.sound_volume),
+            SettingChange::Language => self.language = next(&Language::ALL, &self.language),
+            SettingChange::Difficulty => self.difficulty = next(&Difficulty::ALL, &self.difficulty),
+        }
+    }
+
+    /// The menu line for `change`, e.g. "Resolution: 800x600".
+    pub fn describe(&self, change: SettingChange, locale: &Locale) -> String {
+        let percent = |volume: f32| vec![("percent", format!("{:.0}", volume * 100.0))];
+        let (key, args) = match change {
+            SettingChange::Resolution => (
+                "option.resolution",
+                vec![("width", self.resolution.0.to_string()), ("height", self.resolution.1.to_string())],
+            ),
+            SettingChange::Fullscreen => {
+                let value = locale.text(if self.fullscreen { "option.on" } else { "option.off" });
+                ("option.fullscreen", vec![("value", value)])
+            }
+            SettingChange::AnimationSpeed => {
+                ("option.animation_speed", vec![("speed", self.animation_speed.to_string())])
+            }
+            SettingChange::MusicVolume => ("option.music", percent(self.music_volume)),
+            SettingChange::SoundVolume => ("option.sound", percent(self.sound_volume)),
+            SettingChange::Language => ("option.language", vec![("language", self.language.name().to_string())]),
+            SettingChange::Difficulty => {
+                ("option.difficulty", vec![("difficulty", locale.text(self.difficulty.key()))])
+            }
+        };
+        locale.format(key, &args)
+    }
+}
+
+/// The option after `current`; the first one when `current` is last or not offered at all.
+fn next<T: Copy + PartialEq>(options: &[T], current: &T) -> T {
+    let position = options.iter().position(|option| option == current);
+    options[position.map_or(0, |position| (position + 1) % options.len())]
+}
+
+fn next_volume(volume: f32) -> f32 {
+    if volume >= 1.0 - f32::EPSILON {
+        0.0
+    } else {
+        (((volume / Settings::VOLUME_STEP).round() + 1.0) * Settings::VOLUME_STEP).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_wraps_around() {
+        let mut settings = Settings::default();
+        for _ in 0..Settings::RESOLUTIONS.len() {
+            settings.cycle(SettingChange::Resolution);
+        }
+        assert_eq!(settings.resolution, Settings::default().resolution);
+
+        settings.cycle(SettingChange::Language);
+        assert_eq!(settings.language, Language::English);
+        settings.cycle(SettingChange::Language);
+        assert_eq!(settings.language, Language::Polish);
+    }
+
+    #[test]
+    fn test_volume_steps_to_full_then_mutes() {
+        let mut settings = Settings::default();
+        settings.cycle(SettingChange::MusicVolume);
+        assert_eq!(settings.music_volume, 1.0);
+        settings.cycle(SettingChange::MusicVolume);
+        assert_eq!(settings.music_volume, 0.0);
+        settings.cycle(SettingChange::MusicVolume);
+        assert!((settings.music_volume - Settings::VOLUME_STEP).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_round_trip_through_ron() {
+        let mut settings = Settings::default();
+        settings.cycle(SettingChange::Fullscreen);
+        settings.cycle(SettingChange::Difficulty);
+        let source = ron::ser::to_string_pretty(&settings, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(Settings::from_ron(&source).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_missing_and_unknown_fields() {
+        let source = "(fullscreen: true, shadows: \"high\", key_bindings: (cancel: [\"Q\"]))";
+        let settings = Settings::from_ron(source).unwrap();
+        assert!(settings.fullscreen);
+        assert_eq!(settings.resolution, Settings::default().resolution);
+        assert_eq!(settings.key_bindings.cancel, vec!["Q".to_string()]);
+        assert_eq!(settings.key_bindings.pan_left, KeyBindings::default().pan_left);
+    }
+
+    #[test]
+    fn test_validation_clamps_and_restores_defaults() {
+        let settings = Settings::from_ron(
+            "(resolution: (10, 100000), animation_speed: 100.0, music_volume: -1.0, key_bindings: (cancel: [\"F13\"]))",
+        )
+        .unwrap();
+        assert_eq!(settings.resolution, (Settings::MIN_RESOLUTION.0, Settings::MAX_RESOLUTION.1));
+        assert_eq!(settings.animation_speed, 4.0);
+        assert_eq!(settings.music_volume, 0.0);
+        assert_eq!(settings.key_bindings.cancel, KeyBindings::default().cancel);
+    }
+
+    #[test]
+    fn test_unknown_value_restarts_at_first_option() {
+        let mut settings = Settings {
+            animation_speed: 3.0,
+            ..Settings::default()
+        };
+        settings.cycle(SettingChange::AnimationSpeed);
+        assert_eq!(settings.animation_speed, Settings::ANIMATION_SPEEDS[0]);
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks `mutex` on a path that has no `Result` to hand a poisoned lock back through: the
+/// renderer, the main loop and the event loop. A panic is logged where it happens and the
+/// board behind the lock is only ever swapped whole or changed a stack at a time, so carrying
+/// on with its data beats taking the game down; the poison is cleared so it is logged once.
+/// Code that returns `Result` keeps using `lock()?`, which turns poisoning into `Error::Channel`.
+pub fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        log::error!("Carrying on with a lock poisoned by a panic");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_poisoned_lock_is_recovered() {
+        let shared = Arc::new(Mutex::new(3));
+        let poisoner = Arc::clone(&shared);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join();
+        assert!(shared.is_poisoned());
+
+        *lock(&shared) += 1;
+        assert_eq!(*lock(&shared), 4);
+        assert!(!shared.is_poisoned());
+    }
+}
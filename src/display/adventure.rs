@@ -0,0 +1,377 @@
+use crate::common::display::texture::load_texture_sync;
+use crate::battle::Army;
+use crate::common::locale::Locale;
+use crate::error::Result;
+use crate::world::{Adventure, AdventureMap, MapObject, Resource, Resources, Terrain};
+use macroquad::color::{BLACK, BLUE, Color, DARKGRAY, GOLD, GRAY, GREEN, RED, WHITE};
+use macroquad::input::{MouseButton, is_mouse_button_pressed};
+use macroquad::math::{Rect, vec2};
+use macroquad::prelude::{
+    DrawTextureParams, Texture2D, draw_circle, draw_rectangle, draw_rectangle_lines, draw_text, draw_texture_ex,
+    mouse_position, screen_height, screen_width,
+};
+use macroquad::ui::{root_ui, widgets};
+
+/// What the player asked for on the adventure map this frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdventureAction {
+    /// Walk the selected hero towards the tile.
+    Move(usize),
+    EndDay,
+    /// Open the kingdom overview.
+    Kingdom,
+}
+
+/// What the player asked for on a town's screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TownAction {
+    Build(String),
+    /// Hire this many of the roster creature.
+    Recruit(String, u32),
+    Close,
+}
+
+/// Where the map and the status bar below it go on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapLayout {
+    pub tile_size: f32,
+    pub map: Rect,
+    pub status_bar: Rect,
+}
+
+impl MapLayout {
+    const STATUS_BAR_HEIGHT: f32 = 48.0;
+
+    /// Square tiles as large as fit into the window above the status bar.
+    pub fn new(map: &AdventureMap, width: f32, height: f32) -> Self {
+        let room = (height - Self::STATUS_BAR_HEIGHT).max(0.0);
+        let tile_size = f32::min(width / map.width() as f32, room / map.height() as f32);
+        Self {
+            tile_size,
+            map: Rect::new(0.0, 0.0, tile_size * map.width() as f32, tile_size * map.height() as f32),
+            status_bar: Rect::new(0.0, room, width, Self::STATUS_BAR_HEIGHT),
+        }
+    }
+
+    pub fn tile_rect(&self, map: &AdventureMap, index: usize) -> Rect {
+        let (row, col) = (index / map.width(), index % map.width());
+        Rect::new(
+            self.map.x + col as f32 * self.tile_size,
+            self.map.y + row as f32 * self.tile_size,
+            self.tile_size,
+            self.tile_size,
+        )
+    }
+
+    /// Tile under the screen point, `None` outside the map.
+    pub fn tile_at(&self, map: &AdventureMap, x: f32, y: f32) -> Option<usize> {
+        if !self.map.contains(vec2(x, y)) {
+            return None;
+        }
+        let col = ((x - self.map.x) / self.tile_size) as usize;
+        let row = ((y - self.map.y) / self.tile_size) as usize;
+        (row < map.height() && col < map.width()).then_some(row * map.width() + col)
+    }
+}
+
+/// Draws the adventure map with its heroes and turns clicks into `AdventureAction`s.
+pub struct AdventureRenderer {
+    forest: Texture2D,
+    town: Texture2D,
+}
+
+impl AdventureRenderer {
+    const FOREST_TEXTURE: &'static str = "data/graphics/general/forest.png";
+    const TOWN_TEXTURE: &'static str = "data/graphics/general/city.png";
+    const FONT_SIZE: f32 = 20.0;
+
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            forest: load_texture_sync(Self::FOREST_TEXTURE)?,
+            town: load_texture_sync(Self::TOWN_TEXTURE)?,
+        })
+    }
+
+    fn terrain_color(terrain: Terrain) -> Color {
+        match terrain {
+            Terrain::Grass => Color::new(0.45, 0.7, 0.3, 1.0),
+            Terrain::Road => Color::new(0.75, 0.65, 0.45, 1.0),
+            Terrain::Forest => Color::new(0.2, 0.45, 0.2, 1.0),
+            Terrain::Mountain => Color::new(0.5, 0.5, 0.5, 1.0),
+            Terrain::Water => Color::new(0.25, 0.45, 0.8, 1.0),
+        }
+    }
+
+    fn draw_texture(texture: &Texture2D, rect: Rect) {
+        draw_texture_ex(
+            texture,
+            rect.x,
+            rect.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(rect.size()),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn cost_text(cost: &Resources, locale: &Locale) -> String {
+        let parts: Vec<String> =
+            cost.iter().map(|(resource, amount)| format!("{} {}", amount, locale.text(resource.key()))).collect();
+        parts.join(", ")
+    }
+
+    /// A panel over the map for the town and kingdom screens.
+    fn panel(adventure: &Adventure) -> Rect {
+        let layout = MapLayout::new(&adventure.map, screen_width(), screen_height());
+        let panel = Rect::new(
+            layout.map.x + layout.map.w * 0.1,
+            layout.map.y + layout.map.h * 0.05,
+            layout.map.w * 0.8,
+            layout.map.h * 0.9,
+        );
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, Color::new(1.0, 1.0, 1.0, 0.95));
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, BLACK);
+        panel
+    }
+
+    fn close_button(panel: Rect, locale: &Locale) -> bool {
+        let row_height = Self::FONT_SIZE + 10.0;
+        widgets::Button::new(locale.text("ui.close"))
+            .position(vec2(panel.x + panel.w - 108.0, panel.y + panel.h - row_height - 4.0))
+            .size(vec2(100.0, row_height))
+            .ui(&mut root_ui())
+    }
+
+    /// Draws the kingdom overview: stockpile and daily income of every resource, the towns and
+    /// mines they come from and the heroes. Returns true once it is closed.
+    pub fn display_kingdom(&self, adventure: &Adventure, locale: &Locale) -> bool {
+        let panel = Self::panel(adventure);
+        let row_height = Self::FONT_SIZE + 10.0;
+        let mut y = panel.y + 8.0 + row_height * 0.7;
+        let mut line = |label: String, color: Color| {
+            draw_text(label, panel.x + 8.0, y, Self::FONT_SIZE, color);
+            y += row_height;
+        };
+        line(locale.text("ui.kingdom"), BLACK);
+        let income = adventure.daily_income();
+        for resource in Resource::ALL {
+            let args = [
+                ("resource", locale.text(resource.key())),
+                ("amount", adventure.resources().get(resource).to_string()),
+                ("income", income.get(resource).to_string()),
+            ];
+            line(locale.format("ui.kingdom.resource", &args), if resource.is_rare() { DARKGRAY } else { BLACK });
+        }
+        let towns: Vec<&str> =
+            adventure.towns().filter(|(_, town)| town.owned).map(|(_, town)| town.name.as_str()).collect();
+        line(locale.format("ui.kingdom.towns", &[("towns", towns.join(", "))]), BLACK);
+        line(locale.plural("ui.kingdom.mines", adventure.mines().count() as u32, &[]), BLACK);
+        let heroes: Vec<&str> = adventure.heroes().iter().map(|hero| hero.name()).collect();
+        line(locale.format("ui.kingdom.heroes", &[("heroes", heroes.join(", "))]), BLACK);
+        Self::close_button(panel, locale)
+    }
+
+    /// Draws the screen of the town on `tile` over the map: its build tree, creatures to hire
+    /// and the garrison.
+    pub fn display_town(&self, adventure: &Adventure, tile: usize, locale: &Locale) -> Option<TownAction> {
+        let town = adventure.town(tile)?;
+        let (faction, roster) = (adventure.faction(), adventure.roster());
+        let panel = Self::panel(adventure);
+        let row_height = Self::FONT_SIZE + 10.0;
+        let half = panel.w / 2.0 - 12.0;
+        let mut y = panel.y + 8.0;
+        let text = |label: &str, x: f32, y: f32, color: Color| {
+            draw_text(label, x, y + row_height * 0.7, Self::FONT_SIZE, color);
+        };
+        text(&town.name, panel.x + 8.0, y, BLACK);
+        y += row_height;
+
+        let mut action = None;
+        for building in &faction.buildings {
+            if town.is_built(&building.id) {
+                let label = locale.format("ui.town.built", &[("building", building.name.clone())]);
+                text(&label, panel.x + 8.0, y, DARKGRAY);
+            } else {
+                let label = locale.format(
+                    "ui.town.build",
+                    &[("building", building.name.clone()), ("cost", Self::cost_text(&building.cost, locale))],
+                );
+                let allowed = town.can_build(faction, &building.id).is_ok()
+                    && adventure.resources().affordable(&building.cost) > 0;
+                if !allowed {
+                    text(&label, panel.x + 8.0, y, GRAY);
+                } else if widgets::Button::new(label)
+                    .position(vec2(panel.x + 8.0, y))
+                    .size(vec2(half, row_height - 4.0))
+                    .ui(&mut root_ui())
+                {
+                    action = Some(TownAction::Build(building.id.clone()));
+                }
+            }
+            y += row_height;
+        }
+
+        let mut y = panel.y + 8.0 + row_height;
+        let right = panel.x + panel.w / 2.0 + 4.0;
+        for recruit in town.recruits(faction, roster) {
+            let name = roster.get(&recruit.unit).map_or(recruit.unit.clone(), |unit| unit.name.clone());
+            let available = town.available(&recruit.unit);
+            let label = locale.format(
+                "ui.town.recruit",
+                &[
+                    ("unit", name),
+                    ("available", available.to_string()),
+                    ("cost", Self::cost_text(&recruit.cost, locale)),
+                ],
+            );
+            text(&label, right, y, BLACK);
+            y += row_height;
+            let count = available.min(adventure.resources().affordable(&recruit.cost));
+            if count > 0
+                && widgets::Button::new(locale.plural("ui.town.hire", count, &[]))
+                    .position(vec2(right, y))
+                    .size(vec2(half / 2.0, row_height - 4.0))
+                    .ui(&mut root_ui())
+            {
+                action = Some(TownAction::Recruit(recruit.unit.clone(), count));
+            }
+            y += row_height;
+        }
+
+        let stacks = |army: &Army| {
+            let stacks: Vec<String> =
+                army.stacks().iter().map(|unit| format!("{} {}", unit.count, unit.name)).collect();
+            stacks.join(", ")
+        };
+        let mut y = panel.y + panel.h - row_height * 3.0;
+        text(&locale.format("ui.town.garrison", &[("army", stacks(&town.garrison))]), panel.x + 8.0, y, BLACK);
+        if let Some(hero) = adventure.hero_at(tile).map(|hero| &adventure.heroes()[hero]) {
+            y += row_height;
+            let args = [("hero", hero.name().to_string()), ("army", stacks(&hero.army))];
+            let label = locale.format("ui.town.visitor", &args);
+            text(&label, panel.x + 8.0, y, BLACK);
+        }
+        if Self::close_button(panel, locale) {
+            action = Some(TownAction::Close);
+        }
+        action
+    }
+
+    /// Draws one frame of the map and the status bar; `paused` ignores the mouse.
+    pub fn display(&self, adventure: &Adventure, locale: &Locale, paused: bool) -> Option<AdventureAction> {
+        let map = &adventure.map;
+        let layout = MapLayout::new(map, screen_width(), screen_height());
+        let size = layout.tile_size;
+        for index in 0..map.width() * map.height() {
+            let rect = layout.tile_rect(map, index);
+            let terrain = map.terrain(index).unwrap_or(Terrain::Mountain);
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, Self::terrain_color(terrain));
+            if terrain == Terrain::Forest {
+                Self::draw_texture(&self.forest, rect);
+            }
+        }
+        for (index, object) in map.objects() {
+            let rect = layout.tile_rect(map, index);
+            let center = rect.center();
+            match object {
+                MapObject::Town { name, .. } => {
+                    Self::draw_texture(&self.town, rect);
+                    let owned = adventure.town(index).is_some_and(|town| town.owned);
+                    draw_rectangle(rect.x, rect.y, size * 0.2, size * 0.2, if owned { BLUE } else { RED });
+                    draw_text(name, rect.x, rect.y, Self::FONT_SIZE, BLACK);
+                }
+                MapObject::Resource { amount, .. } => {
+                    draw_rectangle(rect.x + size * 0.25, rect.y + size * 0.35, size * 0.5, size * 0.3, GOLD);
+                    draw_text(amount.to_string(), rect.x + 2.0, rect.y + size - 2.0, Self::FONT_SIZE, BLACK);
+                }
+                MapObject::Mine { resource } => {
+                    draw_rectangle(rect.x + size * 0.15, rect.y + size * 0.15, size * 0.7, size * 0.7, DARKGRAY);
+                    let initial = locale.text(resource.key()).chars().next().unwrap_or('?');
+                    draw_text(initial.to_string(), center.x - 5.0, center.y + 6.0, Self::FONT_SIZE, GOLD);
+                    if adventure.mines().any(|(tile, _)| tile == index) {
+                        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, BLUE);
+                    }
+                }
+                MapObject::Monster { count, .. } => {
+                    draw_circle(center.x, center.y, size * 0.35, RED);
+                    draw_text(count.to_string(), rect.x + 2.0, rect.y + size - 2.0, Self::FONT_SIZE, WHITE);
+                }
+            }
+        }
+        let selected = adventure.selected().map(|hero| hero.position);
+        for hero in adventure.heroes() {
+            let rect = layout.tile_rect(map, hero.position);
+            let center = rect.center();
+            draw_circle(center.x, center.y, size * 0.3, BLUE);
+            if Some(hero.position) == selected {
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 3.0, WHITE);
+            }
+        }
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let hovered = layout.tile_at(map, mouse_x, mouse_y).filter(|_| !paused);
+        if let Some((path, reachable)) = hovered.and_then(|tile| adventure.planned_path(tile)) {
+            for (step, &index) in path.iter().enumerate() {
+                let center = layout.tile_rect(map, index).center();
+                draw_circle(center.x, center.y, size * 0.1, if step < reachable { GREEN } else { RED });
+            }
+        }
+
+        let bar = layout.status_bar;
+        draw_rectangle(bar.x, bar.y, bar.w, bar.h, DARKGRAY);
+        let calendar = adventure.calendar();
+        let date = [
+            ("month", calendar.month().to_string()),
+            ("week", calendar.week().to_string()),
+            ("day", calendar.day_of_week().to_string()),
+        ];
+        let mut status = vec![locale.format("ui.date", &date)];
+        if let Some(hero) = adventure.selected() {
+            status.push(locale.format(
+                "ui.movement",
+                &[("hero", hero.name().to_string()), ("movement", hero.movement.to_string())],
+            ));
+        }
+        status.push(Self::cost_text(adventure.resources(), locale));
+        draw_text(status.join("   "), bar.x + 8.0, bar.y + bar.h * 0.6, Self::FONT_SIZE, WHITE);
+
+        let button = vec2(bar.h * 3.0, bar.h - 8.0);
+        let end_day = widgets::Button::new(locale.text("ui.end_day"))
+            .position(vec2(bar.x + bar.w - button.x - 4.0, bar.y + 4.0))
+            .size(button)
+            .ui(&mut root_ui());
+        let kingdom = widgets::Button::new(locale.text("ui.kingdom"))
+            .position(vec2(bar.x + bar.w - 2.0 * (button.x + 4.0), bar.y + 4.0))
+            .size(button)
+            .ui(&mut root_ui());
+        if paused {
+            return None;
+        }
+        if end_day {
+            return Some(AdventureAction::EndDay);
+        }
+        if kingdom {
+            return Some(AdventureAction::Kingdom);
+        }
+        hovered
+            .filter(|_| is_mouse_button_pressed(MouseButton::Left))
+            .map(AdventureAction::Move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_at_maps_screen_points_to_tiles() {
+        let map = AdventureMap::from_ron(r#"(name: "x", terrain: ["....", "...."], starts: [])"#).unwrap();
+        let layout = MapLayout::new(&map, 400.0, 248.0);
+        assert_eq!(layout.tile_size, 100.0);
+        assert_eq!(layout.tile_at(&map, 150.0, 50.0), Some(1));
+        assert_eq!(layout.tile_at(&map, 399.0, 199.0), Some(7));
+        assert_eq!(layout.tile_at(&map, 150.0, 220.0), None);
+        assert_eq!(layout.tile_rect(&map, 5), Rect::new(100.0, 100.0, 100.0, 100.0));
+    }
+}
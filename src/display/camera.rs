@@ -0,0 +1,182 @@
+use macroquad::math::Rect;
+
+/// Zoom and pan applied on top of the grid placement computed by `BattleLayout`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    zoom: f32,
+    pan: (f32, f32),
+    drag_from: Option<(f32, f32)>,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            drag_from: None,
+        }
+    }
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.5;
+    pub const MAX_ZOOM: f32 = 4.0;
+    pub const ZOOM_STEP: f32 = 1.1;
+    /// Pixels per second for keyboard and edge panning.
+    pub const PAN_SPEED: f32 = 400.0;
+    /// Wheel notches per second while a zoom key is held.
+    pub const KEY_ZOOM_RATE: f32 = 10.0;
+    /// Distance from the window border that triggers edge panning.
+    pub const EDGE_SIZE: f32 = 4.0;
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn pan(&self) -> (f32, f32) {
+        self.pan
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn square_size(&self, base_square_size: f32) -> f32 {
+        base_square_size * self.zoom
+    }
+
+    pub fn grid_origin(&self, viewport: Rect) -> (f32, f32) {
+        (viewport.x + self.pan.0, viewport.y + self.pan.1)
+    }
+
+    /// Zooms by `steps` wheel notches keeping the grid point under `anchor` in place.
+    pub fn zoom_at(&mut self, steps: f32, anchor: (f32, f32), viewport: Rect) {
+        let zoom = (self.zoom * Self::ZOOM_STEP.powf(steps)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let factor = zoom / self.zoom;
+        let (origin_x, origin_y) = self.grid_origin(viewport);
+        let new_origin = (
+            anchor.0 - (anchor.0 - origin_x) * factor,
+            anchor.1 - (anchor.1 - origin_y) * factor,
+        );
+        self.zoom = zoom;
+        self.pan = (new_origin.0 - viewport.x, new_origin.1 - viewport.y);
+        self.clamp(viewport);
+    }
+
+    pub fn pan_by(&mut self, dx: f32, dy: f32, viewport: Rect) {
+        self.pan = (self.pan.0 + dx, self.pan.1 + dy);
+        self.clamp(viewport);
+    }
+
+    pub fn begin_drag(&mut self, x: f32, y: f32) {
+        self.drag_from = Some((x, y));
+    }
+
+    pub fn drag_to(&mut self, x: f32, y: f32, viewport: Rect) {
+        if let Some((from_x, from_y)) = self.drag_from {
+            self.pan_by(x - from_x, y - from_y, viewport);
+            self.drag_from = Some((x, y));
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag_from = None;
+    }
+
+    /// Edge panning direction for a mouse at `(x, y)` in a window of `width` x `height`.
+    pub fn edge_direction(x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        let axis = |position: f32, size: f32| {
+            if position <= Self::EDGE_SIZE {
+                1.0
+            } else if position >= size - Self::EDGE_SIZE {
+                -1.0
+            } else {
+                0.0
+            }
+        };
+        (axis(x, width), axis(y, height))
+    }
+
+    /// A zoomed-in grid must keep covering the viewport, a zoomed-out one must stay inside it.
+    fn clamp(&mut self, viewport: Rect) {
+        let grid_side = viewport.w * self.zoom;
+        let (low, high) = if grid_side >= viewport.w {
+            (viewport.w - grid_side, 0.0)
+        } else {
+            (0.0, viewport.w - grid_side)
+        };
+        self.pan = (self.pan.0.clamp(low, high), self.pan.1.clamp(low, high));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> Rect {
+        Rect::new(100.0, 50.0, 480.0, 480.0)
+    }
+
+    #[test]
+    fn test_zoom_keeps_anchor_in_place() {
+        let mut camera = Camera::default();
+        let anchor = (340.0, 290.0);
+        let (origin_x, _) = camera.grid_origin(viewport());
+        let grid_x = (anchor.0 - origin_x) / camera.square_size(40.0);
+
+        camera.zoom_at(3.0, anchor, viewport());
+
+        let (origin_x, _) = camera.grid_origin(viewport());
+        let zoomed_grid_x = (anchor.0 - origin_x) / camera.square_size(40.0);
+        assert!((grid_x - zoomed_grid_x).abs() < 1e-4);
+        assert!(camera.zoom() > 1.0);
+    }
+
+    #[test]
+    fn test_zoom_is_clamped() {
+        let mut camera = Camera::default();
+        camera.zoom_at(100.0, (0.0, 0.0), viewport());
+        assert_eq!(camera.zoom(), Camera::MAX_ZOOM);
+        camera.zoom_at(-100.0, (0.0, 0.0), viewport());
+        assert_eq!(camera.zoom(), Camera::MIN_ZOOM);
+    }
+
+    #[test]
+    fn test_pan_keeps_zoomed_grid_over_viewport() {
+        let mut camera = Camera::default();
+        camera.zoom_at(8.0, viewport().center().into(), viewport());
+        camera.pan_by(10_000.0, -10_000.0, viewport());
+
+        let grid_side = viewport().w * camera.zoom();
+        let (origin_x, origin_y) = camera.grid_origin(viewport());
+        assert_eq!(origin_x, viewport().x);
+        assert_eq!(origin_y + grid_side, viewport().bottom());
+    }
+
+    #[test]
+    fn test_unzoomed_grid_does_not_pan() {
+        let mut camera = Camera::default();
+        camera.pan_by(50.0, 50.0, viewport());
+        assert_eq!(camera.pan(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_drag_pans_by_mouse_delta() {
+        let mut camera = Camera::default();
+        camera.zoom_at(8.0, viewport().center().into(), viewport());
+        let before = camera.pan();
+        camera.begin_drag(300.0, 300.0);
+        camera.drag_to(290.0, 305.0, viewport());
+        camera.end_drag();
+        camera.drag_to(0.0, 0.0, viewport());
+
+        assert_eq!(camera.pan(), (before.0 - 10.0, before.1 + 5.0));
+    }
+
+    #[test]
+    fn test_edge_direction() {
+        assert_eq!(Camera::edge_direction(0.0, 300.0, 800.0, 600.0), (1.0, 0.0));
+        assert_eq!(Camera::edge_direction(799.0, 599.0, 800.0, 600.0), (-1.0, -1.0));
+        assert_eq!(Camera::edge_direction(400.0, 300.0, 800.0, 600.0), (0.0, 0.0));
+    }
+}
@@ -1,14 +1,44 @@
+use crate::battle::ability;
+use crate::battle::battlefield::{Battlefield, Cover};
+use crate::battle::combat::{self, settle_death};
+use crate::battle::footprint;
+use crate::battle::morale::{self, Roll};
+use crate::battle::status::{self, StatusEffect};
+use crate::battle::result::BattleResult;
+use crate::battle::save::{BattleSave, SavedTile};
+use crate::battle::scenario::Scenario;
+use crate::battle::siege::{self, Siege};
+use crate::battle::{Army, BattleEvent, BattleState, CombatLog, Phase, Roster, Side, Spell, Spellbook};
+use crate::hero::{Hero, LevelUp, SecondarySkill};
+use super::camera::Camera;
+use super::layout::{ActionAvailability, BattleAction, BattleLayout};
+use super::level_up;
+use super::system_menu::{MenuCommand, SystemMenu};
+use crate::common::locale::Locale;
+use crate::common::settings::{self, KeyBindings, Language, Settings};
 use super::tile::{Tile, TileType};
 use crate::common::display::WindowSize;
+use crate::common::rng::GameRng;
+use crate::common::sync;
 use crate::common::display::texture::load_texture_sync;
-use macroquad::color::{BLACK, RED, WHITE};
-use macroquad::prelude::{Texture2D, clear_background, draw_rectangle, draw_rectangle_lines, vec2};
+use crate::error::{Error, Result};
+use macroquad::color::{BLACK, DARKBROWN, DARKGRAY, GRAY, GREEN, RED, WHITE, Color};
+use macroquad::input::{
+    MouseButton, is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, is_mouse_button_released,
+    mouse_position, mouse_wheel,
+};
+use macroquad::math::Rect;
+use macroquad::prelude::{
+    DrawTextureParams, Texture2D, clear_background, draw_rectangle, draw_rectangle_lines, draw_text,
+    draw_texture_ex, measure_text, vec2,
+};
+use macroquad::shapes::draw_circle;
+use macroquad::time::get_frame_time;
 use macroquad::ui::{
     root_ui,
     widgets::{self},
 };
 use std::sync::{Arc, Mutex};
-use macroquad::shapes::draw_circle;
 use tile::Unit;
 
 use crate::display::tile;
@@ -24,6 +54,20 @@ struct BattleIcons {
     system: Texture2D,
 }
 
+impl BattleIcons {
+    fn get(&self, action: BattleAction) -> &Texture2D {
+        match action {
+            BattleAction::Magic => &self.magic,
+            BattleAction::Attack => &self.attack,
+            BattleAction::Defend => &self.defend,
+            BattleAction::Wait => &self.wait,
+            BattleAction::Run => &self.run,
+            BattleAction::Negotiate => &self.negotiate,
+            BattleAction::System => &self.system,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct GameState {
     pub tiles: Arc<Mutex<Vec<Tile>>>,
@@ -31,82 +75,202 @@ pub struct GameState {
 
 impl GameState {
     pub const GRID_SIZE: usize = 12;
+    pub const EMPTY_TEXTURE: &'static str = "data/graphics/general/empty_tail.png";
+    pub const OBSTACLE_TEXTURE: &'static str = "data/graphics/general/forest.png";
+    pub const ROCK_TEXTURE: &'static str = "data/graphics/general/rock.png";
+    /// Wall segments and the gate of a besieged town; the gate gets a frame on top.
+    pub const WALL_TEXTURE: &'static str = "data/graphics/general/city.png";
 }
 
 #[derive(Clone)]
 pub struct Board {
     window_size: WindowSize,
     pub game_state: GameState,
-    square_size: f32,
+    layout: BattleLayout,
+    camera: Camera,
+    active_index: Option<usize>,
+    combat_log: CombatLog,
+    battle_state: BattleState,
+    spellbook: Spellbook,
+    spellbook_open: bool,
+    system_menu: Option<SystemMenu>,
+    locale: Locale,
+    /// Set once the battle is over, until the caller takes it back to the map.
+    result: Option<BattleResult>,
+    pending_spell: Option<String>,
+    hero: Option<Hero>,
+    deployed: Vec<(Side, Unit)>,
+    /// The ground under the stacks, kept to draw its cover again after a load.
+    battlefield: Battlefield,
+    /// The town's walls when the battle is a siege.
+    siege: Option<Siege>,
+    war_machines: Roster,
     battle_icons: BattleIcons,
 }
 
 impl Board {
-    const SQUARE_SIZE: f32 = 50.0;
-    pub fn new(width: f32, height: f32) -> (Self, GameState) {
-        let square_size = Self::SQUARE_SIZE;
+    pub fn new(width: f32, height: f32) -> Result<(Self, GameState)> {
         let window_size = WindowSize {
             screen_width: width,
             screen_height: height,
         };
+        let layout = BattleLayout::new(&window_size);
+        // The classic field places no stacks, so it needs no creatures to load.
+        let battlefield = Scenario::load(Scenario::CLASSIC, &Roster::default())?.field().clone();
         let game_state = GameState {
-            tiles: Arc::new(Mutex::new(vec![
-                Tile::new(
-                    TileType::Empty,
-                    "data/graphics/general/empty_tail.png"
-                );
-                GameState::GRID_SIZE * GameState::GRID_SIZE
-            ])),
+            tiles: Arc::new(Mutex::new(Self::tiles_for(&battlefield)?)),
         };
         let battle_icons = BattleIcons {
-            attack: load_texture_sync("data/graphics/ui/battle/attack.png"),
-            defend: load_texture_sync("data/graphics/ui/battle/defence.png"),
-            magic: load_texture_sync("data/graphics/ui/battle/magic.png"),
-            wait: load_texture_sync("data/graphics/ui/battle/wait.png"),
-            run: load_texture_sync("data/graphics/general/hands.png"),
-            negotiate: load_texture_sync("data/graphics/general/hand.png"),
-            system: load_texture_sync("data/graphics/general/unit_defence.png"),
+            attack: load_texture_sync("data/graphics/ui/battle/attack.png")?,
+            defend: load_texture_sync("data/graphics/ui/battle/defence.png")?,
+            magic: load_texture_sync("data/graphics/ui/battle/magic.png")?,
+            wait: load_texture_sync("data/graphics/ui/battle/wait.png")?,
+            run: load_texture_sync("data/graphics/general/hands.png")?,
+            negotiate: load_texture_sync("data/graphics/general/hand.png")?,
+            system: load_texture_sync("data/graphics/general/unit_defence.png")?,
         };
-        (
+        Ok((
             Self {
                 window_size,
                 game_state: game_state.clone(),
-                square_size,
+                layout,
+                camera: Camera::default(),
+                active_index: None,
+                combat_log: CombatLog::new(),
+                battle_state: BattleState::new(),
+                spellbook: Spellbook::load(Spellbook::PATH)?,
+                spellbook_open: false,
+                system_menu: None,
+                locale: Locale::load(Locale::PATH, Language::default())?,
+                result: None,
+                pending_spell: None,
+                hero: None,
+                deployed: Vec::new(),
+                battlefield,
+                siege: None,
+                war_machines: Roster::load(Roster::WAR_MACHINES)?,
                 battle_icons,
             },
             game_state,
-        )
+        ))
+    }
+
+    /// Tiles of `field` with its cover as obstacles and its spawn points marked.
+    fn tiles_for(field: &Battlefield) -> Result<Vec<Tile>> {
+        let mut tiles = vec![
+            Tile::new(TileType::Empty, GameState::EMPTY_TEXTURE)?;
+            GameState::GRID_SIZE * GameState::GRID_SIZE
+        ];
+        let forest = Tile::new(TileType::Obstacle, GameState::OBSTACLE_TEXTURE)?;
+        let rock = Tile::new(TileType::Obstacle, GameState::ROCK_TEXTURE)?;
+        for (index, cover) in field.obstacles() {
+            tiles[index] = if cover == Cover::Rock { rock.clone() } else { forest.clone() };
+        }
+        for (index, tile) in tiles.iter_mut().enumerate() {
+            if field.spawn_point(index).is_some() {
+                tile.tile_type = TileType::SpawnPoint;
+            }
+        }
+        Ok(tiles)
+    }
+
+    /// Clears the board for the next battle, fought on `field`; the grid keeps its shared
+    /// storage, the battle draws from `rng`.
+    pub fn new_battle(&mut self, rng: GameRng, field: Battlefield) -> Result<()> {
+        let fresh = Self::tiles_for(&field)?;
+        let mut tiles = self.game_state.tiles.lock()?;
+        *tiles = fresh;
+        drop(tiles);
+        self.battlefield = field;
+        self.battle_state = BattleState::with_rng(rng);
+        self.combat_log = CombatLog::new();
+        self.hero = None;
+        self.deployed.clear();
+        self.active_index = None;
+        self.pending_spell = None;
+        self.spellbook_open = false;
+        self.system_menu = None;
+        self.result = None;
+        self.siege = None;
+        Ok(())
+    }
+
+    /// Clears the board for the hand-made battle `scenario` and puts its stacks on the field
+    /// under fresh ids; armies deployed afterwards take the spawn points left free.
+    pub fn start_scenario(&mut self, rng: GameRng, scenario: &Scenario) -> Result<()> {
+        self.new_battle(rng, scenario.field().clone())?;
+        for stack in scenario.stacks() {
+            let id = Self::next_unit_id(&self.game_state.tiles.lock()?);
+            let unit = Unit { id, ..stack.unit.clone() };
+            let (row, col) = (stack.anchor / GameState::GRID_SIZE, stack.anchor % GameState::GRID_SIZE);
+            self.place_unit(row, col, unit, stack.side.tile_type())?;
+        }
+        Ok(())
+    }
+
+    /// Turns the fresh field into a siege: walls with a gate in front of the defenders, an
+    /// arrow tower at either end and a catapult behind the attackers. Call before deploying.
+    pub fn fortify(&mut self) -> Result<()> {
+        let wall = Tile::new(TileType::Wall, GameState::WALL_TEXTURE)?;
+        let gate = Tile::new(TileType::Gate, GameState::WALL_TEXTURE)?;
+        let mut tiles = self.game_state.tiles.lock()?;
+        self.siege = Some(Siege::build(&mut tiles, &wall, &gate)?);
+        let mut machines: Vec<(usize, &str, TileType)> =
+            Siege::tower_tiles().map(|index| (index, "arrow_tower", TileType::EnemyUnit)).collect();
+        machines.push((Siege::CATAPULT_TILE, "catapult", TileType::MyUnit));
+        for (index, kind, tile_type) in machines {
+            let unit = self.war_machines.spawn(kind, Self::next_unit_id(&tiles), 1)?;
+            if !footprint::fits(&tiles, index, unit.size, None) {
+                return Err(Error::rules(format!("No room for the {} on tile {}", unit.name, index)));
+            }
+            // War machines stay out of `deployed`: they are no one's army after the battle.
+            footprint::place(&mut tiles, index, unit, tile_type);
+        }
+        Ok(())
+    }
+
+    pub fn siege(&self) -> Option<&Siege> {
+        self.siege.as_ref()
+    }
+
+    /// First id no stack on `tiles` uses yet.
+    fn next_unit_id(tiles: &[Tile]) -> usize {
+        tiles
+            .iter()
+            .filter_map(|tile| tile.get_unit().map(|unit| unit.id + 1))
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn square_size(&self) -> f32 {
+        self.camera.square_size(self.layout.square_size)
     }
 
     fn calculate_grid_size(&self) -> (f32, f32) {
-        let grid_width = GameState::GRID_SIZE as f32 * self.square_size;
-        let grid_height = GameState::GRID_SIZE as f32 * self.square_size;
-        return (grid_width, grid_height);
+        let grid_width = GameState::GRID_SIZE as f32 * self.square_size();
+        let grid_height = GameState::GRID_SIZE as f32 * self.square_size();
+        (grid_width, grid_height)
     }
 
-    fn calculate_offset(&self, grid_width: f32, grid_height: f32) -> (f32, f32) {
-        let offset_x = (self.window_size.screen_width - grid_width) / 2.0;
-        let offset_y = (self.window_size.screen_height - grid_height) / 2.0;
-        return (offset_x, offset_y);
+    fn calculate_offset(&self) -> (f32, f32) {
+        self.camera.grid_origin(self.layout.grid_rect())
     }
 
+    /// True when `(x, y)` hits a tile that is visible through the grid viewport.
     pub fn check_if_is_in_boundries(&self, x: f32, y: f32) -> bool {
         let (grid_width, grid_height) = self.calculate_grid_size();
-        let (offset_x, offset_y) = self.calculate_offset(grid_width, grid_height);
+        let (offset_x, offset_y) = self.calculate_offset();
 
-        if x < offset_x || x > offset_x + grid_width || y < offset_y || y > offset_y + grid_height {
-            return false;
-        }
-        return true;
+        self.layout.grid_rect().contains(vec2(x, y))
+            && !(x < offset_x || x > offset_x + grid_width || y < offset_y || y > offset_y + grid_height)
     }
     pub fn get_tile_index(&self, x: f32, y: f32) -> Option<usize> {
         if !self.check_if_is_in_boundries(x, y) {
             return None;
         }
-        let (grid_width, grid_height) = self.calculate_grid_size();
-        let (offset_x, offset_y) = self.calculate_offset(grid_width, grid_height);
-        let col = ((x - offset_x) / self.square_size) as usize;
-        let row = ((y - offset_y) / self.square_size) as usize;
+        let (offset_x, offset_y) = self.calculate_offset();
+        let col = (((x - offset_x) / self.square_size()) as usize).min(GameState::GRID_SIZE - 1);
+        let row = (((y - offset_y) / self.square_size()) as usize).min(GameState::GRID_SIZE - 1);
         Some(row * GameState::GRID_SIZE + col)
     }
 
@@ -120,7 +284,7 @@ impl Board {
     }
 
     pub fn get_tile_by_index(&self, row: usize, col: usize) -> Option<Tile> {
-        let tiles = self.game_state.tiles.lock().unwrap();
+        let tiles = sync::lock(&self.game_state.tiles);
         tiles.get(row * GameState::GRID_SIZE + col).cloned()
     }
 
@@ -130,155 +294,1019 @@ impl Board {
             screen_height: height,
         };
 
-        let target_grid_size = f32::min(width, height) * 0.8;
-        self.square_size = target_grid_size / GameState::GRID_SIZE as f32;
+        self.layout = BattleLayout::new(&self.window_size);
+        self.camera.reset();
+    }
+
+    pub fn layout(&self) -> &BattleLayout {
+        &self.layout
+    }
+
+    /// Marks the unit on `index` as the one whose actions the HUD offers.
+    pub fn set_active_tile(&mut self, index: Option<usize>) {
+        self.active_index = index;
+    }
+
+    pub fn action_availability(&self) -> ActionAvailability {
+        if self.phase() == Phase::Tactics {
+            return ActionAvailability::default();
+        }
+        let tiles = sync::lock(&self.game_state.tiles);
+        ActionAvailability::from_tiles(&tiles, self.active_index).with_casting(self.can_cast_any())
+    }
+
+    /// Units on the board in the order they act: those still to act this round first,
+    /// then fastest first, ties broken by id.
+    pub fn turn_order(&self) -> Vec<Unit> {
+        let tiles = sync::lock(&self.game_state.tiles);
+        let mut units: Vec<Unit> = tiles.iter().filter_map(|tile| tile.get_unit().cloned()).collect();
+        units.sort_by(|a, b| {
+            self.battle_state
+                .has_acted(a.id)
+                .cmp(&self.battle_state.has_acted(b.id))
+                .then(b.effective_move_range().cmp(&a.effective_move_range()))
+                .then(a.id.cmp(&b.id))
+        });
+        units
+    }
+
+    pub fn battle_state(&self) -> &BattleState {
+        &self.battle_state
+    }
+
+    /// Ends the turn of `unit_id` unless high morale grants it another one; once every player
+    /// stack has acted a new round starts.
+    pub fn finish_turn(&mut self, unit_id: usize) -> Result<()> {
+        let tiles = self.game_state.tiles.clone();
+        let mut tiles = tiles.lock()?;
+        let inspired = tiles.iter().find_map(|tile| tile.get_unit().filter(|unit| unit.id == unit_id));
+        if let Some(unit) = inspired
+            && morale::roll_morale(unit, self.battle_state.rng_mut()) == Roll::Good
+            && self.battle_state.grant_extra_turn(unit_id)
+        {
+            self.combat_log.push(BattleEvent::Inspired {
+                unit: unit.name.clone(),
+                count: unit.count,
+            });
+            return Ok(());
+        }
+        let mut round_over = self.battle_state.unit_acted(unit_id, &Self::player_units(&tiles));
+        while round_over {
+            self.start_round(&mut tiles);
+            let player_units = Self::player_units(&tiles);
+            round_over = !player_units.is_empty() && self.battle_state.close_round_if_done(&player_units);
+        }
+        Ok(())
+    }
+
+    fn player_units(tiles: &[Tile]) -> Vec<usize> {
+        tiles
+            .iter()
+            .filter(|tile| tile.tile_type == TileType::MyUnit)
+            .filter_map(|tile| tile.get_unit().map(|unit| unit.id))
+            .collect()
+    }
+
+    /// Status effects count down, their turn-start triggers run, round abilities refresh,
+    /// arrow towers take their shot and player stacks with low morale may freeze for the round.
+    fn start_round(&mut self, tiles: &mut [Tile]) {
+        self.combat_log.push(BattleEvent::RoundStarted {
+            round: self.battle_state.round(),
+        });
+        for index in 0..tiles.len() {
+            let Some(unit) = tiles[index].unit.as_mut() else {
+                continue;
+            };
+            let mut events = status::start_round(unit);
+            events.extend(settle_death(tiles, index));
+            for event in events {
+                self.combat_log.push(event);
+            }
+        }
+        for event in ability::start_round(tiles) {
+            self.combat_log.push(event);
+        }
+        let (fired, events) = siege::fire_towers(tiles, self.battle_state.rng_mut());
+        for event in events {
+            self.combat_log.push(event);
+        }
+        for tower in fired {
+            self.battle_state.skip_turn(tower);
+        }
+        for tile in tiles.iter().filter(|tile| tile.tile_type == TileType::MyUnit) {
+            let Some(unit) = tile.get_unit() else {
+                continue;
+            };
+            if morale::roll_morale(unit, self.battle_state.rng_mut()) == Roll::Bad {
+                self.battle_state.skip_turn(unit.id);
+                self.combat_log.push(BattleEvent::Frozen {
+                    unit: unit.name.clone(),
+                    count: unit.count,
+                });
+            }
+        }
+    }
+
+    pub fn spellbook(&self) -> &Spellbook {
+        &self.spellbook
+    }
+
+    /// Spells the player can pick: the hero's own, or the whole spellbook without a hero.
+    pub fn available_spells(&self) -> Vec<&Spell> {
+        self.spellbook
+            .spells()
+            .iter()
+            .filter(|spell| self.hero.as_ref().is_none_or(|hero| hero.knows_spell(&spell.id)))
+            .collect()
+    }
+
+    fn can_cast_any(&self) -> bool {
+        self.available_spells()
+            .iter()
+            .any(|spell| self.battle_state.can_cast(Side::Player, spell.mana_cost))
+    }
+
+    pub fn hero(&self) -> Option<&Hero> {
+        self.hero.as_ref()
+    }
+
+    /// Puts `hero` in command: its mana becomes the player's, its stats and artifacts reach every stack.
+    pub fn set_hero(&mut self, hero: Hero) -> Result<()> {
+        self.battle_state.set_mana(Side::Player, hero.mana);
+        let mut tiles = self.game_state.tiles.lock()?;
+        for tile in tiles.iter_mut().filter(|tile| tile.tile_type == TileType::MyUnit) {
+            if let Some(unit) = tile.unit.as_mut() {
+                for effect in hero.effects_for(unit) {
+                    unit.add_effect(effect);
+                }
+            }
+        }
+        self.hero = Some(hero);
+        Ok(())
+    }
+
+    pub fn is_spellbook_open(&self) -> bool {
+        self.spellbook_open
+    }
+
+    pub fn open_spellbook(&mut self) {
+        self.spellbook_open = true;
+        self.pending_spell = None;
+    }
+
+    pub fn close_spellbook(&mut self) {
+        self.spellbook_open = false;
+    }
+
+    /// The spell waiting for a target tile, picked from the spellbook.
+    pub fn pending_spell(&self) -> Option<&str> {
+        self.pending_spell.as_deref()
+    }
+
+    pub fn select_spell(&mut self, spell_id: Option<String>) {
+        self.pending_spell = spell_id;
+        self.spellbook_open = false;
+    }
+
+    pub fn cast_spell(&mut self, spell_id: &str, target: usize) -> Result<()> {
+        self.pending_spell = None;
+        let spell = self
+            .available_spells()
+            .into_iter()
+            .find(|spell| spell.id == spell_id)
+            .ok_or_else(|| Error::rules(format!("Unknown spell {}", spell_id)))?;
+        if !self.battle_state.can_cast(Side::Player, spell.mana_cost) {
+            return Err(Error::rules(format!("{} can't be cast now", spell.name)));
+        }
+        let power = self.hero.as_ref().map_or(0, |hero| hero.stats().spell_power);
+        let mut tiles = self.game_state.tiles.lock()?;
+        let events = spell.cast(Side::Player, power, target, &mut tiles)?;
+        self.battle_state.spend_cast(Side::Player, spell.mana_cost);
+        for event in events {
+            self.combat_log.push(event);
+        }
+        Ok(())
     }
 
     pub fn reset_back_light_all_tiles(&mut self) {
-        let mut tiles = self.game_state.tiles.lock().unwrap();
+        let mut tiles = sync::lock(&self.game_state.tiles);
         for tile in tiles.iter_mut() {
             tile.back_light = false;
         }
     }
 
-    pub fn add_unit(&mut self, row: usize, col: usize, unit: Unit) {
-        let mut tiles = self.game_state.tiles.lock().unwrap();
-        let tile = tiles.get_mut(row * GameState::GRID_SIZE + col).unwrap();
-        tile.set_unit(unit, TileType::MyUnit);
+    /// Places every stack of `army` on the spawn points of `side` under fresh ids;
+    /// the player's hero takes command, an enemy hero only lends its stats and mana.
+    pub fn deploy(&mut self, side: Side, army: Army) -> Result<()> {
+        let morale = army.morale_effect();
+        let (hero, stacks) = army.into_parts();
+        let (targets, next_id) = {
+            let tiles = self.game_state.tiles.lock()?;
+            let next_id = Self::next_unit_id(&tiles);
+            let sizes: Vec<usize> = stacks.iter().map(|unit| unit.size).collect();
+            (Army::deployment(&tiles, side, &sizes)?, next_id)
+        };
+        if let (Side::Player, Some(hero)) = (side, &hero) {
+            self.set_hero(hero.clone())?;
+        }
+        for (offset, (index, unit)) in targets.into_iter().zip(stacks).enumerate() {
+            let mut unit = Unit {
+                id: next_id + offset,
+                ..unit
+            };
+            if let Some(effect) = &morale {
+                unit.add_effect(effect.clone());
+            }
+            if let (Side::Enemy, Some(hero)) = (side, &hero) {
+                for effect in hero.effects_for(&unit) {
+                    unit.add_effect(effect);
+                }
+            }
+            self.place_unit(index / GameState::GRID_SIZE, index % GameState::GRID_SIZE, unit, side.tile_type())?;
+        }
+        if let (Side::Enemy, Some(hero)) = (side, &hero) {
+            self.battle_state.set_mana(Side::Enemy, hero.mana);
+        }
+        Ok(())
+    }
+
+    /// Hands the battle its random source: the same generator and the same actions replay
+    /// the same battle.
+    pub fn set_rng(&mut self, rng: GameRng) {
+        *self.battle_state.rng_mut() = rng;
     }
 
-    pub fn move_unit(&mut self, index: usize, unit_id: usize) {
-        let mut tiles = self.game_state.tiles.lock().unwrap();
-        if let Some(tile) = tiles.iter_mut().find(|x| {
-            if let Some(unit) = &x.unit {
-                unit.id == unit_id
-            } else {
-                false
+    pub fn phase(&self) -> Phase {
+        self.battle_state.phase()
+    }
+
+    /// Opens the tactics phase when the player's hero knows Tactics; returns whether it did.
+    pub fn begin_tactics(&mut self) -> bool {
+        let has_tactics = self
+            .hero
+            .as_ref()
+            .is_some_and(|hero| hero.skill_level(SecondarySkill::Tactics).is_some());
+        has_tactics && self.battle_state.begin_tactics()
+    }
+
+    pub fn end_tactics(&mut self) {
+        self.battle_state.end_tactics();
+    }
+
+    /// Spawn points the player stack on `from` may move to during tactics.
+    pub fn redeploy_targets(&self, from: usize) -> Vec<usize> {
+        let tiles = sync::lock(&self.game_state.tiles);
+        let Some(unit) = tiles.get(from).and_then(|tile| tile.get_unit()) else {
+            return Vec::new();
+        };
+        (0..tiles.len())
+            .filter(|&index| index != from)
+            .filter(|&index| Army::fits_spawn_zone(&tiles, Side::Player, index, unit.size, Some(unit.id)))
+            .collect()
+    }
+
+    /// Moves a player stack to another spawn point without spending its turn.
+    pub fn redeploy(&mut self, from: usize, to: usize) -> Result<()> {
+        if self.phase() != Phase::Tactics {
+            return Err(Error::rules("Stacks can only be rearranged during tactics"));
+        }
+        let mut tiles = self.game_state.tiles.lock()?;
+        let unit = tiles
+            .get(from)
+            .filter(|tile| tile.tile_type == TileType::MyUnit)
+            .and_then(|tile| tile.get_unit())
+            .ok_or_else(|| Error::rules(format!("No player stack on tile {}", from)))?;
+        if !Army::fits_spawn_zone(&tiles, Side::Player, to, unit.size, Some(unit.id)) {
+            return Err(Error::rules(format!("{} doesn't fit on spawn point {}", unit.name, to)));
+        }
+        let unit = footprint::lift(&mut tiles, from)
+            .ok_or_else(|| Error::rules(format!("No stack on tile {}", from)))?;
+        footprint::place(&mut tiles, to, unit, TileType::MyUnit);
+        Ok(())
+    }
+
+    pub fn add_unit(&mut self, row: usize, col: usize, unit: Unit) -> Result<()> {
+        self.place_unit(row, col, unit, TileType::MyUnit)
+    }
+
+    pub fn add_enemy_unit(&mut self, row: usize, col: usize, unit: Unit) -> Result<()> {
+        self.place_unit(row, col, unit, TileType::EnemyUnit)
+    }
+
+    fn place_unit(&mut self, row: usize, col: usize, unit: Unit, tile_type: TileType) -> Result<()> {
+        if row >= GameState::GRID_SIZE || col >= GameState::GRID_SIZE {
+            return Err(Error::rules(format!("Tile {}, {} is outside the board", row, col)));
+        }
+        let mut tiles = self.game_state.tiles.lock()?;
+        let index = row * GameState::GRID_SIZE + col;
+        if !footprint::fits(&tiles, index, unit.size, None) {
+            return Err(Error::rules(format!("Tile {}, {} is already occupied", row, col)));
+        }
+        let side = Side::from_tile_type(&tile_type).ok_or_else(|| Error::rules("Units belong to a side"))?;
+        self.deployed.push((side, unit.clone()));
+        let mut unit = unit;
+        if let (Side::Player, Some(hero)) = (side, &self.hero) {
+            for effect in hero.effects_for(&unit) {
+                unit.add_effect(effect);
             }
-        }) {
-            println!("Znaleziono: {}", unit_id);
-            let unit = tile.unit.take(); // używamy take() zamiast clone()
-            tile.tile_type = TileType::Empty;
-            if let Some(target_tile) = tiles.get_mut(index) {
-                target_tile.unit = unit;
-                target_tile.tile_type = TileType::MyUnit;
+        }
+        footprint::place(&mut tiles, index, unit, tile_type);
+        Ok(())
+    }
+
+    pub fn combat_log(&self) -> &CombatLog {
+        &self.combat_log
+    }
+
+    pub fn record(&mut self, event: BattleEvent) {
+        self.combat_log.push(event);
+    }
+
+    /// `Some(true)` once the enemy has no stacks left, `Some(false)` once we have none,
+    /// `None` while both sides still fight. War machines alone don't hold a field.
+    pub fn victory(&self) -> Option<bool> {
+        let tiles = sync::lock(&self.game_state.tiles);
+        let has_units = |tile_type: TileType| {
+            tiles
+                .iter()
+                .any(|tile| tile.tile_type == tile_type && tile.get_unit().is_some_and(|unit| !unit.is_war_machine()))
+        };
+        match (has_units(TileType::MyUnit), has_units(TileType::EnemyUnit)) {
+            (true, true) => None,
+            (_, false) => Some(true),
+            (false, true) => Some(false),
+        }
+    }
+
+    /// Wraps up the battle: losses and experience, with the hero taking its share.
+    pub fn finish_battle(&mut self, victory: bool) -> Result<BattleResult> {
+        let tiles = self.game_state.tiles.lock()?;
+        let result = BattleResult::from_tiles(victory, &self.deployed, &tiles);
+        drop(tiles);
+        if let Some(hero) = self.hero.as_mut() {
+            let levels = hero.finish_battle(&result, self.battle_state.mana(Side::Player));
+            if levels > 0 {
+                log::info!("{} reached level {}", hero.name, hero.level);
             }
-        } else {
-            println!("Nie znaleziono pasującego elementu.");
         }
+        self.result = Some(result.clone());
+        Ok(result)
+    }
+
+    /// The hero's oldest level-up still waiting for a skill, once the battle is over.
+    pub fn level_up(&self) -> Option<&LevelUp> {
+        self.result.as_ref()?;
+        self.hero.as_ref()?.pending_level_ups().first()
+    }
+
+    /// Gives the hero the skill at `choice` of the level-up on screen.
+    pub fn choose_skill(&mut self, choice: usize) -> Result<()> {
+        self.hero
+            .as_mut()
+            .ok_or_else(|| Error::rules("No hero to level up"))?
+            .choose_skill(choice)
+    }
+
+    /// The outcome of the finished battle, handed out once.
+    pub fn take_result(&mut self) -> Option<BattleResult> {
+        self.result.take()
+    }
+
+    /// Snapshot of the whole battle for `BattleSave::write_to`.
+    pub fn save(&self) -> Result<BattleSave> {
+        let tiles = self.game_state.tiles.lock()?;
+        Ok(BattleSave {
+            tiles: tiles.iter().map(SavedTile::from).collect(),
+            battle_state: self.battle_state.clone(),
+            hero: self.hero.clone(),
+            deployed: self.deployed.clone(),
+            combat_log: self.combat_log.events().to_vec(),
+            battlefield: self.battlefield.clone(),
+            siege: self.siege.clone(),
+        })
+    }
+
+    /// Replaces the battle in place with `save`; the grid keeps its shared storage so the
+    /// event loop sees the loaded stacks straight away.
+    pub fn restore(&mut self, save: BattleSave) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        if save.tiles.len() != tiles.len() {
+            return Err(Error::Serialization(format!(
+                "save has {} tiles, the board {}",
+                save.tiles.len(),
+                tiles.len()
+            )));
+        }
+        let empty = Tile::new(TileType::Empty, GameState::EMPTY_TEXTURE)?;
+        let obstacle = Tile::new(TileType::Obstacle, GameState::OBSTACLE_TEXTURE)?;
+        let rock = Tile::new(TileType::Obstacle, GameState::ROCK_TEXTURE)?;
+        let wall = Tile::new(TileType::Wall, GameState::WALL_TEXTURE)?;
+        for (index, (tile, saved)) in tiles.iter_mut().zip(save.tiles).enumerate() {
+            let template = match saved.tile_type {
+                TileType::Obstacle if save.battlefield.cover(index) == Cover::Rock => &rock,
+                TileType::Obstacle => &obstacle,
+                TileType::Wall | TileType::Gate => &wall,
+                _ => &empty,
+            };
+            *tile = saved.into_tile(template);
+        }
+        drop(tiles);
+        self.battle_state = save.battle_state;
+        self.hero = save.hero;
+        self.deployed = save.deployed;
+        self.battlefield = save.battlefield;
+        self.siege = save.siege;
+        self.combat_log = CombatLog::new();
+        for event in save.combat_log {
+            self.combat_log.push(event);
+        }
+        self.active_index = None;
+        self.pending_spell = None;
+        self.spellbook_open = false;
+        Ok(())
+    }
+
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Switches every label and log line, including the ones already shown.
+    pub fn set_language(&mut self, language: Language) {
+        self.locale.set_language(language);
+    }
+
+    /// True while the System menu or a level-up is on screen and the battle takes no input.
+    pub fn is_paused(&self) -> bool {
+        self.system_menu.is_some() || self.level_up().is_some()
+    }
+
+    pub fn system_menu_mut(&mut self) -> Option<&mut SystemMenu> {
+        self.system_menu.as_mut()
+    }
+
+    pub fn open_system_menu(&mut self) {
+        self.system_menu.get_or_insert_with(SystemMenu::new);
+    }
+
+    pub fn close_system_menu(&mut self) {
+        self.system_menu = None;
+    }
+
+    pub fn active_unit(&self) -> Option<Unit> {
+        let tiles = sync::lock(&self.game_state.tiles);
+        self.active_index
+            .and_then(|index| tiles.get(index))
+            .and_then(|tile| tile.get_unit().cloned())
+    }
+
+    pub fn attack_unit(&mut self, attacker_index: usize, defender_index: usize) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        let attacker_id = tiles
+            .get(attacker_index)
+            .and_then(|tile| tile.get_unit().map(|unit| unit.id))
+            .ok_or_else(|| Error::rules(format!("No attacker on tile {}", attacker_index)))?;
+        let events = combat::attack(&mut tiles, attacker_index, defender_index, self.battle_state.rng_mut())?;
+        drop(tiles);
+
+        for event in events {
+            self.combat_log.push(event);
+        }
+        self.finish_turn(attacker_id)
+    }
+
+    /// The stack on `attacker_index` batters the wall segment or gate on `target`.
+    pub fn attack_wall(&mut self, attacker_index: usize, target: usize) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        let attacker = tiles
+            .get(attacker_index)
+            .and_then(|tile| tile.get_unit().cloned())
+            .ok_or_else(|| Error::rules(format!("No attacker on tile {}", attacker_index)))?;
+        let damage = attacker
+            .siege_damage()
+            .ok_or_else(|| Error::rules(format!("{} can't batter walls", attacker.name)))?;
+        let siege = self.siege.as_mut().ok_or_else(|| Error::rules("There are no walls in a field battle"))?;
+        let rubble = Tile::new(TileType::Empty, GameState::EMPTY_TEXTURE)?;
+        let events = siege.hit(&mut tiles, target, &attacker.name, damage * attacker.count, &rubble)?;
+        drop(tiles);
+
+        for event in events {
+            self.combat_log.push(event);
+        }
+        self.finish_turn(attacker.id)
+    }
+
+    /// The Defend action: the stack braces itself until the next round starts.
+    pub fn defend(&mut self, unit_id: usize) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        let unit = tiles
+            .iter_mut()
+            .find_map(|tile| tile.unit.as_mut().filter(|unit| unit.id == unit_id))
+            .ok_or_else(|| Error::rules(format!("Unit {} not found on the board", unit_id)))?;
+        unit.add_effect(StatusEffect::defend(unit.defence));
+        let event = BattleEvent::Defended {
+            unit: unit.name.clone(),
+            count: unit.count,
+        };
+        drop(tiles);
+        self.combat_log.push(event);
+        Ok(())
+    }
+
+    /// Moves the stack `unit_id` so that its anchor lands on `index`; the whole footprint follows.
+    pub fn move_unit(&mut self, index: usize, unit_id: usize) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        if index >= tiles.len() {
+            return Err(Error::rules(format!("No tile {}", index)));
+        }
+        let from = tiles
+            .iter()
+            .position(|tile| tile.get_unit().is_some_and(|unit| unit.id == unit_id))
+            .ok_or_else(|| Error::rules(format!("Unit {} not found on the board", unit_id)))?;
+        log::debug!("Moving unit {} to tile {}", unit_id, index);
+        let unit = footprint::relocate(&mut tiles, from, index)
+            .ok_or_else(|| Error::rules(format!("Unit {} not found on the board", unit_id)))?;
+        self.combat_log.push(BattleEvent::Moved {
+            unit: unit.name.clone(),
+            count: unit.count,
+            to: (index / GameState::GRID_SIZE, index % GameState::GRID_SIZE),
+        });
+        drop(tiles);
+        self.finish_turn(unit_id)
     }
 
     pub fn get_unit(&self, index: usize) -> Option<Unit> {
-        let tiles = self.game_state.tiles.lock().unwrap();
-        tiles.iter().find(|x| x.unit.as_ref().map_or(false, |x| x.id == index)).and_then(|tile| tile.unit.clone())
+        let tiles = sync::lock(&self.game_state.tiles);
+        tiles.iter().find(|x| x.unit.as_ref().is_some_and(|x| x.id == index)).and_then(|tile| tile.unit.clone())
     }
 }
 
+/// True while any of the keys named in `names` is held.
+pub fn any_key_down(names: &[String]) -> bool {
+    names.iter().filter_map(|name| settings::key_code(name)).any(is_key_down)
+}
+
+/// True on the frame any of the keys named in `names` goes down.
+pub fn any_key_pressed(names: &[String]) -> bool {
+    names.iter().filter_map(|name| settings::key_code(name)).any(is_key_pressed)
+}
+
 pub struct BoardRenderer {
     board: Arc<Mutex<Board>>,
 }
 
 impl BoardRenderer {
+    const DISABLED_TINT: Color = Color::new(1.0, 1.0, 1.0, 0.3);
+    const TOOLTIP_FONT_SIZE: u16 = 20;
+    const LOG_FONT_SIZE: u16 = 18;
+
     pub fn new(board: Arc<Mutex<Board>>) -> Self {
         Self { board }
     }
     pub fn display(&self) {
         clear_background(WHITE);
 
-        let grid_width = GameState::GRID_SIZE as f32 * self.board.lock().unwrap().square_size;
-        let grid_height = GameState::GRID_SIZE as f32 * self.board.lock().unwrap().square_size;
-        let offset_x = (self.board.lock().unwrap().window_size.screen_width - grid_width) / 2.0;
-        let offset_y = (self.board.lock().unwrap().window_size.screen_height - grid_height) / 2.0;
+        let board = sync::lock(&self.board);
+        let square_size = board.square_size();
+        let (offset_x, offset_y) = board.calculate_offset();
+        let viewport = board.layout().grid_rect();
+        let tiles = sync::lock(&board.game_state.tiles);
 
         for row in 0..GameState::GRID_SIZE {
             for col in 0..GameState::GRID_SIZE {
-                let x = offset_x + col as f32 * self.board.lock().unwrap().square_size;
-                let y = offset_y + row as f32 * self.board.lock().unwrap().square_size;
-                let square_size = self.board.lock().unwrap().square_size;
-                let tile_index = self.board.lock().unwrap().get_tile_index(x, y);
-                if let Some(tile) = self
-                    .board
-                    .lock()
-                    .unwrap()
-                    .game_state
-                    .tiles
-                    .lock()
-                    .unwrap()
-                    .get(tile_index.unwrap())
-                {
-                    if tile.back_light {
+                let x = offset_x + col as f32 * square_size;
+                let y = offset_y + row as f32 * square_size;
+                if !viewport.overlaps(&Rect::new(x, y, square_size, square_size)) {
+                    continue;
+                }
+                if let Some(tile) = tiles.get(row * GameState::GRID_SIZE + col) {
+                    draw_texture_ex(
+                        tile.texture(),
+                        x,
+                        y,
+                        WHITE,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(square_size, square_size)),
+                            ..Default::default()
+                        },
+                    );
+                    // The extra cells of a selected large stack light up with it.
+                    let lit = tile.back_light || tile.anchor.is_some_and(|anchor| tiles[anchor].back_light);
+                    if lit {
                         draw_rectangle(x, y, square_size, square_size, BLACK);
                     } else {
                         draw_rectangle_lines(x, y, square_size, square_size, 2.0, BLACK);
                     }
-                    if let Some(_) = &tile.get_unit() {
-                        let center_x = x + square_size / 2.0;
-                        let center_y = y + square_size / 2.0;
-                        draw_circle(center_x, center_y, 5.0, RED);
-                    }
                 }
             }
         }
+        // Gate frame and a health bar on every wall segment still standing.
+        for (index, segment) in board.siege.iter().flat_map(|siege| siege.segments()) {
+            let (row, col) = (index / GameState::GRID_SIZE, index % GameState::GRID_SIZE);
+            let x = offset_x + col as f32 * square_size;
+            let y = offset_y + row as f32 * square_size;
+            if !viewport.overlaps(&Rect::new(x, y, square_size, square_size)) {
+                continue;
+            }
+            if segment.gate {
+                draw_rectangle_lines(x + 4.0, y + 4.0, square_size - 8.0, square_size - 8.0, 4.0, DARKBROWN);
+            }
+            let share = segment.health as f32 / segment.full_health() as f32;
+            draw_rectangle(x + 2.0, y + square_size - 6.0, (square_size - 4.0) * share, 4.0, GREEN);
+        }
+        // Stacks go on top in a second pass so a large one spans its cells.
+        for (index, tile) in tiles.iter().enumerate() {
+            let Some(unit) = tile.get_unit() else {
+                continue;
+            };
+            let (row, col) = (index / GameState::GRID_SIZE, index % GameState::GRID_SIZE);
+            let x = offset_x + col as f32 * square_size;
+            let y = offset_y + row as f32 * square_size;
+            let width = unit.size as f32 * square_size;
+            if !viewport.overlaps(&Rect::new(x, y, width, square_size)) {
+                continue;
+            }
+            if unit.size > 1 {
+                draw_rectangle_lines(x + 3.0, y + 3.0, width - 6.0, square_size - 6.0, 2.0, RED);
+            }
+            draw_circle(x + width / 2.0, y + square_size / 2.0, 5.0, RED);
+            Self::display_effect_icons(unit, x, y, width);
+        }
+        drop(tiles);
+        Self::mask_outside(viewport, &board.window_size);
+    }
+
+    /// One small marker per active status effect along the top of the tile,
+    /// green for helpful effects and red for harmful ones.
+    fn display_effect_icons(unit: &Unit, x: f32, y: f32, square_size: f32) {
+        let size = (square_size / 6.0).max(3.0);
+        for (slot, effect) in unit.effects.iter().enumerate() {
+            let icon_x = x + 2.0 + slot as f32 * (size + 1.0);
+            if icon_x + size > x + square_size {
+                break;
+            }
+            let color = if effect.is_harmful() { RED } else { GREEN };
+            draw_rectangle(icon_x, y + 2.0, size, size, color);
+            draw_rectangle_lines(icon_x, y + 2.0, size, size, 1.0, BLACK);
+        }
+    }
+
+    /// Covers the parts of zoomed tiles that spill out of the grid viewport.
+    fn mask_outside(viewport: Rect, window_size: &WindowSize) {
+        let (width, height) = (window_size.screen_width, window_size.screen_height);
+        draw_rectangle(0.0, 0.0, width, viewport.y, WHITE);
+        draw_rectangle(0.0, viewport.bottom(), width, height - viewport.bottom(), WHITE);
+        draw_rectangle(0.0, viewport.y, viewport.x, viewport.h, WHITE);
+        draw_rectangle(viewport.right(), viewport.y, width - viewport.right(), viewport.h, WHITE);
+        draw_rectangle_lines(viewport.x, viewport.y, viewport.w, viewport.h, 2.0, BLACK);
     }
 
-    pub fn display_battle_interface(&self) {
-        let x = 80.0;
-        let y = 80.0;
+    /// Applies mouse-wheel zoom, right-button drag, edge and keyboard panning to the camera.
+    pub fn update_camera(&self, keys: &KeyBindings) {
+        let mut board = sync::lock(&self.board);
+        let viewport = board.layout().grid_rect();
+        let (mouse_x, mouse_y) = mouse_position();
+        let over_grid = viewport.contains(vec2(mouse_x, mouse_y));
+        let step = Camera::PAN_SPEED * get_frame_time();
+        let notches = Camera::KEY_ZOOM_RATE * get_frame_time();
+
+        let (_, wheel_y) = mouse_wheel();
+        if over_grid && wheel_y != 0.0 {
+            board.camera.zoom_at(wheel_y.signum(), (mouse_x, mouse_y), viewport);
+        }
+        if any_key_down(&keys.zoom_in) {
+            board.camera.zoom_at(notches, viewport.center().into(), viewport);
+        }
+        if any_key_down(&keys.zoom_out) {
+            board.camera.zoom_at(-notches, viewport.center().into(), viewport);
+        }
+
+        if is_mouse_button_pressed(MouseButton::Right) && over_grid {
+            board.camera.begin_drag(mouse_x, mouse_y);
+        } else if is_mouse_button_down(MouseButton::Right) {
+            board.camera.drag_to(mouse_x, mouse_y, viewport);
+        }
+        if is_mouse_button_released(MouseButton::Right) {
+            board.camera.end_drag();
+        }
 
-        let board = self.board.lock().unwrap();
-        let screen_width = board.window_size.screen_width;
+        let (screen_width, screen_height) = (board.window_size.screen_width, board.window_size.screen_height);
+        let (mut dx, mut dy) = Camera::edge_direction(mouse_x, mouse_y, screen_width, screen_height);
+        if any_key_down(&keys.pan_left) {
+            dx += 1.0;
+        }
+        if any_key_down(&keys.pan_right) {
+            dx -= 1.0;
+        }
+        if any_key_down(&keys.pan_up) {
+            dy += 1.0;
+        }
+        if any_key_down(&keys.pan_down) {
+            dy -= 1.0;
+        }
+        if dx != 0.0 || dy != 0.0 {
+            board.camera.pan_by(dx * step, dy * step, viewport);
+        }
+    }
+
+    /// Draws the HUD around the grid and returns the action button clicked this frame.
+    pub fn display_battle_interface(&self) -> Option<BattleAction> {
+        let board = sync::lock(&self.board);
+        let layout = board.layout();
+        let availability = board.action_availability();
         let icons = &board.battle_icons;
 
-        if widgets::Button::new(icons.magic.clone())
-            .size(vec2(x, y))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+        self.display_turn_order(&board);
+        Self::display_combat_log(&board.combat_log, &board.locale, layout.combat_log);
+
+        let mut clicked = None;
+        for (action, rect) in layout.buttons() {
+            let texture = icons.get(*action);
+            if availability.is_enabled(*action) {
+                if widgets::Button::new(texture.clone())
+                    .size(rect.size())
+                    .position(rect.point())
+                    .ui(&mut root_ui())
+                {
+                    clicked = Some(*action);
+                }
+            } else {
+                draw_texture_ex(
+                    texture,
+                    rect.x,
+                    rect.y,
+                    Self::DISABLED_TINT,
+                    DrawTextureParams {
+                        dest_size: Some(rect.size()),
+                        ..Default::default()
+                    },
+                );
+            }
         }
 
-        if widgets::Button::new(icons.attack.clone())
-            .size(vec2(x, y))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+        let (mouse_x, mouse_y) = mouse_position();
+        if let Some(action) = layout.button_at(mouse_x, mouse_y) {
+            let rect = layout.buttons().find(|(a, _)| *a == action).map(|(_, rect)| *rect);
+            if let Some(rect) = rect {
+                Self::display_tooltip(&board.locale.text(action.tooltip_key()), rect, board.window_size.screen_width);
+            }
         }
+        clicked
+    }
 
-        if widgets::Button::new(icons.defend.clone())
-            .size(vec2(x, y))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+    fn display_turn_order(&self, board: &Board) {
+        let bar = board.layout().turn_order;
+        draw_rectangle_lines(bar.x, bar.y, bar.w, bar.h, 2.0, DARKGRAY);
+        let active_unit = board.active_index.and_then(|index| {
+            sync::lock(&board.game_state.tiles).get(index).and_then(|tile| tile.get_unit().map(|unit| unit.id))
+        });
+        let slot = bar.h;
+        for (position, unit) in board.turn_order().iter().enumerate() {
+            let x = bar.x + position as f32 * slot;
+            if x + slot > bar.right() {
+                break;
+            }
+            let color = if Some(unit.id) == active_unit { BLACK } else { GRAY };
+            draw_rectangle_lines(x, bar.y, slot, slot, 2.0, color);
+            draw_circle(x + slot / 2.0, bar.y + slot / 2.0, slot / 5.0, RED);
         }
+    }
 
-        if widgets::Button::new(icons.wait.clone())
-            .size(vec2(x, y))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+    fn combat_log_rows(panel: Rect) -> usize {
+        ((panel.h - 4.0) / Self::LOG_FONT_SIZE as f32).max(1.0) as usize
+    }
+
+    fn display_combat_log(log: &CombatLog, locale: &Locale, panel: Rect) {
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, DARKGRAY);
+        let rows = Self::combat_log_rows(panel);
+        for (row, event) in log.visible(rows).iter().enumerate() {
+            draw_text(
+                event.localize(locale),
+                panel.x + 4.0,
+                panel.y + (row + 1) as f32 * Self::LOG_FONT_SIZE as f32,
+                Self::LOG_FONT_SIZE as f32,
+                BLACK,
+            );
+        }
+    }
+
+    /// Scrolls the combat log when the mouse wheel turns over its panel.
+    pub fn scroll_combat_log(&self) {
+        let (_, wheel_y) = mouse_wheel();
+        if wheel_y == 0.0 {
+            return;
         }
+        let (mouse_x, mouse_y) = mouse_position();
+        let mut board = sync::lock(&self.board);
+        let panel = board.layout().combat_log;
+        if panel.contains(vec2(mouse_x, mouse_y)) {
+            let rows = Self::combat_log_rows(panel);
+            board.combat_log.scroll_by(wheel_y.signum() as isize, rows);
+        }
+    }
 
-        if widgets::Button::new(icons.run.clone())
-            .size(vec2(x, y))
-            .position(vec2(screen_width - 80.0, 0.0))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+    /// During the tactics phase a "Start battle" button replaces the turn order bar;
+    /// returns true when it was clicked.
+    pub fn display_tactics(&self) -> bool {
+        let board = sync::lock(&self.board);
+        if board.phase() != Phase::Tactics {
+            return false;
         }
+        let bar = board.layout().turn_order;
+        widgets::Button::new(board.locale.text("ui.start_battle"))
+            .position(bar.point())
+            .size(bar.size())
+            .ui(&mut root_ui())
+    }
 
-        if widgets::Button::new(icons.negotiate.clone())
-            .size(vec2(x, y))
-            .position(vec2(screen_width - 80.0, 80.0))
-            .ui(&mut *root_ui())
-        {
-            println!("Textured button clicked!");
+    /// Draws the spellbook over the grid while it is open; picking a spell starts targeting.
+    pub fn display_spellbook(&self) {
+        let mut board = sync::lock(&self.board);
+        if !board.spellbook_open {
+            return;
         }
+        let panel = board.layout().grid_rect();
+        let row_height = f32::max(panel.h / 10.0, Self::TOOLTIP_FONT_SIZE as f32 + 8.0);
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, Color::new(1.0, 1.0, 1.0, 0.9));
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, BLACK);
+        let mana = board.battle_state.mana(Side::Player);
+        draw_text(
+            board.locale.format("ui.mana", &[("mana", mana.to_string())]),
+            panel.x + 8.0,
+            panel.y + row_height * 0.6,
+            Self::TOOLTIP_FONT_SIZE as f32,
+            BLACK,
+        );
 
-        if widgets::Button::new(icons.system.clone())
-            .size(vec2(x, y))
-            .position(vec2(screen_width - 80.0, 160.0))
-            .ui(&mut *root_ui())
+        let mut picked = None;
+        for (row, spell) in board.available_spells().into_iter().enumerate() {
+            let y = panel.y + row_height * (row + 1) as f32;
+            let label = format!("{} ({:?} {}) - {}", spell.name, spell.school, spell.level, spell.mana_cost);
+            if board.battle_state.can_cast(Side::Player, spell.mana_cost) {
+                if widgets::Button::new(label)
+                    .position(vec2(panel.x + 8.0, y))
+                    .size(vec2(panel.w - 16.0, row_height - 4.0))
+                    .ui(&mut root_ui())
+                {
+                    picked = Some(spell.id.clone());
+                }
+            } else {
+                draw_text(label, panel.x + 12.0, y + row_height * 0.6, Self::TOOLTIP_FONT_SIZE as f32, GRAY);
+            }
+        }
+        let close_y = panel.bottom() - row_height;
+        if widgets::Button::new(board.locale.text("ui.close"))
+            .position(vec2(panel.x + 8.0, close_y))
+            .size(vec2(panel.w - 16.0, row_height - 4.0))
+            .ui(&mut root_ui())
         {
-            println!("Textured button clicked!");
+            board.close_spellbook();
+        }
+        if picked.is_some() {
+            board.select_spell(picked);
+        }
+    }
+
+    /// Draws the skill choices of the hero's next level-up once the battle is won and returns
+    /// the one picked.
+    pub fn display_level_up(&self) -> Option<usize> {
+        let board = sync::lock(&self.board);
+        let (hero, level_up) = (board.hero.as_ref()?, board.level_up()?);
+        let window = &board.window_size;
+        draw_rectangle(0.0, 0.0, window.screen_width, window.screen_height, Color::new(0.0, 0.0, 0.0, 0.5));
+        let panel = board.layout().grid_rect();
+        let row_height = f32::max(panel.h / 10.0, Self::TOOLTIP_FONT_SIZE as f32 + 8.0);
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, WHITE);
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, BLACK);
+        draw_text(
+            level_up::title(hero, level_up, &board.locale),
+            panel.x + 8.0,
+            panel.y + row_height * 0.6,
+            Self::TOOLTIP_FONT_SIZE as f32,
+            BLACK,
+        );
+
+        let mut picked = None;
+        for (row, label) in level_up::choices(level_up, &board.locale).into_iter().enumerate() {
+            let y = panel.y + row_height * (row + 1) as f32;
+            if widgets::Button::new(label)
+                .position(vec2(panel.x + 8.0, y))
+                .size(vec2(panel.w - 16.0, row_height - 4.0))
+                .ui(&mut root_ui())
+            {
+                picked = Some(row);
+            }
+        }
+        picked
+    }
+
+    /// Draws the System menu over the whole window while it is open and returns the entry picked.
+    pub fn display_system_menu(&self, settings: &Settings) -> Option<MenuCommand> {
+        let board = sync::lock(&self.board);
+        let menu = board.system_menu.as_ref()?;
+        let window = &board.window_size;
+        draw_rectangle(0.0, 0.0, window.screen_width, window.screen_height, Color::new(0.0, 0.0, 0.0, 0.5));
+        let panel = board.layout().grid_rect();
+        let row_height = f32::max(panel.h / 10.0, Self::TOOLTIP_FONT_SIZE as f32 + 8.0);
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, WHITE);
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, BLACK);
+        draw_text(
+            board.locale.text(menu.title_key()),
+            panel.x + 8.0,
+            panel.y + row_height * 0.6,
+            Self::TOOLTIP_FONT_SIZE as f32,
+            BLACK,
+        );
+
+        let mut picked = None;
+        for (row, (label, command)) in menu.entries(settings, &board.locale).into_iter().enumerate() {
+            let y = panel.y + row_height * (row + 1) as f32;
+            match command {
+                Some(command) => {
+                    if widgets::Button::new(label)
+                        .position(vec2(panel.x + 8.0, y))
+                        .size(vec2(panel.w - 16.0, row_height - 4.0))
+                        .ui(&mut root_ui())
+                    {
+                        picked = Some(command);
+                    }
+                }
+                None => {
+                    draw_text(label, panel.x + 12.0, y + row_height * 0.6, Self::TOOLTIP_FONT_SIZE as f32, GRAY);
+                }
+            }
+        }
+        picked
+    }
+
+    /// Damage the selected stack would deal to the enemy under the mouse, with the shot
+    /// penalties that apply.
+    pub fn display_attack_preview(&self) {
+        let board = sync::lock(&self.board);
+        if board.phase() != Phase::Combat || board.spellbook_open {
+            return;
+        }
+        let (mouse_x, mouse_y) = mouse_position();
+        let (Some(attacker), Some(target)) = (board.active_index, board.get_tile_index(mouse_x, mouse_y)) else {
+            return;
+        };
+        let tiles = sync::lock(&board.game_state.tiles);
+        if tiles.get(target).is_none_or(|tile| tile.tile_type != TileType::EnemyUnit) {
+            return;
         }
+        let Some(preview) = combat::preview(&tiles, attacker, target) else {
+            return;
+        };
+        drop(tiles);
+        let locale = &board.locale;
+        let mut text = locale.format(
+            "ui.preview",
+            &[
+                ("damage", locale.plural("combat.damage", preview.damage, &[])),
+                ("perished", locale.plural("combat.perished", preview.perished, &[])),
+            ],
+        );
+        if let Some(penalty) = preview.shot.filter(|penalty| !penalty.is_none()) {
+            let mut reasons = Vec::new();
+            if penalty.obstructed {
+                reasons.push(locale.text("ui.penalty.obstacle"));
+            }
+            if penalty.long_range {
+                reasons.push(locale.text("ui.penalty.long_range"));
+            }
+            let args = [("factor", penalty.factor().to_string()), ("reasons", reasons.join(", "))];
+            text.push_str(&locale.format("ui.preview.penalty", &args));
+        }
+        let square_size = board.square_size();
+        let (offset_x, offset_y) = board.calculate_offset();
+        let (row, col) = (target / GameState::GRID_SIZE, target % GameState::GRID_SIZE);
+        let tile = Rect::new(
+            offset_x + col as f32 * square_size,
+            offset_y + row as f32 * square_size,
+            square_size,
+            square_size,
+        );
+        Self::display_tooltip(&text, tile, board.window_size.screen_width);
+    }
+
+    /// Tooltips open towards the grid so they never cover their own button.
+    fn display_tooltip(text: &str, button: Rect, screen_width: f32) {
+        let dimensions = measure_text(text, None, Self::TOOLTIP_FONT_SIZE, 1.0);
+        let padding = 4.0;
+        let width = dimensions.width + 2.0 * padding;
+        let height = dimensions.height + 2.0 * padding;
+        let x = if button.center().x < screen_width / 2.0 {
+            button.right() + padding
+        } else {
+            button.left() - padding - width
+        };
+        let y = button.center().y - height / 2.0;
+        draw_rectangle(x, y, width, height, BLACK);
+        draw_text(
+            text,
+            x + padding,
+            y + padding + dimensions.offset_y,
+            Self::TOOLTIP_FONT_SIZE as f32,
+            WHITE,
+        );
     }
 }
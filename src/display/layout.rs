@@ -0,0 +1,279 @@
+use crate::battle::combat;
+use crate::battle::footprint;
+use crate::battle::movement::neighbours;
+use crate::common::display::WindowSize;
+use crate::display::GameState;
+use crate::display::tile::{Tile, TileType};
+use macroquad::math::{Rect, vec2};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BattleAction {
+    Magic,
+    Attack,
+    Defend,
+    Wait,
+    Run,
+    Negotiate,
+    System,
+}
+
+impl BattleAction {
+    /// Left column of `doc/ui_battle.txt`: Czary, Atak, Obrona, Czekaj.
+    pub const ACTIONS: [BattleAction; 4] = [
+        BattleAction::Magic,
+        BattleAction::Attack,
+        BattleAction::Defend,
+        BattleAction::Wait,
+    ];
+    /// Right column: Uciekaj, Negocjuj, then System below the separator.
+    pub const COMMANDS: [BattleAction; 3] = [
+        BattleAction::Run,
+        BattleAction::Negotiate,
+        BattleAction::System,
+    ];
+
+    /// Message key of the tooltip; Polish reads Czary, Atak, Obrona, Czekaj, Uciekaj, Negocjuj.
+    pub fn tooltip_key(&self) -> &'static str {
+        match self {
+            BattleAction::Magic => "action.magic",
+            BattleAction::Attack => "action.attack",
+            BattleAction::Defend => "action.defend",
+            BattleAction::Wait => "action.wait",
+            BattleAction::Run => "action.run",
+            BattleAction::Negotiate => "action.negotiate",
+            BattleAction::System => "action.system",
+        }
+    }
+}
+
+/// Which battle actions the active unit is allowed to take this frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActionAvailability {
+    has_active_unit: bool,
+    enemy_in_reach: bool,
+    can_cast: bool,
+}
+
+impl ActionAvailability {
+    pub fn new(has_active_unit: bool, enemy_in_reach: bool) -> Self {
+        Self {
+            has_active_unit,
+            enemy_in_reach,
+            can_cast: false,
+        }
+    }
+
+    /// Magic depends on the side's mana and whether it already cast this round, not on the unit.
+    pub fn with_casting(mut self, can_cast: bool) -> Self {
+        self.can_cast = can_cast;
+        self
+    }
+
+    /// Builds availability for the unit standing on `active_index`, looking at the stacks
+    /// touching it for an enemy to strike.
+    pub fn from_tiles(tiles: &[Tile], active_index: Option<usize>) -> Self {
+        let Some(index) = active_index else {
+            return Self::default();
+        };
+        if tiles.get(index).and_then(|tile| tile.get_unit()).is_none() {
+            return Self::default();
+        }
+        let enemy_in_reach = combat::can_shoot(tiles, index)
+            || footprint::occupied(tiles, index)
+                .into_iter()
+                .flat_map(neighbours)
+                .any(|next| tiles[next].tile_type == TileType::EnemyUnit);
+        Self::new(true, enemy_in_reach)
+    }
+
+    pub fn is_enabled(&self, action: BattleAction) -> bool {
+        match action {
+            BattleAction::Attack => self.has_active_unit && self.enemy_in_reach,
+            BattleAction::Magic => self.can_cast,
+            BattleAction::Defend | BattleAction::Wait => self.has_active_unit,
+            BattleAction::Run | BattleAction::Negotiate | BattleAction::System => true,
+        }
+    }
+}
+
+/// Screen placement of the battle grid and every HUD element around it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BattleLayout {
+    pub icon_size: f32,
+    pub square_size: f32,
+    pub grid_offset: (f32, f32),
+    pub actions: Vec<(BattleAction, Rect)>,
+    pub commands: Vec<(BattleAction, Rect)>,
+    pub turn_order: Rect,
+    pub combat_log: Rect,
+}
+
+impl BattleLayout {
+    pub const MARGIN: f32 = 8.0;
+    pub const MIN_ICON_SIZE: f32 = 32.0;
+    pub const MAX_ICON_SIZE: f32 = 80.0;
+    const MIN_SQUARE_SIZE: f32 = 4.0;
+
+    pub fn new(window_size: &WindowSize) -> Self {
+        let (width, height) = (window_size.screen_width, window_size.screen_height);
+        let icon_size = (f32::min(width, height) / 8.0).clamp(Self::MIN_ICON_SIZE, Self::MAX_ICON_SIZE);
+        let column_width = icon_size + 2.0 * Self::MARGIN;
+        let turn_order_height = icon_size * 0.5;
+        let combat_log_height = f32::max(height * 0.15, icon_size);
+
+        let grid_area = Rect::new(
+            column_width,
+            turn_order_height + 2.0 * Self::MARGIN,
+            width - 2.0 * column_width,
+            height - turn_order_height - combat_log_height - 4.0 * Self::MARGIN,
+        );
+        let square_size = (f32::min(grid_area.w, grid_area.h) / GameState::GRID_SIZE as f32).max(Self::MIN_SQUARE_SIZE);
+        let grid_side = square_size * GameState::GRID_SIZE as f32;
+        let grid_offset = (
+            grid_area.x + (grid_area.w - grid_side) / 2.0,
+            grid_area.y + (grid_area.h - grid_side) / 2.0,
+        );
+
+        let actions = Self::column(&BattleAction::ACTIONS, Self::MARGIN, icon_size, None);
+        let commands = Self::column(
+            &BattleAction::COMMANDS,
+            width - Self::MARGIN - icon_size,
+            icon_size,
+            Some(BattleAction::System),
+        );
+
+        let turn_order = Rect::new(
+            grid_offset.0,
+            grid_offset.1 - turn_order_height - Self::MARGIN,
+            grid_side,
+            turn_order_height,
+        );
+        let combat_log = Rect::new(grid_offset.0, grid_offset.1 + grid_side + Self::MARGIN, grid_side, combat_log_height);
+
+        Self {
+            icon_size,
+            square_size,
+            grid_offset,
+            actions,
+            commands,
+            turn_order,
+            combat_log,
+        }
+    }
+
+    /// Stacks icons top-down, leaving half an icon of space above `separated`.
+    fn column(
+        actions: &[BattleAction],
+        x: f32,
+        icon_size: f32,
+        separated: Option<BattleAction>,
+    ) -> Vec<(BattleAction, Rect)> {
+        let mut y = Self::MARGIN;
+        actions
+            .iter()
+            .map(|&action| {
+                if Some(action) == separated {
+                    y += icon_size / 2.0;
+                }
+                let rect = Rect::new(x, y, icon_size, icon_size);
+                y += icon_size + Self::MARGIN;
+                (action, rect)
+            })
+            .collect()
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = &(BattleAction, Rect)> {
+        self.actions.iter().chain(self.commands.iter())
+    }
+
+    pub fn grid_rect(&self) -> Rect {
+        let side = self.square_size * GameState::GRID_SIZE as f32;
+        Rect::new(self.grid_offset.0, self.grid_offset.1, side, side)
+    }
+
+    pub fn button_at(&self, x: f32, y: f32) -> Option<BattleAction> {
+        self.buttons()
+            .find(|(_, rect)| rect.contains(vec2(x, y)))
+            .map(|(action, _)| *action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(width: f32, height: f32) -> BattleLayout {
+        BattleLayout::new(&WindowSize::new(width, height))
+    }
+
+    #[test]
+    fn test_buttons_do_not_overlap_grid() {
+        for (width, height) in [(800.0, 600.0), (600.0, 800.0), (400.0, 300.0), (1920.0, 1080.0)] {
+            let layout = layout(width, height);
+            let grid = layout.grid_rect();
+            for (action, rect) in layout.buttons() {
+                assert!(!rect.overlaps(&grid), "{:?} overlaps grid at {}x{}", action, width, height);
+            }
+            assert!(!layout.turn_order.overlaps(&grid));
+            assert!(!layout.combat_log.overlaps(&grid));
+        }
+    }
+
+    #[test]
+    fn test_buttons_stay_inside_window() {
+        let layout = layout(800.0, 600.0);
+        let window = Rect::new(0.0, 0.0, 800.0, 600.0);
+        for (_, rect) in layout.buttons() {
+            assert_eq!(rect.intersect(window), Some(*rect));
+        }
+    }
+
+    #[test]
+    fn test_system_is_separated_from_commands() {
+        let layout = layout(800.0, 600.0);
+        let negotiate = layout.commands[1].1;
+        let system = layout.commands[2].1;
+        assert!(system.y - negotiate.bottom() > BattleLayout::MARGIN);
+    }
+
+    #[test]
+    fn test_button_at() {
+        let layout = layout(800.0, 600.0);
+        let attack = layout.actions[1].1.center();
+        assert_eq!(layout.button_at(attack.x, attack.y), Some(BattleAction::Attack));
+        let grid = layout.grid_rect().center();
+        assert_eq!(layout.button_at(grid.x, grid.y), None);
+    }
+
+    #[test]
+    fn test_no_active_unit_disables_actions() {
+        let tiles = Tile::board_for_test();
+        let availability = ActionAvailability::from_tiles(&tiles, None);
+        for action in BattleAction::ACTIONS {
+            assert!(!availability.is_enabled(action));
+        }
+        for action in BattleAction::COMMANDS {
+            assert!(availability.is_enabled(action));
+        }
+    }
+
+    #[test]
+    fn test_attack_requires_adjacent_enemy() {
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(crate::display::Unit::new(0, "Knights", 12, 30, 3, 2), TileType::MyUnit);
+        let availability = ActionAvailability::from_tiles(&tiles, Some(0));
+        assert!(availability.is_enabled(BattleAction::Defend));
+        assert!(!availability.is_enabled(BattleAction::Attack));
+
+        tiles[GameState::GRID_SIZE + 1].tile_type = TileType::EnemyUnit;
+        let availability = ActionAvailability::from_tiles(&tiles, Some(0));
+        assert!(availability.is_enabled(BattleAction::Attack));
+    }
+
+    #[test]
+    fn test_magic_follows_casting() {
+        let availability = ActionAvailability::default();
+        assert!(!availability.is_enabled(BattleAction::Magic));
+        assert!(availability.with_casting(true).is_enabled(BattleAction::Magic));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::common::locale::Locale;
+use crate::hero::{Hero, LevelUp};
+
+/// Heading of the screen on which `hero` picks a skill for `level_up` after a battle.
+pub fn title(hero: &Hero, level_up: &LevelUp, locale: &Locale) -> String {
+    locale.format(
+        "level_up.title",
+        &[
+            ("hero", hero.name.clone()),
+            ("level", level_up.level.to_string()),
+            ("stat", locale.text(level_up.stat.key())),
+        ],
+    )
+}
+
+/// One label per skill `level_up` offers, in the order `Hero::choose_skill` counts them.
+pub fn choices(level_up: &LevelUp, locale: &Locale) -> Vec<String> {
+    level_up
+        .choices
+        .iter()
+        .map(|(skill, level)| {
+            locale.format("level_up.choice", &[("skill", locale.text(skill.key())), ("level", locale.text(level.key()))])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::BattleResult;
+    use crate::common::settings::Language;
+    use crate::hero::{PrimaryStats, SecondarySkill, SkillLevel};
+
+    #[test]
+    fn test_every_level_up_of_a_battle_is_offered_and_picked() {
+        let locale = Locale::load(Locale::PATH, Language::English).unwrap();
+        let mut hero = Hero::new("Roland", PrimaryStats::default());
+        let result = BattleResult {
+            victory: true,
+            experience: 2500,
+            ..BattleResult::default()
+        };
+        hero.finish_battle(&result, 0);
+
+        let mut picked = Vec::new();
+        while let Some(level_up) = hero.pending_level_ups().first() {
+            picked.push(title(&hero, level_up, &locale));
+            picked.push(choices(level_up, &locale)[0].clone());
+            hero.choose_skill(0).unwrap();
+        }
+
+        assert_eq!(
+            picked,
+            [
+                "Roland reaches level 2: +1 Attack. Pick a skill:",
+                "Offense (basic)",
+                "Roland reaches level 3: +1 Defence. Pick a skill:",
+                "Offense (advanced)",
+            ]
+        );
+        assert_eq!(hero.skill_level(SecondarySkill::Offense), Some(SkillLevel::Advanced));
+    }
+}
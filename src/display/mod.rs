@@ -1,7 +1,18 @@
+pub mod adventure;
+pub mod camera;
 pub mod tile;
+#[allow(clippy::module_inception)]
 pub mod display;
+pub mod layout;
+pub mod level_up;
+pub mod system_menu;
 
+pub use adventure::{AdventureAction, AdventureRenderer, MapLayout, TownAction};
+pub use camera::Camera;
 pub use display::Board;
 pub use display::BoardRenderer;
+pub use display::any_key_pressed;
 pub use display::GameState;
+pub use layout::{ActionAvailability, BattleAction, BattleLayout};
+pub use system_menu::{MenuCommand, MenuScreen, SystemMenu};
 pub use tile::Unit;
\ No newline at end of file
@@ -0,0 +1,151 @@
+use crate::common::locale::Locale;
+use crate::common::settings::{SettingChange, Settings};
+
+/// Pages of the System menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuScreen {
+    Main,
+    Save,
+    Load,
+    Settings,
+    ConfirmQuit,
+}
+
+/// What picking a menu entry asks the game to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuCommand {
+    Show(MenuScreen),
+    Resume,
+    Save(usize),
+    Load(usize),
+    Change(SettingChange),
+    Quit,
+}
+
+/// The modal menu behind the System button; the battle is paused while it is open.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemMenu {
+    screen: MenuScreen,
+    /// What each save slot holds, `None` for an empty one.
+    slots: Vec<Option<String>>,
+}
+
+impl Default for SystemMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemMenu {
+    pub fn new() -> Self {
+        Self {
+            screen: MenuScreen::Main,
+            slots: Vec::new(),
+        }
+    }
+
+    pub fn screen(&self) -> MenuScreen {
+        self.screen
+    }
+
+    pub fn show(&mut self, screen: MenuScreen) {
+        self.screen = screen;
+    }
+
+    pub fn set_slots(&mut self, slots: Vec<Option<String>>) {
+        self.slots = slots;
+    }
+
+    pub fn title_key(&self) -> &'static str {
+        match self.screen {
+            MenuScreen::Main => "menu.title.main",
+            MenuScreen::Save => "menu.title.save",
+            MenuScreen::Load => "menu.title.load",
+            MenuScreen::Settings => "menu.title.settings",
+            MenuScreen::ConfirmQuit => "menu.title.confirm_quit",
+        }
+    }
+
+    /// Labels of the current page with their commands; entries without one are shown disabled.
+    pub fn entries(&self, settings: &Settings, locale: &Locale) -> Vec<(String, Option<MenuCommand>)> {
+        let back = (locale.text("menu.back"), Some(MenuCommand::Show(MenuScreen::Main)));
+        match self.screen {
+            MenuScreen::Main => vec![
+                (locale.text("menu.resume"), Some(MenuCommand::Resume)),
+                (locale.text("menu.save"), Some(MenuCommand::Show(MenuScreen::Save))),
+                (locale.text("menu.load"), Some(MenuCommand::Show(MenuScreen::Load))),
+                (locale.text("menu.settings"), Some(MenuCommand::Show(MenuScreen::Settings))),
+                (locale.text("menu.quit"), Some(MenuCommand::Show(MenuScreen::ConfirmQuit))),
+            ],
+            MenuScreen::Save | MenuScreen::Load => {
+                let saving = self.screen == MenuScreen::Save;
+                let mut entries: Vec<_> = self
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, summary)| {
+                        let shown = summary.clone().unwrap_or_else(|| locale.text("menu.slot.empty"));
+                        let label = locale.format("menu.slot", &[("slot", (slot + 1).to_string()), ("summary", shown)]);
+                        let command = match (saving, summary) {
+                            (true, _) => Some(MenuCommand::Save(slot)),
+                            (false, Some(_)) => Some(MenuCommand::Load(slot)),
+                            (false, None) => None,
+                        };
+                        (label, command)
+                    })
+                    .collect();
+                entries.push(back);
+                entries
+            }
+            MenuScreen::Settings => {
+                let mut entries: Vec<_> = SettingChange::ALL
+                    .into_iter()
+                    .map(|change| (settings.describe(change, locale), Some(MenuCommand::Change(change))))
+                    .collect();
+                entries.push(back);
+                entries
+            }
+            MenuScreen::ConfirmQuit => vec![
+                (locale.text("menu.quit.yes"), Some(MenuCommand::Quit)),
+                (locale.text("menu.quit.no"), Some(MenuCommand::Show(MenuScreen::Main))),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::settings::Language;
+
+    fn english() -> Locale {
+        Locale::load(Locale::PATH, Language::English).unwrap()
+    }
+
+    #[test]
+    fn test_empty_slots_can_be_saved_but_not_loaded() {
+        let mut menu = SystemMenu::new();
+        menu.set_slots(vec![Some("Round 3".to_string()), None]);
+
+        menu.show(MenuScreen::Save);
+        let entries = menu.entries(&Settings::default(), &english());
+        let commands: Vec<_> = entries.into_iter().map(|(_, command)| command).collect();
+        assert_eq!(commands[..2], [Some(MenuCommand::Save(0)), Some(MenuCommand::Save(1))]);
+
+        menu.show(MenuScreen::Load);
+        let entries = menu.entries(&Settings::default(), &english());
+        assert_eq!(entries[0], ("Slot 1: Round 3".to_string(), Some(MenuCommand::Load(0))));
+        assert_eq!(entries[1], ("Slot 2: empty".to_string(), None));
+    }
+
+    #[test]
+    fn test_quit_needs_confirmation() {
+        let mut menu = SystemMenu::new();
+        let quit = menu.entries(&Settings::default(), &english()).pop().unwrap().1;
+        assert_eq!(quit, Some(MenuCommand::Show(MenuScreen::ConfirmQuit)));
+
+        menu.show(MenuScreen::ConfirmQuit);
+        let entries = menu.entries(&Settings::default(), &english());
+        assert!(entries.iter().any(|(_, command)| *command == Some(MenuCommand::Quit)));
+    }
+}
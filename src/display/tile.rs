@@ -1,19 +1,201 @@
+use crate::battle::ability::Ability;
+use crate::battle::morale;
+use crate::battle::status::{Stat, StatusEffect};
 use crate::common::display::texture::load_texture_sync;
+use crate::error::Result;
 use macroquad::prelude::Texture2D;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Unit {
     pub id: usize,
+    /// Unit definition id from the roster, if the stack was spawned from one.
+    pub kind: Option<String>,
+    pub name: String,
+    pub count: u32,
+    /// Stack size at deployment; resurrection never goes past it.
+    pub initial_count: u32,
+    pub health: u32,
+    pub top_health: u32,
+    pub attack: u32,
+    pub defence: u32,
+    pub damage: u32,
     pub move_range: usize,
+    pub effects: Vec<StatusEffect>,
+    pub abilities: Vec<Ability>,
+    pub ammo: u32,
+    /// Set once the stack struck back this round.
+    pub retaliated: bool,
+    /// Cells the stack covers in a row, starting at its anchor tile; see `battle::footprint`.
+    pub size: usize,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Unit {
+    pub fn new(id: usize, name: &str, count: u32, health: u32, damage: u32, move_range: usize) -> Self {
+        Self {
+            id,
+            kind: None,
+            name: name.to_string(),
+            count,
+            initial_count: count,
+            health,
+            top_health: health,
+            attack: 0,
+            defence: 0,
+            damage,
+            move_range,
+            effects: Vec::new(),
+            abilities: Vec::new(),
+            ammo: 0,
+            retaliated: false,
+            size: 1,
+        }
+    }
+
+    /// Gives the stack its abilities, with a full quiver for shooters.
+    pub fn with_abilities(mut self, abilities: Vec<Ability>) -> Self {
+        self.ammo = abilities
+            .iter()
+            .find_map(|ability| match ability {
+                Ability::Ranged { ammo } => Some(*ammo),
+                _ => None,
+            })
+            .unwrap_or_default();
+        self.abilities = abilities;
+        self
+    }
+
+    pub fn has_ability(&self, ability: &Ability) -> bool {
+        self.abilities.contains(ability)
+    }
+
+    pub fn is_flying(&self) -> bool {
+        self.has_ability(&Ability::Flying)
+    }
+
+    pub fn is_ranged(&self) -> bool {
+        self.abilities.iter().any(|ability| matches!(ability, Ability::Ranged { .. }))
+    }
+
+    pub fn can_shoot(&self) -> bool {
+        self.is_ranged() && self.ammo > 0
+    }
+
+    /// Towers and siege engines: built rather than recruited, they don't decide a battle.
+    pub fn is_war_machine(&self) -> bool {
+        self.has_ability(&Ability::WarMachine)
+    }
+
+    /// Damage the stack deals to walls and gates, if it can batter them at all.
+    pub fn siege_damage(&self) -> Option<u32> {
+        self.abilities.iter().find_map(|ability| match ability {
+            Ability::Siege { damage } => Some(*damage),
+            _ => None,
+        })
+    }
+
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: &str) -> Self {
+        self.kind = Some(kind.to_string());
+        self
+    }
+
+    pub fn with_skills(mut self, attack: u32, defence: u32) -> Self {
+        self.attack = attack;
+        self.defence = defence;
+        self
+    }
+
+    /// Adds a status effect following its stacking rule.
+    pub fn add_effect(&mut self, effect: StatusEffect) {
+        StatusEffect::apply(&mut self.effects, effect);
+    }
+
+    pub fn has_effect(&self, id: &str) -> bool {
+        self.effects.iter().any(|effect| effect.id == id)
+    }
+
+    pub fn effective_attack(&self) -> u32 {
+        StatusEffect::total(self.attack, &self.effects, Stat::Attack)
+    }
+
+    pub fn effective_defence(&self) -> u32 {
+        StatusEffect::total(self.defence, &self.effects, Stat::Defence)
+    }
+
+    pub fn effective_damage(&self) -> u32 {
+        StatusEffect::total(self.damage, &self.effects, Stat::Damage)
+    }
+
+    pub fn effective_move_range(&self) -> usize {
+        StatusEffect::total(self.move_range as u32, &self.effects, Stat::Speed) as usize
+    }
+
+    /// Morale from effects, capped at ±3; the undead and war machines feel neither fear nor zeal.
+    pub fn effective_morale(&self) -> i32 {
+        if self.has_ability(&Ability::Undead) || self.is_war_machine() {
+            return 0;
+        }
+        StatusEffect::sum(&self.effects, Stat::Morale).clamp(-morale::MAX_POINTS, morale::MAX_POINTS)
+    }
+
+    pub fn effective_luck(&self) -> i32 {
+        StatusEffect::sum(&self.effects, Stat::Luck).clamp(-morale::MAX_POINTS, morale::MAX_POINTS)
+    }
+
+    /// Restores health of the top creature only; healing never brings the dead back.
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        if !self.is_alive() {
+            return 0;
+        }
+        let healed = amount.min(self.health - self.top_health);
+        self.top_health += healed;
+        healed
+    }
+
+    /// Heals the top creature, then raises fallen ones with what is left, up to the initial count.
+    /// Returns how many creatures came back.
+    pub fn resurrect(&mut self, amount: u32) -> u32 {
+        if !self.is_alive() {
+            return 0;
+        }
+        let remaining = amount - self.heal(amount);
+        let raised = (remaining / self.health).min(self.initial_count.saturating_sub(self.count));
+        self.count += raised;
+        raised
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.count > 0
+    }
+
+    /// Applies `damage` to the stack, top creature first, and returns how many creatures perished.
+    pub fn take_damage(&mut self, damage: u32) -> u32 {
+        let total = (self.count.saturating_sub(1)) * self.health + self.top_health;
+        let remaining = total.saturating_sub(damage);
+        let survivors = remaining.div_ceil(self.health);
+        let perished = self.count - survivors;
+        self.count = survivors;
+        self.top_health = if survivors == 0 { 0 } else { remaining - (survivors - 1) * self.health };
+        perished
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Empty,
     Obstacle,
     SpawnPoint,
     MyUnit,
     EnemyUnit,
+    /// A town wall segment; see `battle::siege`.
+    Wall,
+    /// The town gate, which defenders walk through and attackers have to break.
+    Gate,
 }
 
 #[derive(Debug, Clone)]
@@ -21,27 +203,66 @@ pub struct Tile {
     pub tile_type: TileType,
     texture: Texture2D,
     pub back_light: bool,
-    pub unit: Option<Unit>
+    pub unit: Option<Unit>,
+    /// For the extra cells of a large stack: the tile holding the stack.
+    pub anchor: Option<usize>,
+    /// While a stack stands here: what the tile was before, e.g. a spawn point, restored when
+    /// the stack leaves.
+    pub ground: Option<TileType>,
 }
 
 impl Tile {
-    pub fn new(tile_type: TileType, texture_path: &str) -> Self {
-        Self {
-            tile_type: tile_type,
-            texture: load_texture_sync(texture_path),
+    pub fn new(tile_type: TileType, texture_path: &str) -> Result<Self> {
+        Ok(Self {
+            tile_type,
+            texture: load_texture_sync(texture_path)?,
             back_light: false,
             unit: None,
-        }
+            anchor: None,
+            ground: None,
+        })
     }
 
     pub fn set_unit(&mut self, unit: Unit, tile_type: TileType) {
+        self.keep_ground();
         self.unit = Some(unit);
+        self.anchor = None;
         self.tile_type = tile_type;
     }
 
+    /// Makes the tile one of the extra cells of the stack on `anchor`.
+    pub fn set_part_of(&mut self, anchor: usize, tile_type: TileType) {
+        self.keep_ground();
+        self.unit = None;
+        self.anchor = Some(anchor);
+        self.tile_type = tile_type;
+    }
+
+    /// Takes the stack, or the part of one, off the tile and leaves the ground it stood on.
+    pub fn clear(&mut self) -> Option<Unit> {
+        self.anchor = None;
+        self.tile_type = self.ground.take().unwrap_or(TileType::Empty);
+        self.unit.take()
+    }
+
+    /// Notes what the tile is before a stack steps on it. A side's colour left on a tile
+    /// without a stack is not ground, so it leaves plain ground behind.
+    fn keep_ground(&mut self) {
+        if self.unit.is_none() && self.anchor.is_none() {
+            self.ground = Some(match self.tile_type {
+                TileType::MyUnit | TileType::EnemyUnit => TileType::Empty,
+                ref ground => ground.clone(),
+            });
+        }
+    }
+
     pub fn get_unit(&self) -> Option<&Unit> {
         self.unit.as_ref()
     }
+
+    pub fn texture(&self) -> &Texture2D {
+        &self.texture
+    }
 }
 
 impl Tile {
@@ -52,6 +273,15 @@ impl Tile {
             texture: unsafe { std::mem::zeroed() }, // Bezpieczne w kontekście testów
             back_light: false,
             unit: None,
+            anchor: None,
+            ground: None,
         }
     }
+
+    /// A whole grid of empty tiles.
+    #[cfg(test)]
+    pub fn board_for_test() -> Vec<Self> {
+        use crate::display::GameState;
+        vec![Self::new_for_test(TileType::Empty); GameState::GRID_SIZE * GameState::GRID_SIZE]
+    }
 }
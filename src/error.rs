@@ -0,0 +1,96 @@
+use std::fmt;
+use std::sync::PoisonError;
+use std::sync::mpsc::SendError;
+
+/// Every failure the game can recover from: the caller logs it and carries on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A texture or data file could not be read or decoded.
+    Asset { path: String, reason: String },
+    /// The requested action breaks the battle rules or targets a missing tile or unit.
+    Rules(String),
+    /// A payload could not be encoded or decoded.
+    Serialization(String),
+    /// A channel peer hung up or a shared lock was poisoned.
+    Channel(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn asset(path: &str, reason: impl fmt::Display) -> Self {
+        Error::Asset {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn rules(reason: impl Into<String>) -> Self {
+        Error::Rules(reason.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Asset { path, reason } => write!(f, "asset {}: {}", path, reason),
+            Error::Rules(reason) => write!(f, "rules: {}", reason),
+            Error::Serialization(reason) => write!(f, "serialization: {}", reason),
+            Error::Channel(reason) => write!(f, "channel: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<bincode::error::EncodeError> for Error {
+    fn from(error: bincode::error::EncodeError) -> Self {
+        Error::Serialization(error.to_string())
+    }
+}
+
+impl From<bincode::error::DecodeError> for Error {
+    fn from(error: bincode::error::DecodeError) -> Self {
+        Error::Serialization(error.to_string())
+    }
+}
+
+impl<T> From<SendError<T>> for Error {
+    fn from(_: SendError<T>) -> Self {
+        Error::Channel("receiver disconnected".to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::Channel("lock poisoned".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::config;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_decode_error_is_serialization() {
+        let result: std::result::Result<(usize, usize), _> = bincode::decode_from_slice(&[], config::standard());
+        let error: Error = result.map(|(value, _)| value).unwrap_err().into();
+        assert!(matches!(error, Error::Serialization(_)));
+    }
+
+    #[test]
+    fn test_send_error_is_channel() {
+        let (tx, rx) = mpsc::channel::<u8>();
+        drop(rx);
+        let error: Error = tx.send(1).unwrap_err().into();
+        assert_eq!(error, Error::Channel("receiver disconnected".to_string()));
+    }
+
+    #[test]
+    fn test_display() {
+        let error = Error::asset("data/missing.png", "not found");
+        assert_eq!(error.to_string(), "asset data/missing.png: not found");
+    }
+}
@@ -0,0 +1,91 @@
+use crate::battle::combat;
+use crate::battle::footprint::touching;
+use crate::display::GameState;
+use crate::display::tile::TileType;
+use crate::error::{Error, Result};
+use crate::game::GuiEvent;
+use crate::game::event_loop::Payload;
+use bincode::config::Configuration;
+use std::sync::{Arc, mpsc};
+
+pub struct AttackUnit {
+    game_state: Arc<GameState>,
+    tx: mpsc::Sender<(GuiEvent, Payload)>,
+}
+
+impl AttackUnit {
+    pub fn new(game_state: Arc<GameState>, tx: mpsc::Sender<(GuiEvent, Payload)>) -> Self {
+        Self { game_state, tx }
+    }
+
+    pub fn attack_unit(&self, config: Configuration, index: usize, last_selected_index: usize) -> Result<()> {
+        let tiles = self.game_state.tiles.lock()?;
+        tiles
+            .get(last_selected_index)
+            .and_then(|tile| tile.get_unit())
+            .ok_or_else(|| Error::rules("Can't get attacking unit"))?;
+        let target = tiles.get(index).ok_or_else(|| Error::rules("Can't get tile"))?;
+        if target.tile_type != TileType::EnemyUnit || target.get_unit().is_none() {
+            return Err(Error::rules("No enemy on target tile"));
+        }
+        if !touching(&tiles, index, last_selected_index) && !combat::can_shoot(&tiles, last_selected_index) {
+            return Err(Error::rules("Enemy is out of reach"));
+        }
+        let encoded: Vec<u8> = bincode::encode_to_vec((last_selected_index, index), config)?;
+        self.tx.send((GuiEvent::AttackUnit, encoded))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tile::{Tile, Unit};
+    use bincode::config;
+    use std::sync::Mutex;
+
+    macro_rules! setup_game_state {
+    () => {{
+        let (tx, rx) = mpsc::channel();
+        let mut tiles = Tile::board_for_test();
+        tiles[0].set_unit(Unit::new(0, "Knights", 12, 30, 3, 2), TileType::MyUnit);
+        tiles[1].set_unit(Unit::new(1, "Peasants", 20, 5, 1, 1), TileType::EnemyUnit);
+        tiles[5].set_unit(Unit::new(2, "Peasants", 20, 5, 1, 1), TileType::EnemyUnit);
+        let game_state = GameState {
+            tiles: Arc::new(Mutex::new(tiles)),
+        };
+        (tx, rx, Arc::new(game_state), config::standard())
+    }};
+}
+
+    #[test]
+    fn test_attack_adjacent_enemy() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let sut = AttackUnit::new(game_state, tx);
+
+        assert!(sut.attack_unit(config, 1, 0).is_ok());
+        let (event, payload) = rx.try_recv().unwrap();
+        let ((attacker, defender), _): ((usize, usize), usize) = bincode::decode_from_slice(&payload, config).unwrap();
+
+        assert_eq!(event, GuiEvent::AttackUnit);
+        assert_eq!((attacker, defender), (0, 1));
+    }
+
+    #[test]
+    fn test_enemy_out_of_reach() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let sut = AttackUnit::new(game_state, tx);
+
+        assert!(sut.attack_unit(config, 5, 0).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_no_enemy_on_tile() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let sut = AttackUnit::new(game_state, tx);
+
+        assert!(sut.attack_unit(config, 2, 0).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::display::GameState;
+use crate::display::tile::TileType;
+use crate::error::{Error, Result};
+use crate::game::GuiEvent;
+use crate::game::event_loop::Payload;
+use bincode::config::Configuration;
+use std::sync::{Arc, mpsc};
+
+pub struct AttackWall {
+    game_state: Arc<GameState>,
+    tx: mpsc::Sender<(GuiEvent, Payload)>,
+}
+
+impl AttackWall {
+    pub fn new(game_state: Arc<GameState>, tx: mpsc::Sender<(GuiEvent, Payload)>) -> Self {
+        Self { game_state, tx }
+    }
+
+    pub fn attack_wall(&self, config: Configuration, index: usize, last_selected_index: usize) -> Result<()> {
+        let tiles = self.game_state.tiles.lock()?;
+        let attacker = tiles
+            .get(last_selected_index)
+            .and_then(|tile| tile.get_unit())
+            .ok_or_else(|| Error::rules("Can't get attacking unit"))?;
+        if attacker.siege_damage().is_none() {
+            return Err(Error::rules(format!("{} can't batter walls", attacker.name)));
+        }
+        let target = tiles.get(index).ok_or_else(|| Error::rules("Can't get tile"))?;
+        if !matches!(target.tile_type, TileType::Wall | TileType::Gate) {
+            return Err(Error::rules("No wall on target tile"));
+        }
+        let encoded: Vec<u8> = bincode::encode_to_vec((last_selected_index, index), config)?;
+        self.tx.send((GuiEvent::AttackWall, encoded))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::Ability;
+    use crate::display::tile::{Tile, Unit};
+    use bincode::config;
+    use std::sync::Mutex;
+
+    macro_rules! setup_game_state {
+    () => {{
+        let (tx, rx) = mpsc::channel();
+        let mut tiles = Tile::board_for_test();
+        let catapult = Unit::new(0, "Catapult", 1, 100, 1, 0).with_abilities(vec![Ability::Siege { damage: 30 }]);
+        tiles[0].set_unit(catapult, TileType::MyUnit);
+        tiles[1].set_unit(Unit::new(1, "Knights", 12, 30, 3, 2), TileType::MyUnit);
+        tiles[9].tile_type = TileType::Wall;
+        let game_state = GameState {
+            tiles: Arc::new(Mutex::new(tiles)),
+        };
+        (tx, rx, Arc::new(game_state), config::standard())
+    }};
+}
+
+    #[test]
+    fn test_catapult_targets_wall() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let sut = AttackWall::new(game_state, tx);
+
+        assert!(sut.attack_wall(config, 9, 0).is_ok());
+        let (event, payload) = rx.try_recv().unwrap();
+        let ((attacker, wall), _): ((usize, usize), usize) = bincode::decode_from_slice(&payload, config).unwrap();
+
+        assert_eq!(event, GuiEvent::AttackWall);
+        assert_eq!((attacker, wall), (0, 9));
+    }
+
+    #[test]
+    fn test_only_siege_units_batter_walls() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let sut = AttackWall::new(game_state, tx);
+
+        assert!(sut.attack_wall(config, 9, 1).is_err());
+        assert!(sut.attack_wall(config, 8, 0).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+}
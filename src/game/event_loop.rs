@@ -1,15 +1,18 @@
+use crate::common::sync;
+use crate::error::Result;
 use crate::game::GameEvent;
 use std::collections::HashMap;
 use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 
 pub type Payload = Vec<u8>;
+type HandlerRegistry = HashMap<GameEvent, Vec<Arc<Mutex<dyn Handler>>>>;
 pub trait Handler: Send + Sync {
-    fn handle(&mut self, event: &GameEvent, payload: &Payload);
+    fn handle(&mut self, event: &GameEvent, payload: &Payload) -> Result<()>;
 }
 
 pub struct EventLoop {
-    register: Arc<Mutex<HashMap<GameEvent, Vec<Arc<Mutex<dyn Handler>>>>>>,
+    register: Arc<Mutex<HandlerRegistry>>,
     rx: Receiver<(GameEvent, Payload)>,
 }
 
@@ -22,26 +25,32 @@ impl EventLoop {
     }
 
     pub fn register_handler(&self, event: GameEvent, handler: Arc<Mutex<dyn Handler>>) {
-        let mut registry = self.register.lock().unwrap();
-        registry.entry(event).or_insert_with(Vec::new).push(handler);
+        let mut registry = sync::lock(&self.register);
+        registry.entry(event).or_default().push(handler);
     }
 
     fn handle_event(&self, event: &GameEvent, payload: &Payload) {
-        let registry = self.register.lock().unwrap();
-        if let Some(handlers) = registry.get(&event) {
+        let registry = sync::lock(&self.register);
+        log::trace!("Handling {:?} with {} byte payload", event, payload.len());
+        if let Some(handlers) = registry.get(event) {
             for handler in handlers {
-                if let Ok(mut handler) = handler.lock() {
-                    handler.handle(&event, &payload);
+                if let Ok(mut handler) = handler.lock()
+                    && let Err(error) = handler.handle(event, payload)
+                {
+                    log::warn!("Handler for {:?} failed: {}", event, error);
                 }
             }
         }
     }
+    /// Blocks handling events until `GameEvent::Quit` arrives or every sender is gone.
     pub fn start(&self) {
-        loop {
-            for (event, payload) in &self.rx {
-                self.handle_event(&event, &payload);
+        for (event, payload) in &self.rx {
+            if event == GameEvent::Quit {
+                break;
             }
+            self.handle_event(&event, &payload);
         }
+        log::debug!("Event loop stopped");
     }
 }
 
@@ -56,8 +65,17 @@ mod tests {
     }
 
     impl Handler for TestHandler {
-        fn handle(&mut self, _event: &GameEvent, _payload: &Payload) {
+        fn handle(&mut self, _event: &GameEvent, _payload: &Payload) -> Result<()> {
             *self.called.lock().unwrap() = true;
+            Ok(())
+        }
+    }
+
+    struct FailingHandler;
+
+    impl Handler for FailingHandler {
+        fn handle(&mut self, _event: &GameEvent, _payload: &Payload) -> Result<()> {
+            Err(crate::error::Error::rules("always fails"))
         }
     }
 
@@ -95,7 +113,7 @@ mod tests {
         event_loop.handle_event(&GameEvent::TileClicked, &vec![]);
 
         let called = *called_flag.lock().unwrap();
-        assert_eq!(called, true);
+        assert!(called);
     }
 
     #[test]
@@ -106,4 +124,39 @@ mod tests {
 
         assert!(*called_flag.lock().unwrap());
     }
+
+    #[test]
+    fn test_start_returns_on_quit() {
+        let (tx, rx) = mpsc::channel();
+        let event_loop = EventLoop::new(rx);
+        let called_flag = Arc::new(Mutex::new(false));
+        event_loop.register_handler(
+            GameEvent::TileClicked,
+            Arc::new(Mutex::new(TestHandler {
+                called: Arc::clone(&called_flag),
+            })),
+        );
+        tx.send((GameEvent::TileClicked, vec![])).unwrap();
+        tx.send((GameEvent::Quit, vec![])).unwrap();
+
+        event_loop.start();
+        assert!(*called_flag.lock().unwrap());
+    }
+
+    #[test]
+    fn test_start_returns_when_senders_hang_up() {
+        let (tx, rx) = mpsc::channel::<(GameEvent, Payload)>();
+        drop(tx);
+        EventLoop::new(rx).start();
+    }
+
+    #[test]
+    fn test_failing_handler_does_not_stop_others() {
+        let (event_loop, called_flag, handler) = setup_event_loop_and_handler!();
+        event_loop.register_handler(GameEvent::TileClicked, Arc::new(Mutex::new(FailingHandler)));
+        event_loop.register_handler(GameEvent::TileClicked, handler);
+        event_loop.handle_event(&GameEvent::TileClicked, &vec![]);
+
+        assert!(*called_flag.lock().unwrap());
+    }
 }
@@ -4,11 +4,17 @@ pub enum GameEvent {
     TileClicked,
     WindowResized,
     MouseClicked,
+    /// Stops the event loop; sent once when the player quits.
+    Quit,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
 pub enum GuiEvent {
     BackLightTile,
-    MoveUnit
+    MoveUnit,
+    AttackUnit,
+    AttackWall,
+    CastSpell,
+    Redeploy,
 }
 
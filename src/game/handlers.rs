@@ -1,12 +1,16 @@
+use crate::battle::Phase;
+use crate::battle::footprint::anchor_of;
 use crate::common::display::WindowSize;
 use crate::common::io::MousePosition;
 use crate::display::tile::TileType;
 use crate::display::{Board, GameState};
+use crate::error::{Error, Result};
 use crate::game::event_loop::{Handler, Payload};
+use crate::game::attack_unit::AttackUnit;
+use crate::game::attack_wall::AttackWall;
 use crate::game::move_unit::MoveUnit;
 use crate::game::{GameEvent, GuiEvent};
 use bincode::config;
-use bincode::config::Configuration;
 use std::sync::{Arc, Mutex, mpsc};
 
 pub struct MouseClickHandler {
@@ -29,69 +33,139 @@ impl MouseClickHandler {
         }
     }
 
-    fn back_light_tile(&mut self, index: usize) {
+    fn back_light_tile(&mut self, index: usize) -> Result<()> {
         let config = config::standard();
-        let encoded: Vec<u8> = bincode::encode_to_vec(index, config).unwrap();
-        self.tx.send((GuiEvent::BackLightTile, encoded)).unwrap();
+        let encoded: Vec<u8> = bincode::encode_to_vec(index, config)?;
+        self.tx.send((GuiEvent::BackLightTile, encoded))?;
         self.last_selected_index = Some(index);
+        Ok(())
     }
 
-    fn handle_click_in_area(&mut self, mouse_x: f32, mouse_y: f32) {
+    /// True when the stack on tile `index` already finished its turn this round.
+    fn has_acted(&self, index: usize) -> Result<bool> {
+        let board = self.board.lock()?;
+        let tiles = self.game_state.tiles.lock()?;
+        Ok(tiles
+            .get(index)
+            .and_then(|tile| tile.get_unit())
+            .is_some_and(|unit| board.battle_state().has_acted(unit.id)))
+    }
+
+    fn handle_click_in_area(&mut self, mouse_x: f32, mouse_y: f32) -> Result<()> {
         let config = config::standard();
         let get_tile_index = {
-            let board = self.board.lock().unwrap();
-            let config = config::standard();
+            let board = self.board.lock()?;
             board.get_tile_index(mouse_x, mouse_y)
         };
 
-        let tile_index = get_tile_index;
+        // Any cell of a large stack stands for the stack itself.
+        let tile_index = match get_tile_index {
+            Some(index) => Some(anchor_of(&self.game_state.tiles.lock()?, index)),
+            None => None,
+        };
+        log::trace!("Clicked in area {}, {}", mouse_x, mouse_y);
+
+        let pending_spell = self.board.lock()?.pending_spell().map(str::to_string);
+        let acted = match tile_index {
+            Some(index) => self.has_acted(index)?,
+            None => false,
+        };
+        // Defend and Wait end a turn from the HUD without the handler seeing it.
+        if let Some(selected) = self.last_selected_index
+            && self.has_acted(selected)?
+        {
+            self.last_selected_index = None;
+        }
+        if let (Some(spell_id), Some(index)) = (pending_spell, tile_index) {
+            self.last_selected_index = None;
+            let encoded: Vec<u8> = bincode::encode_to_vec((spell_id, index), config)?;
+            self.tx.send((GuiEvent::CastSpell, encoded))?;
+            return Ok(());
+        }
+
+        let phase = self.board.lock()?.phase();
+        if let (Phase::Tactics, Some(index)) = (phase, tile_index) {
+            return self.handle_tactics_click(index);
+        }
 
         if let Some(index) = tile_index {
             let tile_type = {
-                let tiles = self.game_state.tiles.lock().unwrap();
-                tiles.get(index).unwrap().tile_type.clone()
+                let tiles = self.game_state.tiles.lock()?;
+                tiles
+                    .get(index)
+                    .ok_or_else(|| Error::rules(format!("No tile {}", index)))?
+                    .tile_type
+                    .clone()
             };
             match tile_type {
+                TileType::MyUnit if acted => {
+                    log::debug!("Unit on tile {} already acted this round", index);
+                }
                 TileType::MyUnit => {
-                    self.back_light_tile(index);
+                    self.back_light_tile(index)?;
                 }
-                TileType::Empty => {
+                TileType::Empty | TileType::SpawnPoint => {
                     // First check if there is a selected unit
                     // then if it is my unit
                     // then try too move
-                    if let Some(last_selested_index) = self.last_selected_index {
+                    if let Some(last_selested_index) = self.last_selected_index.take() {
                         let move_unit = MoveUnit::new(self.game_state.clone(), self.tx.clone());
-                        move_unit.move_unit(config, index, last_selested_index);
+                        move_unit.move_unit(config, index, last_selested_index)?;
+                    }
+                }
+                TileType::EnemyUnit => {
+                    if let Some(last_selected_index) = self.last_selected_index.take() {
+                        let attack_unit = AttackUnit::new(self.game_state.clone(), self.tx.clone());
+                        attack_unit.attack_unit(config, index, last_selected_index)?;
+                    }
+                }
+                TileType::Wall | TileType::Gate => {
+                    if let Some(last_selected_index) = self.last_selected_index.take() {
+                        let attack_wall = AttackWall::new(self.game_state.clone(), self.tx.clone());
+                        attack_wall.attack_wall(config, index, last_selected_index)?;
                     }
-                    self.last_selected_index = None;
                 }
                 _ => {}
             }
         }
-        println!(
-            "Mouse clicked Event Loop! Cliked in area {}, {}",
-            mouse_x, mouse_y
-        );
+        Ok(())
+    }
+
+    /// Before round one a click picks a stack, the next one moves it to a free spawn point.
+    fn handle_tactics_click(&mut self, index: usize) -> Result<()> {
+        let tile_type = {
+            let tiles = self.game_state.tiles.lock()?;
+            tiles.get(index).map(|tile| tile.tile_type.clone())
+        };
+        match (tile_type, self.last_selected_index.take()) {
+            (Some(TileType::MyUnit), _) => self.back_light_tile(index)?,
+            (Some(TileType::SpawnPoint), Some(selected)) => {
+                let encoded: Vec<u8> = bincode::encode_to_vec((selected, index), config::standard())?;
+                self.tx.send((GuiEvent::Redeploy, encoded))?;
+            }
+            _ => {}
+        }
+        Ok(())
     }
 }
 impl Handler for MouseClickHandler {
-    fn handle(&mut self, event: &GameEvent, payload: &Payload) {
+    fn handle(&mut self, _event: &GameEvent, payload: &Payload) -> Result<()> {
         let config = config::standard();
-        let (decoded, _): (MousePosition, usize) =
-            bincode::decode_from_slice(&payload[..], config).unwrap();
+        let (decoded, _): (MousePosition, usize) = bincode::decode_from_slice(&payload[..], config)?;
 
         let (mouse_x, mouse_y) = (decoded.0, decoded.1);
-        println!("Mouse clicked Event Loop! {}, {}", mouse_x, mouse_y);
+        log::debug!("Mouse clicked at {}, {}", mouse_x, mouse_y);
 
         let check_boundries = {
-            let board = self.board.lock().unwrap();
+            let board = self.board.lock()?;
             board.check_if_is_in_boundries(mouse_x, mouse_y)
         };
 
         if check_boundries {
-            self.handle_click_in_area(mouse_x, mouse_y);
+            self.handle_click_in_area(mouse_x, mouse_y)
         } else {
-            println!("Mouse clicked Event Loop! Out of boundries");
+            log::trace!("Mouse clicked out of boundries");
+            Ok(())
         }
     }
 }
@@ -99,9 +173,10 @@ impl Handler for MouseClickHandler {
 pub struct WindowResizeHandler {}
 
 impl Handler for WindowResizeHandler {
-    fn handle(&mut self, event: &GameEvent, payload: &Payload) {
+    fn handle(&mut self, _event: &GameEvent, payload: &Payload) -> Result<()> {
         let (window_size, _): (WindowSize, usize) =
-            bincode::decode_from_slice(payload, bincode::config::standard()).unwrap();
-        println!("Window resized Event Loop! {:?}", window_size);
+            bincode::decode_from_slice(payload, bincode::config::standard())?;
+        log::debug!("Window resized to {:?}", window_size);
+        Ok(())
     }
 }
@@ -1,4 +1,6 @@
 pub mod game_event;
+mod attack_unit;
+mod attack_wall;
 mod event_loop;
 mod handlers;
 mod move_unit;
@@ -1,9 +1,9 @@
-use bincode::config::Configuration;
+use crate::battle::movement::reachable;
 use crate::display::GameState;
-use crate::display::tile::TileType;
-use crate::display::tile::Unit;
+use crate::error::{Error, Result};
 use crate::game::GuiEvent;
 use crate::game::event_loop::Payload;
+use bincode::config::Configuration;
 use std::sync::{Arc, mpsc};
 
 pub struct MoveUnit {
@@ -16,14 +16,16 @@ impl MoveUnit {
         Self { game_state, tx }
     }
 
-    pub fn move_unit(&self, config: Configuration, index: usize, last_selected_index: usize) -> Result<(), String> {
-        let mut tiles = self.game_state.tiles.lock().map_err(|_| "Can't lock tile for move of unit")?;
-        let mut last_tile = tiles.get_mut(last_selected_index).ok_or_else(|| "Can't get last tile")?;
-        let unit = last_tile.get_unit().ok_or_else(|| "Can't get unit")?;
-        let encoded: Vec<u8> = bincode::encode_to_vec((index, unit.id), config).map_err(|_| "Serialization error")?;
-        let tile = tiles.get_mut(index).ok_or_else(|| "Can't get tile")?;
-        tile.tile_type = TileType::MyUnit;
-        self.tx.send((GuiEvent::MoveUnit, encoded)).map_err(|_| "Failed to send move unit")?;
+    pub fn move_unit(&self, config: Configuration, index: usize, last_selected_index: usize) -> Result<()> {
+        let mut tiles = self.game_state.tiles.lock()?;
+        let last_tile = tiles.get_mut(last_selected_index).ok_or_else(|| Error::rules("Can't get last tile"))?;
+        let unit = last_tile.get_unit().ok_or_else(|| Error::rules("Can't get unit"))?;
+        let encoded: Vec<u8> = bincode::encode_to_vec((index, unit.id), config)?;
+        let (move_range, flying) = (unit.effective_move_range(), unit.is_flying());
+        if !reachable(&tiles, last_selected_index, move_range, flying).contains(&index) {
+            return Err(Error::rules(format!("Tile {} is out of reach", index)));
+        }
+        self.tx.send((GuiEvent::MoveUnit, encoded))?;
         Ok(())
     }
 }
@@ -31,7 +33,10 @@ impl MoveUnit {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::battle::footprint;
+    use crate::battle::movement::is_free;
     use crate::display::GameState;
+    use crate::display::tile::{TileType, Unit};
     use crate::display::tile::Tile;
     use bincode::config;
     use std::sync::mpsc::TryRecvError; // Zmiana z mpmc na mpsc
@@ -40,10 +45,7 @@ mod tests {
     () => {{
         let (tx, rx) = mpsc::channel();
         let game_state = GameState {
-            tiles: Arc::new(Mutex::new(vec![
-                Tile::new_for_test(TileType::Empty);
-                GameState::GRID_SIZE * GameState::GRID_SIZE
-            ])),
+            tiles: Arc::new(Mutex::new(Tile::board_for_test())),
         };
         let config = config::standard();
         (tx, rx, game_state, config)
@@ -54,10 +56,11 @@ mod tests {
         let (tx, rx, game_state, config) = setup_game_state!();
         let sut = MoveUnit::new(Arc::new(game_state), tx.clone());
 
-        sut.move_unit(config, 0, 1);
+        let result = sut.move_unit(config, 0, 1);
         let response = rx.try_recv();
 
-        assert_eq!(response.is_err(), true);
+        assert!(result.is_err());
+        assert!(response.is_err());
         assert_eq!(response.err().unwrap(), TryRecvError::Empty);
     }
 
@@ -68,16 +71,52 @@ mod tests {
         let index: usize = 1;
         let mut tiles = game_state.tiles.lock().unwrap();
         if let Some(last_tile) = tiles.get_mut(last_selected_index) {
-            last_tile.unit = Some(Unit { id: 0, move_range: 2 });
+            last_tile.unit = Some(Unit::new(0, "Knights", 12, 30, 3, 2));
         }
         drop(tiles);
         let sut = MoveUnit::new(Arc::new(game_state), tx.clone());
 
 
-        sut.move_unit(config, 1, 0);
+        let result = sut.move_unit(config, index, last_selected_index);
         let response = rx.try_recv();
 
-        assert_eq!(response.is_ok(), true);
+        assert!(result.is_ok());
+        assert!(response.is_ok());
         // assert_eq!(rx.try_recv().err().unwrap(), TryRecvError::Empty);
     }
+
+    #[test]
+    fn test_moved_stack_leaves_the_ground_it_crossed() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let mut tiles = game_state.tiles.lock().unwrap();
+        tiles[0].tile_type = TileType::SpawnPoint;
+        tiles[0].set_unit(Unit::new(0, "Knights", 12, 30, 3, 2), TileType::MyUnit);
+        drop(tiles);
+        let tiles = game_state.tiles.clone();
+        let sut = MoveUnit::new(Arc::new(game_state), tx.clone());
+
+        for (from, to) in [(0, 1), (1, 2)] {
+            sut.move_unit(config, to, from).unwrap();
+            assert!(rx.try_recv().is_ok());
+            footprint::relocate(&mut tiles.lock().unwrap(), from, to).unwrap();
+        }
+
+        let tiles = tiles.lock().unwrap();
+        assert_eq!(tiles[0].tile_type, TileType::SpawnPoint);
+        assert_eq!(tiles[1].tile_type, TileType::Empty);
+        assert!(is_free(&tiles[0]) && is_free(&tiles[1]));
+        assert_eq!(tiles[2].tile_type, TileType::MyUnit);
+    }
+
+    #[test]
+    fn test_move_out_of_reach() {
+        let (tx, rx, game_state, config) = setup_game_state!();
+        let mut tiles = game_state.tiles.lock().unwrap();
+        tiles[0].unit = Some(Unit::new(0, "Knights", 12, 30, 3, 2));
+        drop(tiles);
+        let sut = MoveUnit::new(Arc::new(game_state), tx.clone());
+
+        assert!(sut.move_unit(config, 5, 0).is_err());
+        assert!(rx.try_recv().is_err());
+    }
 }
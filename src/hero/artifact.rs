@@ -0,0 +1,306 @@
+use crate::battle::status::{Stat, StatusEffect};
+use crate::display::tile::Unit;
+use crate::error::{Error, Result};
+use crate::hero::hero::PrimaryStats;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Slot {
+    Head,
+    Neck,
+    Torso,
+    Weapon,
+    Shield,
+    Ring,
+    Feet,
+    Misc,
+}
+
+impl Slot {
+    /// How many artifacts the slot holds at once.
+    pub fn capacity(&self) -> usize {
+        match self {
+            Slot::Ring => 2,
+            Slot::Misc => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// Stat changes for the hero's stacks: every stack, or only those of one creature type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnitBonus {
+    /// Unit definition id, see `data/units`; `None` covers the whole army.
+    #[serde(default)]
+    pub unit: Option<String>,
+    pub modifiers: Vec<(Stat, i32)>,
+}
+
+impl UnitBonus {
+    pub fn applies_to(&self, unit: &Unit) -> bool {
+        self.unit.is_none() || self.unit == unit.kind
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub id: String,
+    pub name: String,
+    pub slot: Slot,
+    #[serde(default)]
+    pub bonus: PrimaryStats,
+    /// Spells the hero can cast while the artifact is worn.
+    #[serde(default)]
+    pub spells: Vec<String>,
+    #[serde(default)]
+    pub unit_bonuses: Vec<UnitBonus>,
+}
+
+/// Extra bonuses for wearing every piece of a set at once.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactSet {
+    pub id: String,
+    pub name: String,
+    pub pieces: Vec<String>,
+    #[serde(default)]
+    pub bonus: PrimaryStats,
+    #[serde(default)]
+    pub unit_bonuses: Vec<UnitBonus>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactCatalog {
+    artifacts: Vec<Artifact>,
+    #[serde(default)]
+    sets: Vec<ArtifactSet>,
+}
+
+impl ArtifactCatalog {
+    pub const PATH: &'static str = "data/artifacts.ron";
+
+    pub fn from_ron(source: &str) -> std::result::Result<Self, ron::error::SpannedError> {
+        ron::from_str(source)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn artifacts(&self) -> &[Artifact] {
+        &self.artifacts
+    }
+
+    pub fn sets(&self) -> &[ArtifactSet] {
+        &self.sets
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Artifact> {
+        self.artifacts.iter().find(|artifact| artifact.id == id)
+    }
+}
+
+/// What a hero wears and carries. Only worn artifacts and completed sets count.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Equipment {
+    worn: Vec<Artifact>,
+    backpack: Vec<Artifact>,
+    sets: Vec<ArtifactSet>,
+}
+
+impl Equipment {
+    pub const BACKPACK_CAPACITY: usize = 32;
+
+    /// Equipment that knows which sets to look for.
+    pub fn with_sets(sets: Vec<ArtifactSet>) -> Self {
+        Self {
+            sets,
+            ..Self::default()
+        }
+    }
+
+    pub fn worn(&self) -> &[Artifact] {
+        &self.worn
+    }
+
+    pub fn backpack(&self) -> &[Artifact] {
+        &self.backpack
+    }
+
+    pub fn pick_up(&mut self, artifact: Artifact) -> Result<()> {
+        if self.backpack.len() >= Self::BACKPACK_CAPACITY {
+            return Err(Error::rules(format!("No room in the backpack for {}", artifact.name)));
+        }
+        self.backpack.push(artifact);
+        Ok(())
+    }
+
+    /// Moves the backpack item at `index` to its slot; a full slot sends its oldest artifact
+    /// to the backpack in exchange.
+    pub fn equip(&mut self, index: usize) -> Result<()> {
+        if index >= self.backpack.len() {
+            return Err(Error::rules(format!("No artifact {} in the backpack", index)));
+        }
+        let artifact = self.backpack.remove(index);
+        let slot = artifact.slot;
+        if self.worn.iter().filter(|worn| worn.slot == slot).count() >= slot.capacity()
+            && let Some(position) = self.worn.iter().position(|worn| worn.slot == slot)
+        {
+            let swapped = self.worn.remove(position);
+            self.backpack.push(swapped);
+        }
+        self.worn.push(artifact);
+        Ok(())
+    }
+
+    pub fn unequip(&mut self, id: &str) -> Result<()> {
+        let position = self
+            .worn
+            .iter()
+            .position(|artifact| artifact.id == id)
+            .ok_or_else(|| Error::rules(format!("{} is not worn", id)))?;
+        if self.backpack.len() >= Self::BACKPACK_CAPACITY {
+            return Err(Error::rules("The backpack is full"));
+        }
+        let artifact = self.worn.remove(position);
+        self.backpack.push(artifact);
+        Ok(())
+    }
+
+    pub fn active_sets(&self) -> Vec<&ArtifactSet> {
+        self.sets
+            .iter()
+            .filter(|set| set.pieces.iter().all(|piece| self.worn.iter().any(|worn| &worn.id == piece)))
+            .collect()
+    }
+
+    pub fn bonus(&self) -> PrimaryStats {
+        let worn = self.worn.iter().map(|artifact| artifact.bonus);
+        let sets = self.active_sets().into_iter().map(|set| set.bonus);
+        worn.chain(sets).fold(PrimaryStats::default(), |total, bonus| total + bonus)
+    }
+
+    pub fn grants_spell(&self, spell_id: &str) -> bool {
+        self.worn
+            .iter()
+            .any(|artifact| artifact.spells.iter().any(|spell| spell == spell_id))
+    }
+
+    /// One permanent status effect per worn artifact or completed set that covers `unit`.
+    pub fn effects_for(&self, unit: &Unit) -> Vec<StatusEffect> {
+        let worn = self
+            .worn
+            .iter()
+            .map(|artifact| (&artifact.id, &artifact.name, &artifact.unit_bonuses));
+        let sets = self
+            .active_sets()
+            .into_iter()
+            .map(|set| (&set.id, &set.name, &set.unit_bonuses));
+        worn.chain(sets)
+            .filter_map(|(id, name, bonuses)| {
+                let modifiers: Vec<(Stat, i32)> = bonuses
+                    .iter()
+                    .filter(|bonus| bonus.applies_to(unit))
+                    .flat_map(|bonus| bonus.modifiers.iter().copied())
+                    .collect();
+                (!modifiers.is_empty()).then(|| StatusEffect {
+                    modifiers,
+                    ..StatusEffect::new(id, name, None)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> ArtifactCatalog {
+        ArtifactCatalog::load(ArtifactCatalog::PATH).unwrap()
+    }
+
+    fn equipment_with(ids: &[&str]) -> Equipment {
+        let catalog = catalog();
+        let mut equipment = Equipment::with_sets(catalog.sets().to_vec());
+        for id in ids {
+            equipment.pick_up(catalog.get(id).unwrap().clone()).unwrap();
+            equipment.equip(equipment.backpack().len() - 1).unwrap();
+        }
+        equipment
+    }
+
+    #[test]
+    fn test_bundled_catalog_parses() {
+        let catalog = catalog();
+        assert!(catalog.get("centaur_axe").is_some());
+        assert!(!catalog.sets().is_empty());
+    }
+
+    #[test]
+    fn test_full_slot_swaps_into_backpack() {
+        let equipment = equipment_with(&["centaur_axe", "royal_sword"]);
+        assert_eq!(equipment.worn()[0].id, "royal_sword");
+        assert_eq!(equipment.backpack()[0].id, "centaur_axe");
+        assert_eq!(equipment.bonus().attack, 1);
+    }
+
+    #[test]
+    fn test_rings_take_two_slots() {
+        let catalog = catalog();
+        let mut equipment = Equipment::default();
+        let ring = catalog.get("ring_of_the_wayfarer").unwrap().clone();
+        for _ in 0..3 {
+            equipment.pick_up(ring.clone()).unwrap();
+            equipment.equip(equipment.backpack().len() - 1).unwrap();
+        }
+        assert_eq!((equipment.worn().len(), equipment.backpack().len()), (2, 1));
+    }
+
+    #[test]
+    fn test_set_bonus_needs_every_piece() {
+        let partial = equipment_with(&["royal_helm", "royal_cuirass"]);
+        assert!(partial.active_sets().is_empty());
+        assert_eq!(partial.bonus().defence, 2);
+
+        let full = equipment_with(&["royal_helm", "royal_cuirass", "royal_sword"]);
+        assert_eq!(full.active_sets().len(), 1);
+        assert_eq!((full.bonus().attack, full.bonus().defence), (3, 4));
+    }
+
+    #[test]
+    fn test_unit_bonuses_become_status_effects() {
+        let equipment = equipment_with(&["bow_of_elven_cherrywood", "necklace_of_swiftness"]);
+        let archers = Unit::new(0, "Archers", 10, 10, 2, 2).with_kind("archer");
+        let knights = Unit::new(1, "Knights", 10, 30, 3, 2).with_kind("knight");
+
+        let mut effects = equipment.effects_for(&archers);
+        assert_eq!(effects.len(), 2);
+        assert_eq!(equipment.effects_for(&knights).len(), 1);
+
+        let mut archers = archers;
+        for effect in effects.drain(..) {
+            archers.add_effect(effect);
+        }
+        assert_eq!((archers.effective_damage(), archers.effective_move_range()), (3, 3));
+    }
+
+    #[test]
+    fn test_worn_tome_grants_spells() {
+        let mut equipment = equipment_with(&["tome_of_fire"]);
+        assert!(equipment.grants_spell("fireball"));
+        equipment.unequip("tome_of_fire").unwrap();
+        assert!(!equipment.grants_spell("fireball"));
+    }
+
+    #[test]
+    fn test_backpack_is_limited() {
+        let axe = catalog().get("centaur_axe").unwrap().clone();
+        let mut equipment = Equipment::default();
+        for _ in 0..Equipment::BACKPACK_CAPACITY {
+            equipment.pick_up(axe.clone()).unwrap();
+        }
+        assert!(equipment.pick_up(axe).is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use crate::battle::result::BattleResult;
+use crate::battle::spell::Spell;
+use crate::battle::status::{Stat, StatusEffect};
+use crate::error::{Error, Result};
+use crate::display::tile::Unit;
+use crate::hero::artifact::Equipment;
+use crate::hero::skill::{SecondarySkill, SkillLevel};
+use serde::{Deserialize, Serialize};
+use std::ops::Add;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrimaryStats {
+    #[serde(default)]
+    pub attack: u32,
+    #[serde(default)]
+    pub defence: u32,
+    #[serde(default)]
+    pub spell_power: u32,
+    #[serde(default)]
+    pub knowledge: u32,
+}
+
+impl Add for PrimaryStats {
+    type Output = PrimaryStats;
+
+    fn add(self, other: PrimaryStats) -> PrimaryStats {
+        PrimaryStats {
+            attack: self.attack + other.attack,
+            defence: self.defence + other.defence,
+            spell_power: self.spell_power + other.spell_power,
+            knowledge: self.knowledge + other.knowledge,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrimaryStat {
+    Attack,
+    Defence,
+    SpellPower,
+    Knowledge,
+}
+
+impl PrimaryStat {
+    /// Order in which levels raise primary stats.
+    pub const CYCLE: [PrimaryStat; 4] = [
+        PrimaryStat::Attack,
+        PrimaryStat::Defence,
+        PrimaryStat::SpellPower,
+        PrimaryStat::Knowledge,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            PrimaryStat::Attack => "stat.attack",
+            PrimaryStat::Defence => "stat.defence",
+            PrimaryStat::SpellPower => "stat.spell_power",
+            PrimaryStat::Knowledge => "stat.knowledge",
+        }
+    }
+}
+
+/// A level gained and waiting for the player to pick one of `choices`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LevelUp {
+    pub level: u32,
+    pub stat: PrimaryStat,
+    pub choices: Vec<(SecondarySkill, SkillLevel)>,
+}
+
+/// The commander of an army: lends its stats to every stack and casts its spells.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hero {
+    pub name: String,
+    pub level: u32,
+    pub experience: u32,
+    stats: PrimaryStats,
+    pub mana: u32,
+    spells: Vec<String>,
+    skills: Vec<(SecondarySkill, SkillLevel)>,
+    pub equipment: Equipment,
+    level_ups: Vec<LevelUp>,
+}
+
+impl Hero {
+    pub const COMMAND_EFFECT_ID: &'static str = "hero";
+    pub const MANA_PER_KNOWLEDGE: u32 = 10;
+    pub const MAX_SKILLS: usize = 8;
+    /// Experience needed for levels 2 to 12; later levels each need a fifth more.
+    pub const LEVEL_EXPERIENCE: [u32; 11] = [1000, 2000, 3200, 4600, 6200, 8000, 10000, 12200, 14700, 17500, 20600];
+
+    pub fn new(name: &str, stats: PrimaryStats) -> Self {
+        let mut hero = Self {
+            name: name.to_string(),
+            level: 1,
+            experience: 0,
+            stats,
+            mana: 0,
+            spells: Vec::new(),
+            skills: Vec::new(),
+            equipment: Equipment::default(),
+            level_ups: Vec::new(),
+        };
+        hero.mana = hero.max_mana();
+        hero
+    }
+
+    /// Base stats plus everything worn artifacts and completed sets add.
+    pub fn stats(&self) -> PrimaryStats {
+        self.stats + self.equipment.bonus()
+    }
+
+    pub fn skill_level(&self, skill: SecondarySkill) -> Option<SkillLevel> {
+        self.skills
+            .iter()
+            .find(|(known, _)| *known == skill)
+            .map(|(_, level)| *level)
+    }
+
+    fn skill_rank(&self, skill: SecondarySkill) -> u32 {
+        self.skill_level(skill).map_or(0, |level| level.rank())
+    }
+
+    pub fn skills(&self) -> &[(SecondarySkill, SkillLevel)] {
+        &self.skills
+    }
+
+    /// Learns `skill` at `level`, or raises it if already known.
+    pub fn learn_skill(&mut self, skill: SecondarySkill, level: SkillLevel) {
+        match self.skills.iter_mut().find(|(known, _)| *known == skill) {
+            Some((_, known_level)) => *known_level = level.max(*known_level),
+            None => self.skills.push((skill, level)),
+        }
+    }
+
+    pub fn max_mana(&self) -> u32 {
+        let base = self.stats().knowledge * Self::MANA_PER_KNOWLEDGE;
+        base + base * self.skill_rank(SecondarySkill::Intelligence) / 4
+    }
+
+    pub fn spells(&self) -> &[String] {
+        &self.spells
+    }
+
+    /// Learned spells and those granted by worn artifacts.
+    pub fn knows_spell(&self, spell_id: &str) -> bool {
+        self.spells.iter().any(|known| known == spell_id) || self.equipment.grants_spell(spell_id)
+    }
+
+    /// Levels 1 and 2 are open to everyone, each rank of Wisdom opens one more.
+    pub fn max_spell_level(&self) -> u8 {
+        2 + self.skill_rank(SecondarySkill::Wisdom) as u8
+    }
+
+    pub fn learn_spell(&mut self, spell: &Spell) -> Result<()> {
+        if spell.level > self.max_spell_level() {
+            return Err(Error::rules(format!("{} needs more Wisdom to learn {}", self.name, spell.name)));
+        }
+        if !self.spells.contains(&spell.id) {
+            self.spells.push(spell.id.clone());
+        }
+        Ok(())
+    }
+
+    /// The permanent effect through which the hero's stats and skills reach every stack.
+    pub fn command_effect(&self) -> StatusEffect {
+        let stats = self.stats();
+        StatusEffect::new(Self::COMMAND_EFFECT_ID, &self.name, None)
+            .with_modifier(Stat::Attack, (stats.attack + self.skill_rank(SecondarySkill::Offense)) as i32)
+            .with_modifier(Stat::Defence, (stats.defence + self.skill_rank(SecondarySkill::Armorer)) as i32)
+            .with_modifier(Stat::Speed, self.skill_rank(SecondarySkill::Logistics).div_ceil(2) as i32)
+            .with_modifier(Stat::Morale, self.skill_rank(SecondarySkill::Leadership) as i32)
+            .with_modifier(Stat::Luck, self.skill_rank(SecondarySkill::Luck) as i32)
+    }
+
+    /// Everything the hero lends to `unit`: the command effect plus artifact bonuses for it.
+    pub fn effects_for(&self, unit: &Unit) -> Vec<StatusEffect> {
+        let mut effects = vec![self.command_effect()];
+        effects.extend(self.equipment.effects_for(unit));
+        effects
+    }
+
+    pub fn experience_for_level(level: u32) -> u32 {
+        match level {
+            0 | 1 => 0,
+            2..=12 => Self::LEVEL_EXPERIENCE[level as usize - 2],
+            _ => {
+                let last = *Self::LEVEL_EXPERIENCE.last().unwrap_or(&0);
+                (13..=level).fold(last, |needed, _| needed + needed / 5)
+            }
+        }
+    }
+
+    /// Takes the experience and mana left after a battle; returns how many levels were gained.
+    pub fn finish_battle(&mut self, result: &BattleResult, mana_left: u32) -> u32 {
+        self.mana = mana_left.min(self.max_mana());
+        self.gain_experience(result.experience)
+    }
+
+    pub fn gain_experience(&mut self, experience: u32) -> u32 {
+        self.experience += experience;
+        let mut gained = 0;
+        while self.experience >= Self::experience_for_level(self.level + 1) {
+            self.level += 1;
+            gained += 1;
+            let stat = PrimaryStat::CYCLE[(self.level as usize - 2) % PrimaryStat::CYCLE.len()];
+            self.raise(stat);
+            let choices = self.skill_choices();
+            if !choices.is_empty() {
+                self.level_ups.push(LevelUp {
+                    level: self.level,
+                    stat,
+                    choices,
+                });
+            }
+        }
+        gained
+    }
+
+    fn raise(&mut self, stat: PrimaryStat) {
+        match stat {
+            PrimaryStat::Attack => self.stats.attack += 1,
+            PrimaryStat::Defence => self.stats.defence += 1,
+            PrimaryStat::SpellPower => self.stats.spell_power += 1,
+            PrimaryStat::Knowledge => self.stats.knowledge += 1,
+        }
+    }
+
+    /// One upgrade of a known skill and one new skill, when there are any to offer.
+    fn skill_choices(&self) -> Vec<(SecondarySkill, SkillLevel)> {
+        let upgrade = self
+            .skills
+            .iter()
+            .find_map(|(skill, level)| level.next().map(|next| (*skill, next)));
+        let new_skill = SecondarySkill::ALL
+            .iter()
+            .find(|skill| self.skill_level(**skill).is_none())
+            .filter(|_| self.skills.len() < Self::MAX_SKILLS)
+            .map(|skill| (*skill, SkillLevel::Basic));
+        upgrade.into_iter().chain(new_skill).collect()
+    }
+
+    pub fn pending_level_ups(&self) -> &[LevelUp] {
+        &self.level_ups
+    }
+
+    /// Resolves the oldest pending level-up with the skill at `choice`. The next one offers
+    /// choices from the skills as they are now; it is dropped when none are left to offer.
+    pub fn choose_skill(&mut self, choice: usize) -> Result<()> {
+        if self.level_ups.is_empty() {
+            return Err(Error::rules(format!("{} has no level-up to resolve", self.name)));
+        }
+        let Some(&(skill, level)) = self.level_ups[0].choices.get(choice) else {
+            return Err(Error::rules(format!("No skill choice {}", choice)));
+        };
+        self.level_ups.remove(0);
+        self.learn_skill(skill, level);
+        let choices = self.skill_choices();
+        if choices.is_empty() {
+            self.level_ups.clear();
+        } else if let Some(next) = self.level_ups.first_mut() {
+            next.choices = choices;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::spell::{School, SpellEffect, Targeting};
+    use crate::hero::artifact::ArtifactCatalog;
+
+    fn hero() -> Hero {
+        Hero::new(
+            "Roland",
+            PrimaryStats {
+                attack: 2,
+                defence: 1,
+                spell_power: 1,
+                knowledge: 3,
+            },
+        )
+    }
+
+    fn spell(level: u8) -> Spell {
+        Spell {
+            id: format!("level_{}", level),
+            name: format!("Level {}", level),
+            school: School::Fire,
+            level,
+            mana_cost: 5,
+            targeting: Targeting::SingleUnit { friendly: false },
+            effect: SpellEffect::Damage(10),
+        }
+    }
+
+    fn result(experience: u32) -> BattleResult {
+        BattleResult {
+            victory: true,
+            experience,
+            ..BattleResult::default()
+        }
+    }
+
+    #[test]
+    fn test_command_effect_raises_every_stack() {
+        let mut hero = hero();
+        hero.learn_skill(SecondarySkill::Offense, SkillLevel::Advanced);
+        let mut knights = Unit::new(0, "Knights", 10, 30, 3, 2).with_skills(6, 6);
+
+        knights.add_effect(hero.command_effect());
+
+        assert_eq!((knights.effective_attack(), knights.effective_defence()), (10, 7));
+    }
+
+    #[test]
+    fn test_leadership_and_luck_reach_stacks() {
+        let mut hero = hero();
+        hero.learn_skill(SecondarySkill::Leadership, SkillLevel::Advanced);
+        hero.learn_skill(SecondarySkill::Luck, SkillLevel::Basic);
+        let mut knights = Unit::new(0, "Knights", 10, 30, 3, 2);
+
+        knights.add_effect(hero.command_effect());
+
+        assert_eq!((knights.effective_morale(), knights.effective_luck()), (2, 1));
+    }
+
+    #[test]
+    fn test_mana_follows_knowledge_and_intelligence() {
+        let mut hero = hero();
+        assert_eq!(hero.mana, 30);
+        hero.learn_skill(SecondarySkill::Intelligence, SkillLevel::Expert);
+        assert_eq!(hero.max_mana(), 52);
+    }
+
+    #[test]
+    fn test_wisdom_gates_spell_levels() {
+        let mut hero = hero();
+        assert!(hero.learn_spell(&spell(2)).is_ok());
+        assert!(hero.learn_spell(&spell(3)).is_err());
+        hero.learn_skill(SecondarySkill::Wisdom, SkillLevel::Basic);
+        assert!(hero.learn_spell(&spell(3)).is_ok());
+        assert_eq!(hero.spells().len(), 2);
+    }
+
+    #[test]
+    fn test_experience_thresholds() {
+        assert_eq!(Hero::experience_for_level(2), 1000);
+        assert_eq!(Hero::experience_for_level(12), 20600);
+        assert_eq!(Hero::experience_for_level(13), 24720);
+    }
+
+    #[test]
+    fn test_battle_result_levels_up() {
+        let mut hero = hero();
+        let gained = hero.finish_battle(&result(2500), 10);
+
+        assert_eq!((gained, hero.level, hero.mana), (2, 3, 10));
+        assert_eq!(hero.stats().attack, 3);
+        assert_eq!(hero.stats().defence, 2);
+        assert_eq!(hero.pending_level_ups().len(), 2);
+    }
+
+    #[test]
+    fn test_level_up_skill_choices() {
+        let mut hero = hero();
+        hero.learn_skill(SecondarySkill::Offense, SkillLevel::Basic);
+        hero.gain_experience(1000);
+
+        let level_up = &hero.pending_level_ups()[0];
+        assert_eq!(
+            level_up.choices,
+            vec![(SecondarySkill::Offense, SkillLevel::Advanced), (SecondarySkill::Armorer, SkillLevel::Basic)]
+        );
+        hero.choose_skill(1).unwrap();
+        assert_eq!(hero.skill_level(SecondarySkill::Armorer), Some(SkillLevel::Basic));
+        assert!(hero.choose_skill(0).is_err());
+    }
+
+    #[test]
+    fn test_next_level_up_offers_skills_as_they_are_now() {
+        let mut hero = hero();
+        hero.gain_experience(2500);
+        hero.choose_skill(0).unwrap();
+
+        assert_eq!(hero.skill_level(SecondarySkill::Offense), Some(SkillLevel::Basic));
+        assert_eq!(hero.pending_level_ups()[0].choices[0], (SecondarySkill::Offense, SkillLevel::Advanced));
+    }
+
+    #[test]
+    fn test_artifacts_flow_into_stacks() {
+        let catalog = ArtifactCatalog::load(ArtifactCatalog::PATH).unwrap();
+        let mut hero = hero();
+        hero.equipment.pick_up(catalog.get("centaur_axe").unwrap().clone()).unwrap();
+        hero.equipment.pick_up(catalog.get("necklace_of_swiftness").unwrap().clone()).unwrap();
+        hero.equipment.equip(0).unwrap();
+        hero.equipment.equip(0).unwrap();
+        let mut knights = Unit::new(0, "Knights", 10, 30, 3, 2);
+
+        for effect in hero.effects_for(&knights) {
+            knights.add_effect(effect);
+        }
+
+        assert_eq!(hero.stats().attack, 4);
+        assert_eq!((knights.effective_attack(), knights.effective_move_range()), (4, 3));
+    }
+}
@@ -0,0 +1,8 @@
+pub mod artifact;
+#[allow(clippy::module_inception)]
+pub mod hero;
+pub mod skill;
+
+pub use artifact::{Artifact, ArtifactCatalog, ArtifactSet, Equipment, Slot, UnitBonus};
+pub use hero::{Hero, LevelUp, PrimaryStat, PrimaryStats};
+pub use skill::{SecondarySkill, SkillLevel};
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SecondarySkill {
+    /// Attack bonus for every stack.
+    Offense,
+    /// Defence bonus for every stack.
+    Armorer,
+    /// Extra tiles of movement for every stack.
+    Logistics,
+    /// Lets the hero learn higher level spells.
+    Wisdom,
+    /// More mana per point of knowledge.
+    Intelligence,
+    /// Lets the army rearrange inside its spawn zone before round one.
+    Tactics,
+    /// Morale bonus for every stack.
+    Leadership,
+    /// Luck bonus for every stack.
+    Luck,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SkillLevel {
+    Basic,
+    Advanced,
+    Expert,
+}
+
+impl SecondarySkill {
+    pub const ALL: [SecondarySkill; 8] = [
+        SecondarySkill::Offense,
+        SecondarySkill::Armorer,
+        SecondarySkill::Logistics,
+        SecondarySkill::Wisdom,
+        SecondarySkill::Intelligence,
+        SecondarySkill::Tactics,
+        SecondarySkill::Leadership,
+        SecondarySkill::Luck,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SecondarySkill::Offense => "skill.offense",
+            SecondarySkill::Armorer => "skill.armorer",
+            SecondarySkill::Logistics => "skill.logistics",
+            SecondarySkill::Wisdom => "skill.wisdom",
+            SecondarySkill::Intelligence => "skill.intelligence",
+            SecondarySkill::Tactics => "skill.tactics",
+            SecondarySkill::Leadership => "skill.leadership",
+            SecondarySkill::Luck => "skill.luck",
+        }
+    }
+}
+
+impl SkillLevel {
+    /// 1 for Basic up to 3 for Expert.
+    pub fn rank(&self) -> u32 {
+        match self {
+            SkillLevel::Basic => 1,
+            SkillLevel::Advanced => 2,
+            SkillLevel::Expert => 3,
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SkillLevel::Basic => "skill.level.basic",
+            SkillLevel::Advanced => "skill.level.advanced",
+            SkillLevel::Expert => "skill.level.expert",
+        }
+    }
+
+    pub fn next(&self) -> Option<SkillLevel> {
+        match self {
+            SkillLevel::Basic => Some(SkillLevel::Advanced),
+            SkillLevel::Advanced => Some(SkillLevel::Expert),
+            SkillLevel::Expert => None,
+        }
+    }
+}
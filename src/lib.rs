@@ -0,0 +1,9 @@
+pub mod battle;
+pub mod common;
+pub mod display;
+pub mod error;
+pub mod game;
+pub mod hero;
+pub mod world;
+
+pub use error::{Error, Result};
@@ -1,103 +1,236 @@
-mod common;
-mod display;
-mod game;
-
-use crate::common::io::MousePosition;
-use crate::game::{GameEvent, GuiEvent};
-use bincode::{config};
+use audax::battle::save::{self, BattleSave};
+use audax::battle::{Army, BattleEvent, Battlefield, Phase, Roster, Scenario, Side, movement};
+use audax::common::io::MousePosition;
+use audax::common::logging;
+use audax::common::rng::GameRng;
+use audax::common::sync;
+use audax::display;
+use audax::{Error, Result};
+use audax::common::settings::{SettingChange, Settings};
+use audax::display::{AdventureAction, AdventureRenderer, BattleAction, Board, MenuCommand, MenuScreen, TownAction};
+use audax::display::tile::{Tile, TileType, Unit};
+use audax::game;
+use audax::hero::{ArtifactCatalog, Equipment, Hero, PrimaryStats, SecondarySkill, SkillLevel};
+use audax::game::{GameEvent, GuiEvent};
+use audax::world::{Adventure, AdventureMap, Encounter, Foe, Terrain, TownDefinition};
+use bincode::config;
+use bincode::config::Configuration;
 use macroquad::prelude::*;
+use macroquad::miniquad::date;
+use std::path::Path;
 use std::sync::{Arc, Mutex, mpsc};
-use crate::display::{Board, GameState};
-use crate::display::tile::Tile;
-
-fn back_light_tiles(move_count: usize, tiles_size: usize, tile_index: usize, tiles: &mut std::sync::MutexGuard<'_, Vec<Tile>>) {
-    for i in 0..move_count {
-        if i < tiles_size {
-            if (tile_index >= GameState::GRID_SIZE) {
-                let minus_row = tile_index.checked_sub(GameState::GRID_SIZE * i);
-                if let Some(minus_row) = minus_row {
-                    if let Some(tile) = tiles.get_mut(minus_row) {
-                        tile.back_light = true;
-                    }
-                }
-            }
-
-            if tile_index <= (GameState::GRID_SIZE * GameState::GRID_SIZE - 1) {
-                let plus_row = tile_index.checked_add(GameState::GRID_SIZE * i);
-                if let Some(plus_row) = plus_row {
-                    if let Some(tile) = tiles.get_mut(plus_row) {
-                        tile.back_light = true;
-                    }
-                }
-            }
 
-            if tile_index % GameState::GRID_SIZE != 0 {
-                let minus_column = tile_index.checked_sub(1 * i);
-                if let Some(minus_column) = minus_column {
-                    if (minus_column % GameState::GRID_SIZE <= tile_index % GameState::GRID_SIZE) {
-                        if let Some(tile) = tiles.get_mut(minus_column) {
-                            tile.back_light = true;
-                        }
-                    }
-                }
-            }
+/// Which screen takes the input: the adventure map, a town on it, the kingdom overview, the
+/// battle of an encounter or a hand-made battle started with `--battle <map file>`.
+enum Mode {
+    Adventure,
+    Town(usize),
+    Kingdom,
+    Battle(Encounter),
+    Scenario,
+}
 
-            if tile_index >= GameState::GRID_SIZE && (tile_index % GameState::GRID_SIZE != 0) {
-                let corner_column_left = tile_index.checked_sub(GameState::GRID_SIZE + 1 * i);
-                if let Some(corner_column_left) = corner_column_left {
-                    if let Some(tile) = tiles.get_mut(corner_column_left) {
-                        tile.back_light = true;
-                    }
-                }
-            }
+fn back_light_tiles(unit: &Unit, tile_index: usize, tiles: &mut [Tile]) {
+    let range = unit.effective_move_range();
+    for index in movement::reachable(tiles, tile_index, range, unit.is_flying()) {
+        tiles[index].back_light = true;
+    }
+    if unit.siege_damage().is_some() {
+        for tile in tiles.iter_mut().filter(|tile| matches!(tile.tile_type, TileType::Wall | TileType::Gate)) {
+            tile.back_light = true;
+        }
+    }
+}
 
-            if tile_index >= GameState::GRID_SIZE && ((tile_index + 1) % GameState::GRID_SIZE != 0) {
-                let corner_column_rh = tile_index.checked_sub(GameState::GRID_SIZE - 1 * i);
-                if let Some(corner_column_rh) = corner_column_rh {
-                    if (corner_column_rh % GameState::GRID_SIZE >= tile_index % GameState::GRID_SIZE) {
-                        if let Some(tile) = tiles.get_mut(corner_column_rh) {
-                            tile.back_light = true;
-                        }
-                    }
+fn handle_gui_event(board: &Mutex<Board>, event: GuiEvent, payload: &[u8], config: Configuration) -> Result<()> {
+    match event {
+        GuiEvent::BackLightTile => {
+            let (tile_index, _): (usize, usize) = bincode::decode_from_slice(payload, config)?;
+            log::debug!("Backlighting tile at index: {}", tile_index);
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(Some(tile_index));
+            let mut tiles = board_guard.game_state.tiles.lock()?;
+            if board_guard.phase() == Phase::Tactics {
+                drop(tiles);
+                let targets = board_guard.redeploy_targets(tile_index);
+                let mut tiles = board_guard.game_state.tiles.lock()?;
+                for index in targets.into_iter().chain([tile_index]) {
+                    tiles[index].back_light = true;
                 }
-            }
-
-            if tile_index <= (GameState::GRID_SIZE * GameState::GRID_SIZE - 1) && (tile_index % GameState::GRID_SIZE) != 0 {
-                let corner_column_left_down = tile_index.checked_add(GameState::GRID_SIZE - 1 * i);
-                if let Some(corner_column_left_down) = corner_column_left_down {
-                    if let Some(tile) = tiles.get_mut(corner_column_left_down) {
-                        tile.back_light = true;
-                    }
+            } else if let Some(tile) = tiles.get_mut(tile_index) {
+                tile.back_light = true;
+                if let Some(unit) = tile.get_unit().cloned() {
+                    back_light_tiles(&unit, tile_index, &mut tiles);
                 }
             }
+        }
+        GuiEvent::Redeploy => {
+            let ((from, to), _): ((usize, usize), usize) = bincode::decode_from_slice(payload, config)?;
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(None);
+            board_guard.redeploy(from, to)?;
+        }
+        GuiEvent::MoveUnit => {
+            let ((tile_index, unit_id), _): ((usize, usize), usize) = bincode::decode_from_slice(payload, config)?;
+            log::debug!("Move tile at index: {}", tile_index);
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(None);
+            board_guard.move_unit(tile_index, unit_id)?;
+            end_battle_if_over(&mut board_guard);
+        }
+        GuiEvent::AttackUnit => {
+            let ((attacker_index, defender_index), _): ((usize, usize), usize) =
+                bincode::decode_from_slice(payload, config)?;
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(None);
+            board_guard.attack_unit(attacker_index, defender_index)?;
+            end_battle_if_over(&mut board_guard);
+        }
+        GuiEvent::AttackWall => {
+            let ((attacker_index, wall_index), _): ((usize, usize), usize) =
+                bincode::decode_from_slice(payload, config)?;
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(None);
+            board_guard.attack_wall(attacker_index, wall_index)?;
+            end_battle_if_over(&mut board_guard);
+        }
+        GuiEvent::CastSpell => {
+            let ((spell_id, target), _): ((String, usize), usize) = bincode::decode_from_slice(payload, config)?;
+            let mut board_guard = board.lock()?;
+            board_guard.reset_back_light_all_tiles();
+            board_guard.set_active_tile(None);
+            board_guard.cast_spell(&spell_id, target)?;
+            end_battle_if_over(&mut board_guard);
+        }
+    }
+    Ok(())
+}
 
+fn finish_turn(board: &mut Board, unit_id: usize) {
+    board.reset_back_light_all_tiles();
+    board.set_active_tile(None);
+    if let Err(error) = board.finish_turn(unit_id) {
+        log::warn!("Can't finish turn: {}", error);
+    }
+    end_battle_if_over(board);
+}
 
-            if ((tile_index + 1) % GameState::GRID_SIZE) != 0 {
-                let plus_column = tile_index.checked_add(1);
-                if let Some(plus_column) = plus_column {
-                    if let Some(tile) = tiles.get_mut(plus_column) {
-                        tile.back_light = true;
-                    }
+/// Carries out a System menu entry; returns true once the player confirmed quitting.
+fn run_menu_command(board: &mut Board, settings: &mut Settings, command: MenuCommand) -> Result<bool> {
+    match command {
+        MenuCommand::Show(screen) => {
+            if let Some(menu) = board.system_menu_mut() {
+                if matches!(screen, MenuScreen::Save | MenuScreen::Load) {
+                    menu.set_slots(save::slot_summaries());
                 }
+                menu.show(screen);
             }
-
-            if tile_index <= (GameState::GRID_SIZE * GameState::GRID_SIZE - 1) && ((tile_index + 1) % GameState::GRID_SIZE) != 0 {
-                let corner_column_rh_down = tile_index.checked_add(GameState::GRID_SIZE + 1);
-                if let Some(corner_column_rh_down) = corner_column_rh_down {
-                    if let Some(tile) = tiles.get_mut(corner_column_rh_down) {
-                        tile.back_light = true;
-                    }
+        }
+        MenuCommand::Resume => board.close_system_menu(),
+        MenuCommand::Save(slot) => {
+            let path = save::slot_path(slot);
+            board.save()?.write_to(&path)?;
+            log::info!("Battle saved to {}", path);
+            board.close_system_menu();
+        }
+        MenuCommand::Load(slot) => {
+            let path = save::slot_path(slot);
+            board.restore(BattleSave::read_from(&path)?)?;
+            log::info!("Battle loaded from {}", path);
+            board.close_system_menu();
+        }
+        MenuCommand::Change(change) => {
+            settings.cycle(change);
+            if let Err(error) = settings.save(Settings::PATH) {
+                log::warn!("Can't save settings: {}", error);
+            }
+            match change {
+                SettingChange::Resolution => {
+                    request_new_screen_size(settings.resolution.0 as f32, settings.resolution.1 as f32)
                 }
+                SettingChange::Fullscreen => set_fullscreen(settings.fullscreen),
+                SettingChange::Language => board.set_language(settings.language),
+                _ => {}
             }
         }
+        MenuCommand::Quit => return Ok(true),
+    }
+    Ok(false)
+}
+
+fn end_battle_if_over(board: &mut Board) {
+    if let Some(victory) = board.victory() {
+        board.record(BattleEvent::BattleEnded { victory });
+        match board.finish_battle(victory) {
+            Ok(result) => log::info!("Battle over: {:?}", result),
+            Err(error) => log::warn!("Can't finish battle: {}", error),
+        }
+        if let Err(error) = board.combat_log().export_to(Path::new("combat_log.txt")) {
+            log::error!("Can't export combat log: {}", error);
+        }
+    }
+}
+
+/// Whether the selected hero stands in the player's town on `tile`.
+fn visits_town(adventure: &Adventure, tile: usize) -> bool {
+    adventure.selected().is_some_and(|hero| hero.position == tile)
+        && adventure.town(tile).is_some_and(|town| town.owned)
+}
+
+/// Clears the board for a field generated from the ground the fight happens on and lines up
+/// the encounter's hero against the neutral stack, or behind the walls of the town it
+/// attacks against the garrison.
+fn start_battle(board: &mut Board, adventure: &Adventure, encounter: &Encounter, mut rng: GameRng) -> Result<()> {
+    let army = adventure
+        .heroes()
+        .get(encounter.hero)
+        .map(|hero| hero.army.clone())
+        .ok_or_else(|| Error::rules(format!("No hero {} on the map", encounter.hero)))?;
+    let terrain = adventure.map.terrain(encounter.tile).unwrap_or(Terrain::Grass);
+    let seed = rng.next_u64();
+    log::info!("Battlefield: {:?}, seed {}", terrain, seed);
+    board.new_battle(rng, Battlefield::generate(terrain, seed))?;
+    if encounter.foe == Foe::Garrison {
+        board.fortify()?;
     }
+    board.deploy(Side::Player, army)?;
+    board.deploy(Side::Enemy, adventure.enemy_army(encounter)?)?;
+    if board.begin_tactics() {
+        log::info!("Tactics: rearrange your stacks, then start the battle");
+    }
+    Ok(())
+}
+
+/// Clears the board for the battle map at `path`, its stacks taken from the kingdom roster.
+fn start_scenario(board: &mut Board, path: &str, rng: GameRng) -> Result<()> {
+    let scenario = Scenario::load(path, &Roster::load(Roster::KINGDOM)?)?;
+    log::info!("Scenario: {}", scenario.name);
+    board.start_scenario(rng, &scenario)
+}
+
+fn window_conf() -> Conf {
+    Settings::load_or_default(Settings::PATH).conf()
 }
-#[macroquad::main("Grid Example")]
+
+#[macroquad::main(window_conf)]
 async fn main() {
-    let mut screen_height: f32 = 800.0;
-    let mut screen_width: f32 = 600.0;
+    logging::init();
+    let mut settings = Settings::load_or_default(Settings::PATH);
+    let mut screen_width = settings.resolution.0 as f32;
+    let mut screen_height = settings.resolution.1 as f32;
 
-    let (board_obj, game_stat) = display::Board::new(screen_width, screen_height);
+    let (board_obj, game_stat) = match display::Board::new(screen_width, screen_height) {
+        Ok(board) => board,
+        Err(error) => {
+            log::error!("Can't create the battle board: {}", error);
+            return;
+        }
+    };
     let board = Arc::new(Mutex::new(board_obj));
     let game_state = Arc::new(game_stat);
 
@@ -109,30 +242,214 @@ async fn main() {
         board.clone(),
         tx_gui.clone(),
     )));
-    let handler_window_size = Arc::new(Mutex::new(crate::game::WindowResizeHandler {}));
+    let handler_window_size = Arc::new(Mutex::new(game::WindowResizeHandler {}));
 
     let loop_thread = std::thread::spawn(move || {
-        let event_loop = crate::game::EventLoop::new(rx);
+        let event_loop = game::EventLoop::new(rx);
         event_loop.register_handler(GameEvent::MouseClicked, handler_mouse_cliked.clone());
         event_loop.register_handler(GameEvent::WindowResized, handler_window_size.clone());
         event_loop.start();
     });
 
     let board_renderer = display::BoardRenderer::new(board.clone());
+    let adventure_renderer = match AdventureRenderer::new() {
+        Ok(renderer) => renderer,
+        Err(error) => {
+            log::error!("Can't create the adventure map: {}", error);
+            return;
+        }
+    };
+    let world = AdventureMap::load(AdventureMap::WORLD).and_then(|map| {
+        let faction = TownDefinition::load(TownDefinition::KINGDOM)?;
+        Adventure::new(map, faction, Roster::load(Roster::KINGDOM)?)
+    });
+    let mut adventure = match world {
+        Ok(adventure) => adventure,
+        Err(error) => {
+            log::error!("Can't load the world: {}", error);
+            return;
+        }
+    };
+    let mut mode = Mode::Adventure;
     let config = config::standard();
+    let seed = date::now().to_bits();
+    log::info!("Game seed {}", seed);
+    let mut game_rng = GameRng::new(seed);
 
-    let unit = display::Unit { id: 0, move_range: 2 };
-    board.lock().unwrap().add_unit(0, 0, unit);
+    {
+        let mut board_guard = sync::lock(&board);
+        board_guard.set_language(settings.language);
+        let mut hero = Hero::new(
+            "Roland",
+            PrimaryStats {
+                attack: 2,
+                defence: 2,
+                spell_power: 1,
+                knowledge: 3,
+            },
+        );
+        hero.learn_skill(SecondarySkill::Tactics, SkillLevel::Basic);
+        match ArtifactCatalog::load(ArtifactCatalog::PATH) {
+            Ok(catalog) => {
+                hero.equipment = Equipment::with_sets(catalog.sets().to_vec());
+                if let Some(axe) = catalog.get("centaur_axe")
+                    && let Err(error) = hero.equipment.pick_up(axe.clone()).and_then(|_| hero.equipment.equip(0))
+                {
+                    log::warn!("Can't equip {}: {}", axe.name, error);
+                }
+            }
+            Err(error) => log::warn!("Can't load artifacts: {}", error),
+        }
+        for spell in board_guard.spellbook().spells() {
+            if let Err(error) = hero.learn_spell(spell) {
+                log::debug!("{}", error);
+            }
+        }
+        let mut army = Army::new(Some(hero));
+        let recruited = [("knight", 12), ("archer", 10), ("royal_knight", 4)]
+            .into_iter()
+            .try_for_each(|(unit, count)| army.add(adventure.roster().spawn(unit, 0, count)?))
+            .and_then(|_| adventure.add_hero(army));
+        if let Err(error) = recruited {
+            log::error!("Can't put the hero on the map: {}", error);
+        }
+    }
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = args.iter().position(|arg| arg == "--battle").and_then(|at| args.get(at + 1)) {
+        match start_scenario(&mut sync::lock(&board), path, game_rng.fork()) {
+            Ok(()) => mode = Mode::Scenario,
+            Err(error) => log::error!("Can't start the battle {}: {}", path, error),
+        }
+    }
 
     loop {
+        if let Mode::Adventure | Mode::Town(_) | Mode::Kingdom = mode {
+            clear_background(BLACK);
+            let in_town = if let Mode::Town(tile) = mode { Some(tile) } else { None };
+            let (action, town_action, kingdom_closed) = {
+                let board_guard = sync::lock(&board);
+                let locale = board_guard.locale();
+                let overlay_open = !matches!(mode, Mode::Adventure);
+                (
+                    adventure_renderer.display(&adventure, locale, overlay_open),
+                    in_town.and_then(|tile| adventure_renderer.display_town(&adventure, tile, locale)),
+                    matches!(mode, Mode::Kingdom) && adventure_renderer.display_kingdom(&adventure, locale),
+                )
+            };
+            if kingdom_closed {
+                mode = Mode::Adventure;
+            }
+            match action {
+                Some(AdventureAction::EndDay) => adventure.end_day(),
+                Some(AdventureAction::Kingdom) => mode = Mode::Kingdom,
+                Some(AdventureAction::Move(tile)) if visits_town(&adventure, tile) => mode = Mode::Town(tile),
+                Some(AdventureAction::Move(tile)) => match adventure.move_hero(tile) {
+                    Ok(Some(encounter)) => {
+                        match start_battle(&mut sync::lock(&board), &adventure, &encounter, game_rng.fork()) {
+                            Ok(()) => mode = Mode::Battle(encounter),
+                            Err(error) => log::error!("Can't start the battle: {}", error),
+                        }
+                    }
+                    Ok(None) => {
+                        if let Some(tile) = adventure.selected().map(|hero| hero.position)
+                            && visits_town(&adventure, tile)
+                        {
+                            mode = Mode::Town(tile);
+                        }
+                    }
+                    Err(error) => log::info!("Can't move there: {}", error),
+                },
+                None => {}
+            }
+            if let (Some(tile), Some(action)) = (in_town, town_action) {
+                let done = match &action {
+                    TownAction::Build(building) => adventure.build(tile, building),
+                    TownAction::Recruit(unit, count) => adventure.recruit(tile, unit, *count),
+                    TownAction::Close => {
+                        mode = Mode::Adventure;
+                        Ok(())
+                    }
+                };
+                if let Err(error) = done {
+                    log::info!("{:?} failed: {}", action, error);
+                }
+            }
+            if display::any_key_pressed(&settings.key_bindings.cancel) {
+                mode = Mode::Adventure;
+            }
+            next_frame().await;
+            continue;
+        }
+        let paused = sync::lock(&board).is_paused();
+        if !paused {
+            board_renderer.update_camera(&settings.key_bindings);
+        }
         board_renderer.display();
-        board_renderer.display_battle_interface();
-        if is_mouse_button_pressed(MouseButton::Left) {
+        board_renderer.scroll_combat_log();
+        if let Some(action) = board_renderer.display_battle_interface().filter(|_| !paused) {
+            let mut board_guard = sync::lock(&board);
+            match (action, board_guard.active_unit()) {
+                (BattleAction::Magic, _) => board_guard.open_spellbook(),
+                (BattleAction::System, _) => board_guard.open_system_menu(),
+                (BattleAction::Defend, Some(unit)) => {
+                    if let Err(error) = board_guard.defend(unit.id) {
+                        log::warn!("Can't defend: {}", error);
+                    }
+                    finish_turn(&mut board_guard, unit.id);
+                }
+                (BattleAction::Wait, Some(unit)) => {
+                    board_guard.record(BattleEvent::Waited {
+                        unit: unit.name,
+                        count: unit.count,
+                    });
+                    finish_turn(&mut board_guard, unit.id);
+                }
+                _ => log::info!("Battle action clicked: {:?}", action),
+            }
+        }
+        if board_renderer.display_tactics() && !paused {
+            let mut board_guard = sync::lock(&board);
+            board_guard.reset_back_light_all_tiles();
+            board_guard.end_tactics();
+        }
+        if !paused {
+            board_renderer.display_attack_preview();
+        }
+        board_renderer.display_spellbook();
+        if let Some(command) = board_renderer.display_system_menu(&settings) {
+            match run_menu_command(&mut sync::lock(&board), &mut settings, command) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(error) => log::warn!("{:?} failed: {}", command, error),
+            }
+        }
+        if let Some(choice) = board_renderer.display_level_up()
+            && let Err(error) = sync::lock(&board).choose_skill(choice)
+        {
+            log::warn!("Can't pick skill {}: {}", choice, error);
+        }
+        if display::any_key_pressed(&settings.key_bindings.cancel) {
+            let mut board_guard = sync::lock(&board);
+            board_guard.close_spellbook();
+            board_guard.close_system_menu();
+            board_guard.select_spell(None);
+        }
+        let overlay_open = {
+            let board_guard = sync::lock(&board);
+            board_guard.is_spellbook_open() || board_guard.is_paused()
+        };
+        if is_mouse_button_pressed(MouseButton::Left) && !overlay_open {
             let (mouse_x, mouse_y) = mouse_position();
             let position = MousePosition(mouse_x, mouse_y);
-            let encoded: Vec<u8> = bincode::encode_to_vec(&position, config).unwrap();
-            tx.send((GameEvent::MouseClicked, encoded)).unwrap();
-            print!("Mouse clicked at ({}, {})\n", mouse_x, mouse_y);
+            match bincode::encode_to_vec(&position, config) {
+                Ok(encoded) => {
+                    if tx.send((GameEvent::MouseClicked, encoded)).is_err() {
+                        log::error!("Event loop is gone, dropping click");
+                    }
+                }
+                Err(error) => log::warn!("Can't encode mouse position: {}", error),
+            }
+            log::trace!("Mouse clicked at ({}, {})", mouse_x, mouse_y);
         }
 
         if screen_width != macroquad::window::screen_width()
@@ -140,46 +457,44 @@ async fn main() {
         {
             screen_width = macroquad::window::screen_width();
             screen_height = macroquad::window::screen_height();
-            board
-                .lock()
-                .unwrap()
-                .update_screen_size(screen_width, screen_height);
-        }
-
-        if let Ok((event, payload)) = rx_gui.try_recv() {
-            match event {
-                GuiEvent::BackLightTile => {
-                    let (tile_index, _): (usize, usize) =
-                        bincode::decode_from_slice(&payload[..], config).unwrap();
-                    println!("Backlighting tile at index: {}", tile_index);
-                    {
-                        let mut board_guard = board.lock().unwrap();
-                        board_guard.reset_back_light_all_tiles();
-                        let mut tiles = board_guard.game_state.tiles.lock().unwrap();
-                        let tiles_size = tiles.len();
-                        if let Some(tile) = tiles.get_mut(tile_index) {
-                            tile.back_light = true;
-                            if let Some(unit) = tile.get_unit() {
-                                let move_count = unit.move_range + 1;
-                                back_light_tiles(move_count, tiles_size, tile_index, &mut tiles);
-                            }
-                        }
-                    }
-                },
-                GuiEvent::MoveUnit => {
-                    let mut board_guard = board.lock().unwrap();
-                    board_guard.reset_back_light_all_tiles();
-                    let ((tile_index, unit_id), _): ((usize, usize), usize) =
-                        bincode::decode_from_slice(&payload[..], config).unwrap();
-                    println!("Move tile at index: {}", tile_index);
-                    {
-                        board_guard.move_unit(tile_index, unit_id);
-                    }
+            sync::lock(&board).update_screen_size(screen_width, screen_height);
+        }
+
+        if !paused
+            && let Ok((event, payload)) = rx_gui.try_recv()
+            && let Err(error) = handle_gui_event(&board, event, &payload, config)
+        {
+            log::warn!("Gui event failed: {}", error);
+        }
+
+        if let Mode::Battle(encounter) = &mode {
+            let mut board_guard = sync::lock(&board);
+            // The hero goes back to the map once every level it gained has its skill.
+            if board_guard.level_up().is_none()
+                && let Some(result) = board_guard.take_result()
+            {
+                let hero = board_guard.hero().cloned();
+                if let Err(error) = adventure.resolve_battle(encounter, &result, hero) {
+                    log::error!("Can't bring the battle back to the map: {}", error);
                 }
+                mode = Mode::Adventure;
             }
         }
+        if let Mode::Scenario = mode
+            && let Some(result) = sync::lock(&board).take_result()
+        {
+            log::info!("Scenario over, {}", if result.victory { "won" } else { "lost" });
+            mode = Mode::Adventure;
+        }
 
         next_frame().await
     }
-    loop_thread.join().unwrap();
+
+    if tx.send((GameEvent::Quit, Vec::new())).is_err() {
+        log::warn!("Event loop already stopped");
+    }
+    if loop_thread.join().is_err() {
+        log::error!("Event loop thread panicked");
+    }
+    log::info!("Bye");
 }
\ No newline at end of file
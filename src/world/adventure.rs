@@ -0,0 +1,536 @@
+use crate::battle::{Army, BattleResult, Roster, Side};
+use crate::error::{Error, Result};
+use crate::hero::Hero;
+use crate::world::economy::{Calendar, Resources};
+use crate::world::map::{AdventureMap, MapObject};
+use crate::world::town::{Town, TownDefinition};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A hero travelling the map with the army it leads.
+#[derive(Clone, Debug)]
+pub struct MapHero {
+    pub army: Army,
+    pub position: usize,
+    /// Points left for today; every tile entered costs its terrain's price.
+    pub movement: u32,
+}
+
+impl MapHero {
+    pub const MOVEMENT_PER_DAY: u32 = 20;
+
+    pub fn new(army: Army, position: usize) -> Self {
+        Self {
+            army,
+            position,
+            movement: Self::MOVEMENT_PER_DAY,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.army.hero.as_ref().map_or("", |hero| hero.name.as_str())
+    }
+}
+
+/// Who stands in a hero's way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Foe {
+    /// A neutral stack of `count` roster creatures `unit`.
+    Monster { unit: String, count: u32 },
+    /// The garrison of a town the player doesn't hold.
+    Garrison,
+}
+
+/// A hero walked into a fight: the battle screen takes over until it is resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Encounter {
+    pub hero: usize,
+    pub tile: usize,
+    pub foe: Foe,
+}
+
+/// The adventure map layer: the map, the player's heroes and towns on it and the passing days.
+#[derive(Clone, Debug)]
+pub struct Adventure {
+    pub map: AdventureMap,
+    heroes: Vec<MapHero>,
+    selected: usize,
+    /// Town state by map tile.
+    towns: BTreeMap<usize, Town>,
+    faction: TownDefinition,
+    roster: Roster,
+    /// Mines the player holds, by map tile.
+    mines: BTreeSet<usize>,
+    calendar: Calendar,
+    /// The player's stockpile.
+    resources: Resources,
+}
+
+impl Adventure {
+    /// Sets up every town on `map` as a `faction` town with its garrison from `roster`; the
+    /// first week's creatures are ready at once.
+    pub fn new(map: AdventureMap, faction: TownDefinition, roster: Roster) -> Result<Self> {
+        let mut towns = BTreeMap::new();
+        for (tile, object) in map.objects() {
+            if let MapObject::Town { name, owned, garrison } = object {
+                let mut town = Town::new(name, *owned, &faction);
+                for (unit, count) in garrison {
+                    town.garrison.add(roster.spawn(unit, 0, *count)?)?;
+                }
+                town.new_week(&faction, &roster);
+                towns.insert(tile, town);
+            }
+        }
+        Ok(Self {
+            resources: map.resources().clone(),
+            map,
+            heroes: Vec::new(),
+            selected: 0,
+            towns,
+            faction,
+            roster,
+            mines: BTreeSet::new(),
+            calendar: Calendar::new(),
+        })
+    }
+
+    /// Puts `army` on the next free starting tile of the map.
+    pub fn add_hero(&mut self, army: Army) -> Result<usize> {
+        let start = self
+            .map
+            .starts()
+            .iter()
+            .copied()
+            .find(|start| self.hero_at(*start).is_none())
+            .ok_or_else(|| Error::rules("No free starting tile left"))?;
+        self.heroes.push(MapHero::new(army, start));
+        Ok(self.heroes.len() - 1)
+    }
+
+    pub fn heroes(&self) -> &[MapHero] {
+        &self.heroes
+    }
+
+    pub fn hero_at(&self, tile: usize) -> Option<usize> {
+        self.heroes.iter().position(|hero| hero.position == tile)
+    }
+
+    pub fn selected(&self) -> Option<&MapHero> {
+        self.heroes.get(self.selected)
+    }
+
+    pub fn select(&mut self, hero: usize) {
+        if hero < self.heroes.len() {
+            self.selected = hero;
+        }
+    }
+
+    pub fn town(&self, tile: usize) -> Option<&Town> {
+        self.towns.get(&tile)
+    }
+
+    pub fn towns(&self) -> impl Iterator<Item = (usize, &Town)> {
+        self.towns.iter().map(|(tile, town)| (*tile, town))
+    }
+
+    pub fn faction(&self) -> &TownDefinition {
+        &self.faction
+    }
+
+    pub fn roster(&self) -> &Roster {
+        &self.roster
+    }
+
+    fn owned_town(towns: &mut BTreeMap<usize, Town>, tile: usize) -> Result<&mut Town> {
+        towns
+            .get_mut(&tile)
+            .filter(|town| town.owned)
+            .ok_or_else(|| Error::rules(format!("No town of yours on tile {}", tile)))
+    }
+
+    /// Raises `building` in the player's town on `tile`.
+    pub fn build(&mut self, tile: usize, building: &str) -> Result<()> {
+        Self::owned_town(&mut self.towns, tile)?.build(&self.faction, building, &mut self.resources)
+    }
+
+    /// Hires `count` of `unit` in the town on `tile`; they join the hero visiting the town or,
+    /// without one, its garrison.
+    pub fn recruit(&mut self, tile: usize, unit: &str, count: u32) -> Result<()> {
+        let visitor = self.hero_at(tile);
+        let room = match visitor {
+            Some(hero) => &self.heroes[hero].army,
+            None => &Self::owned_town(&mut self.towns, tile)?.garrison,
+        };
+        let joins = room.stacks().iter().any(|stack| stack.kind.as_deref() == Some(unit));
+        if !joins && room.stacks().len() >= Army::MAX_STACKS {
+            return Err(Error::rules(format!("No room for another stack, at most {}", Army::MAX_STACKS)));
+        }
+        let town = Self::owned_town(&mut self.towns, tile)?;
+        let stack = town.recruit(&self.faction, &self.roster, unit, count, &mut self.resources)?;
+        match visitor {
+            Some(hero) => self.heroes[hero].army.reinforce(stack),
+            None => town.garrison.reinforce(stack),
+        }
+    }
+
+    /// The army `encounter`'s hero has to beat.
+    pub fn enemy_army(&self, encounter: &Encounter) -> Result<Army> {
+        match &encounter.foe {
+            Foe::Monster { unit, count } => {
+                let mut army = Army::new(None);
+                army.add(self.roster.spawn(unit, 0, *count)?)?;
+                Ok(army)
+            }
+            Foe::Garrison => self
+                .towns
+                .get(&encounter.tile)
+                .map(|town| town.garrison.clone())
+                .ok_or_else(|| Error::rules(format!("No town on tile {}", encounter.tile))),
+        }
+    }
+
+    pub fn calendar(&self) -> Calendar {
+        self.calendar
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Mines the player holds with their map tiles.
+    pub fn mines(&self) -> impl Iterator<Item = (usize, &MapObject)> {
+        self.mines.iter().filter_map(|tile| Some((*tile, self.map.object(*tile)?)))
+    }
+
+    /// What the player's towns and mines bring in every day.
+    pub fn daily_income(&self) -> Resources {
+        let mut income = Resources::new();
+        for town in self.towns.values().filter(|town| town.owned) {
+            income.add_all(&town.income(&self.faction));
+        }
+        for (_, mine) in self.mines() {
+            if let MapObject::Mine { resource } = mine {
+                income.add(*resource, resource.mine_output());
+            }
+        }
+        income
+    }
+
+    /// Starts the next day: the daily income comes in and every hero's movement is restored;
+    /// a new week also brings the dwellings' growth.
+    pub fn end_day(&mut self) {
+        self.calendar.advance();
+        let income = self.daily_income();
+        self.resources.add_all(&income);
+        for hero in &mut self.heroes {
+            hero.movement = MapHero::MOVEMENT_PER_DAY;
+        }
+        let new_week = self.calendar.is_week_start();
+        for town in self.towns.values_mut() {
+            town.new_day();
+            if new_week {
+                town.new_week(&self.faction, &self.roster);
+            }
+        }
+    }
+
+    /// The way the selected hero would walk to `to` and how many of its steps fit into today.
+    pub fn planned_path(&self, to: usize) -> Option<(Vec<usize>, usize)> {
+        let hero = self.selected()?;
+        let path = self.map.path(hero.position, to)?;
+        let mut left = hero.movement;
+        let reachable = path
+            .iter()
+            .take_while(|&&step| match self.map.cost(step) {
+                Some(cost) if cost <= left => {
+                    left -= cost;
+                    true
+                }
+                _ => false,
+            })
+            .count();
+        Some((path, reachable))
+    }
+
+    /// Walks the selected hero towards `to` as far as today's movement allows. It stops on a
+    /// town or a resource pile it steps onto, in front of another hero and in front of a
+    /// monster or a defended town that isn't the player's, either of which starts a battle.
+    pub fn move_hero(&mut self, to: usize) -> Result<Option<Encounter>> {
+        let (path, reachable) = self
+            .planned_path(to)
+            .ok_or_else(|| Error::rules(format!("No way to tile {}", to)))?;
+        if reachable == 0 {
+            return Err(Error::rules("Not enough movement left today"));
+        }
+        let reachable = match path[..reachable].iter().position(|&step| self.hero_at(step).is_some()) {
+            Some(0) => return Err(Error::rules(format!("Another hero stands on tile {}", path[0]))),
+            Some(blocked) => blocked,
+            None => reachable,
+        };
+        for &step in &path[..reachable] {
+            let cost = self.map.cost(step).unwrap_or_default();
+            let foe = match (self.map.object(step), self.towns.get(&step)) {
+                (Some(MapObject::Monster { unit, count }), _) => Some(Foe::Monster {
+                    unit: unit.clone(),
+                    count: *count,
+                }),
+                (_, Some(town)) if !town.owned && !town.garrison.stacks().is_empty() => Some(Foe::Garrison),
+                _ => None,
+            };
+            if let Some(foe) = foe {
+                self.heroes[self.selected].movement -= cost;
+                return Ok(Some(Encounter {
+                    hero: self.selected,
+                    tile: step,
+                    foe,
+                }));
+            }
+            let hero = &mut self.heroes[self.selected];
+            hero.movement -= cost;
+            hero.position = step;
+            match self.map.object(step) {
+                Some(MapObject::Resource { .. }) => {
+                    if let Some(MapObject::Resource { kind, amount }) = self.map.remove_object(step) {
+                        log::info!("Picked up {} {:?}", amount, kind);
+                        self.resources.add(kind, amount);
+                    }
+                    break;
+                }
+                Some(MapObject::Mine { resource }) => {
+                    if self.mines.insert(step) {
+                        log::info!("{} claims a {:?} mine", self.heroes[self.selected].name(), resource);
+                    }
+                    break;
+                }
+                Some(MapObject::Town { .. }) => {
+                    if let Some(town) = self.towns.get_mut(&step) {
+                        if !town.owned {
+                            log::info!("{} takes {}", self.heroes[self.selected].name(), town.name);
+                        }
+                        town.owned = true;
+                    }
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Brings the outcome of `encounter`'s battle back to the map. The winner keeps the field:
+    /// a beaten monster disappears and a beaten garrison leaves its town to the player, while
+    /// a beaten hero is gone and the defenders stay with their survivors. `hero` is the
+    /// commander as the battle left it, experience included.
+    pub fn resolve_battle(&mut self, encounter: &Encounter, result: &BattleResult, hero: Option<Hero>) -> Result<()> {
+        if encounter.hero >= self.heroes.len() {
+            return Err(Error::rules(format!("No hero {} on the map", encounter.hero)));
+        }
+        let town = self.towns.get_mut(&encounter.tile).filter(|_| encounter.foe == Foe::Garrison);
+        if let Some(town) = town {
+            town.garrison = town.garrison.after_battle(Side::Enemy, result);
+            if result.victory {
+                town.owned = true;
+            }
+        }
+        if result.victory {
+            if let Foe::Monster { .. } = encounter.foe {
+                self.map.remove_object(encounter.tile);
+            }
+            let map_hero = &mut self.heroes[encounter.hero];
+            map_hero.army = map_hero.army.after_battle(Side::Player, result);
+            if hero.is_some() {
+                map_hero.army.hero = hero;
+            }
+        } else {
+            if let Foe::Monster { count: fought, .. } = &encounter.foe {
+                let left = fought.saturating_sub(result.losses(Side::Enemy));
+                if let Some(MapObject::Monster { count, .. }) = self.map.object_mut(encounter.tile) {
+                    *count = left;
+                }
+            }
+            self.heroes.remove(encounter.hero);
+            self.selected = 0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::tile::Unit;
+    use crate::hero::PrimaryStats;
+    use crate::world::economy::Resource;
+
+    const ROAD: &str = r#"(
+        name: "Road",
+        terrain: ["==========", "~~~~======"],
+        objects: [
+            (at: (0, 3), object: Resource(kind: Gold, amount: 500)),
+            (at: (1, 5), object: Mine(resource: Ore)),
+            (at: (1, 6), object: Monster(unit: "peasant", count: 20)),
+            (at: (0, 7), object: Town(name: "Home", owned: true)),
+            (at: (1, 9), object: Town(name: "Keep", garrison: [("archer", 5)])),
+        ],
+        starts: [(0, 0), (0, 9)],
+    )"#;
+
+    fn adventure() -> Adventure {
+        let map = AdventureMap::from_ron(ROAD).unwrap();
+        let faction = TownDefinition::load(TownDefinition::KINGDOM).unwrap();
+        let mut adventure = Adventure::new(map, faction, Roster::load(Roster::KINGDOM).unwrap()).unwrap();
+        let mut army = Army::new(Some(Hero::new("Roland", PrimaryStats::default())));
+        army.add(Unit::new(0, "Knights", 10, 30, 3, 2)).unwrap();
+        adventure.add_hero(army).unwrap();
+        adventure
+    }
+
+    #[test]
+    fn test_resources_are_picked_up_on_the_way() {
+        let mut adventure = adventure();
+        adventure.heroes[0].position = 2;
+        assert_eq!(adventure.move_hero(5).unwrap(), None);
+        assert_eq!(adventure.selected().unwrap().position, 3);
+        assert_eq!(adventure.resources().get(Resource::Gold), 500);
+        assert!(adventure.map.object(3).is_none());
+    }
+
+    #[test]
+    fn test_movement_runs_out_until_the_next_day() {
+        let mut adventure = adventure();
+        adventure.heroes[0].movement = 2;
+        adventure.move_hero(2).unwrap();
+        assert_eq!(adventure.selected().unwrap().position, 2);
+        assert!(adventure.move_hero(1).is_err());
+
+        adventure.end_day();
+        assert_eq!(adventure.calendar().days(), 2);
+        assert_eq!(adventure.selected().unwrap().movement, MapHero::MOVEMENT_PER_DAY);
+    }
+
+    #[test]
+    fn test_hero_stops_in_front_of_another_hero() {
+        let mut adventure = adventure();
+        let mut army = Army::new(Some(Hero::new("Catherine", PrimaryStats::default())));
+        army.add(Unit::new(0, "Archers", 10, 10, 2, 2)).unwrap();
+        let other = adventure.add_hero(army).unwrap();
+        adventure.heroes[other].position = 2;
+        let movement = adventure.selected().unwrap().movement;
+
+        assert_eq!(adventure.move_hero(5).unwrap(), None);
+        assert_eq!(adventure.selected().unwrap().position, 1);
+        assert_eq!(adventure.selected().unwrap().movement, movement - 1);
+
+        assert!(adventure.move_hero(5).is_err());
+        assert_eq!(adventure.selected().unwrap().position, 1);
+        assert_eq!(adventure.selected().unwrap().movement, movement - 1);
+    }
+
+    #[test]
+    fn test_monster_starts_an_encounter() {
+        let mut adventure = adventure();
+        adventure.heroes[0].position = 14;
+        let encounter = adventure.move_hero(16).unwrap().unwrap();
+        assert_eq!(encounter.tile, 16);
+        assert_eq!(adventure.enemy_army(&encounter).unwrap().stacks()[0].count, 20);
+        let position = adventure.selected().unwrap().position;
+        assert!(adventure.map.neighbours(position).any(|next| next == 16));
+    }
+
+    #[test]
+    fn test_victory_clears_the_monster_and_counts_losses() {
+        let mut adventure = adventure();
+        let encounter = Encounter {
+            hero: 0,
+            tile: 16,
+            foe: Foe::Monster {
+                unit: "peasant".to_string(),
+                count: 20,
+            },
+        };
+        let result = BattleResult {
+            victory: true,
+            losses: [4, 20],
+            experience: 100,
+            remaining: vec![(Side::Player, 6), (Side::Enemy, 0)],
+        };
+        adventure.resolve_battle(&encounter, &result, None).unwrap();
+        assert!(adventure.map.object(16).is_none());
+        assert_eq!(adventure.selected().unwrap().army.stacks()[0].count, 6);
+
+        let result = BattleResult {
+            victory: false,
+            losses: [6, 5],
+            ..BattleResult::default()
+        };
+        adventure.map = AdventureMap::from_ron(ROAD).unwrap();
+        adventure.resolve_battle(&encounter, &result, None).unwrap();
+        assert!(adventure.heroes().is_empty());
+        assert_eq!(
+            adventure.map.object(16),
+            Some(&MapObject::Monster {
+                unit: "peasant".to_string(),
+                count: 15
+            })
+        );
+    }
+
+    #[test]
+    fn test_garrison_defends_its_town() {
+        let mut adventure = adventure();
+        adventure.heroes[0].position = 9;
+        let encounter = adventure.move_hero(19).unwrap().unwrap();
+        assert_eq!((encounter.tile, &encounter.foe), (19, &Foe::Garrison));
+        assert_eq!(adventure.enemy_army(&encounter).unwrap().stacks()[0].count, 5);
+
+        let result = BattleResult {
+            victory: true,
+            remaining: vec![(Side::Player, 10), (Side::Enemy, 0)],
+            ..BattleResult::default()
+        };
+        adventure.resolve_battle(&encounter, &result, None).unwrap();
+        let town = adventure.town(19).unwrap();
+        assert!(town.owned && town.garrison.stacks().is_empty());
+        assert!(adventure.map.object(19).is_some());
+    }
+
+    #[test]
+    fn test_recruits_join_the_visiting_hero() {
+        let mut adventure = adventure();
+        adventure.resources = Resources::from([(Resource::Gold, 10_000), (Resource::Wood, 10), (Resource::Ore, 10)]);
+        assert!(adventure.build(19, "fort").is_err());
+        adventure.build(7, "fort").unwrap();
+        adventure.end_day();
+        adventure.build(7, "guardhouse").unwrap();
+        for _ in 0..Calendar::DAYS_PER_WEEK - 1 {
+            adventure.end_day();
+        }
+        assert!(adventure.calendar().is_week_start());
+        assert_eq!(adventure.town(7).unwrap().available("peasant"), 14);
+
+        adventure.recruit(7, "peasant", 4).unwrap();
+        assert_eq!(adventure.town(7).unwrap().garrison.stacks()[0].count, 4);
+        adventure.heroes[0].position = 6;
+        adventure.move_hero(7).unwrap();
+        adventure.recruit(7, "peasant", 10).unwrap();
+        let army = &adventure.selected().unwrap().army;
+        assert_eq!(army.stacks().last().map(|stack| stack.count), Some(10));
+        let income = 7 * 500;
+        assert_eq!(adventure.resources().get(Resource::Gold), 10_000 - 1000 - 500 - 14 * 25 + income);
+    }
+
+    #[test]
+    fn test_mines_and_towns_pay_daily() {
+        let mut adventure = adventure();
+        adventure.heroes[0].position = 14;
+        adventure.move_hero(15).unwrap();
+        assert_eq!(adventure.mines().count(), 1);
+        assert_eq!(
+            adventure.daily_income(),
+            Resources::from([(Resource::Gold, 500), (Resource::Ore, Resource::Ore.mine_output())])
+        );
+
+        adventure.end_day();
+        assert_eq!(adventure.resources().get(Resource::Ore), 2);
+        assert_eq!(adventure.resources().get(Resource::Gold), 500);
+    }
+}
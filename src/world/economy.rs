@@ -0,0 +1,208 @@
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What towns are built and creatures paid with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Resource {
+    Gold,
+    Wood,
+    Ore,
+    Mercury,
+    Sulfur,
+    Crystal,
+    Gems,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 7] = [
+        Resource::Gold,
+        Resource::Wood,
+        Resource::Ore,
+        Resource::Mercury,
+        Resource::Sulfur,
+        Resource::Crystal,
+        Resource::Gems,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Resource::Gold => "ui.resource.gold",
+            Resource::Wood => "ui.resource.wood",
+            Resource::Ore => "ui.resource.ore",
+            Resource::Mercury => "ui.resource.mercury",
+            Resource::Sulfur => "ui.resource.sulfur",
+            Resource::Crystal => "ui.resource.crystal",
+            Resource::Gems => "ui.resource.gems",
+        }
+    }
+
+    /// Rare resources only come from their own mines and the odd pile.
+    pub fn is_rare(&self) -> bool {
+        !matches!(self, Resource::Gold | Resource::Wood | Resource::Ore)
+    }
+
+    /// What a mine of the resource yields every day.
+    pub fn mine_output(&self) -> u32 {
+        match self {
+            Resource::Gold => 1000,
+            Resource::Wood | Resource::Ore => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Amounts of resources, a stockpile or a price; RON writes it as a map, `{Gold: 500}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Resources(BTreeMap<Resource, u32>);
+
+impl<const N: usize> From<[(Resource, u32); N]> for Resources {
+    fn from(amounts: [(Resource, u32); N]) -> Self {
+        Self(BTreeMap::from(amounts))
+    }
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, resource: Resource) -> u32 {
+        self.0.get(&resource).copied().unwrap_or_default()
+    }
+
+    /// Resources with a non-zero amount, in `Resource::ALL` order.
+    pub fn iter(&self) -> impl Iterator<Item = (Resource, u32)> + '_ {
+        self.0.iter().filter(|(_, amount)| **amount > 0).map(|(resource, amount)| (*resource, *amount))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    pub fn add(&mut self, resource: Resource, amount: u32) {
+        *self.0.entry(resource).or_default() += amount;
+    }
+
+    pub fn add_all(&mut self, other: &Resources) {
+        for (resource, amount) in other.iter() {
+            self.add(resource, amount);
+        }
+    }
+
+    /// How many times the stockpile covers `cost`; `u32::MAX` for something free.
+    pub fn affordable(&self, cost: &Resources) -> u32 {
+        cost.iter().map(|(resource, price)| self.get(resource) / price).min().unwrap_or(u32::MAX)
+    }
+
+    /// Takes `times` the `cost` out of the stockpile, or nothing at all when it falls short.
+    pub fn pay(&mut self, cost: &Resources, times: u32) -> Result<()> {
+        if let Some((resource, price)) = cost.iter().find(|(resource, price)| self.get(*resource) < price * times) {
+            return Err(Error::rules(format!(
+                "Not enough {:?}: {} of {}",
+                resource,
+                self.get(resource),
+                price * times
+            )));
+        }
+        for (resource, price) in cost.iter() {
+            if let Some(have) = self.0.get_mut(&resource) {
+                *have -= price * times;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Days of the adventure, counted from day 1 of week 1 of month 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Calendar {
+    day: u32,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calendar {
+    pub const DAYS_PER_WEEK: u32 = 7;
+    pub const WEEKS_PER_MONTH: u32 = 4;
+
+    pub fn new() -> Self {
+        Self { day: 1 }
+    }
+
+    /// Days since the start, the first one being 1.
+    pub fn days(&self) -> u32 {
+        self.day
+    }
+
+    pub fn day_of_week(&self) -> u32 {
+        (self.day - 1) % Self::DAYS_PER_WEEK + 1
+    }
+
+    /// Week of the current month.
+    pub fn week(&self) -> u32 {
+        (self.day - 1) / Self::DAYS_PER_WEEK % Self::WEEKS_PER_MONTH + 1
+    }
+
+    pub fn month(&self) -> u32 {
+        (self.day - 1) / (Self::DAYS_PER_WEEK * Self::WEEKS_PER_MONTH) + 1
+    }
+
+    pub fn is_week_start(&self) -> bool {
+        self.day_of_week() == 1
+    }
+
+    pub fn is_month_start(&self) -> bool {
+        self.is_week_start() && self.week() == 1
+    }
+
+    pub fn advance(&mut self) {
+        self.day += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paying_takes_all_or_nothing() {
+        let mut stock = Resources::from([(Resource::Gold, 1000), (Resource::Wood, 3)]);
+        let cost = Resources::from([(Resource::Gold, 300), (Resource::Wood, 1)]);
+        assert_eq!(stock.affordable(&cost), 3);
+        stock.pay(&cost, 2).unwrap();
+        assert_eq!((stock.get(Resource::Gold), stock.get(Resource::Wood)), (400, 1));
+
+        assert!(stock.pay(&cost, 2).is_err());
+        assert_eq!((stock.get(Resource::Gold), stock.get(Resource::Wood)), (400, 1));
+        assert_eq!(stock.affordable(&Resources::new()), u32::MAX);
+    }
+
+    #[test]
+    fn test_resources_read_as_a_map() {
+        let cost: Resources = ron::from_str("{Gold: 500, Crystal: 1}").unwrap();
+        assert_eq!(cost.iter().collect::<Vec<_>>(), [(Resource::Gold, 500), (Resource::Crystal, 1)]);
+        assert!(Resource::Crystal.is_rare() && !Resource::Ore.is_rare());
+    }
+
+    #[test]
+    fn test_calendar_weeks_and_months() {
+        let mut calendar = Calendar::new();
+        assert!(calendar.is_month_start());
+        for _ in 0..Calendar::DAYS_PER_WEEK {
+            calendar.advance();
+        }
+        assert_eq!((calendar.month(), calendar.week(), calendar.day_of_week()), (1, 2, 1));
+        assert!(calendar.is_week_start() && !calendar.is_month_start());
+        for _ in 0..Calendar::DAYS_PER_WEEK * 3 {
+            calendar.advance();
+        }
+        assert_eq!((calendar.days(), calendar.month(), calendar.week()), (29, 2, 1));
+        assert!(calendar.is_month_start());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::error::{Error, Result};
+use crate::world::economy::{Resource, Resources};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fs;
+
+/// Ground of one adventure map tile, written as one character in map files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Terrain {
+    Grass,
+    Road,
+    Forest,
+    Mountain,
+    Water,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 5] = [Terrain::Grass, Terrain::Road, Terrain::Forest, Terrain::Mountain, Terrain::Water];
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Terrain::Grass),
+            '=' => Some(Terrain::Road),
+            'F' => Some(Terrain::Forest),
+            '^' => Some(Terrain::Mountain),
+            '~' => Some(Terrain::Water),
+            _ => None,
+        }
+    }
+
+    /// Movement points a hero spends entering the tile; `None` when heroes can't walk there.
+    pub fn cost(&self) -> Option<u32> {
+        match self {
+            Terrain::Road => Some(1),
+            Terrain::Grass => Some(2),
+            Terrain::Forest => Some(3),
+            Terrain::Mountain | Terrain::Water => None,
+        }
+    }
+}
+
+/// Something standing on a tile that a hero interacts with by stepping onto it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapObject {
+    /// A town of the player's faction; `garrison` lists (roster creature, count) defending it.
+    Town {
+        name: String,
+        #[serde(default)]
+        owned: bool,
+        #[serde(default)]
+        garrison: Vec<(String, u32)>,
+    },
+    /// A pile of `amount` of a resource, picked up and gone.
+    Resource { kind: Resource, amount: u32 },
+    /// Yields its resource every day to whoever last set foot on it.
+    Mine { resource: Resource },
+    /// A neutral stack of roster creature `unit` that fights whoever steps in.
+    Monster { unit: String, count: u32 },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct PlacedObject {
+    at: (usize, usize),
+    object: MapObject,
+}
+
+/// A map file: one string per row of terrain characters, objects and hero starts as (row, col).
+#[derive(Clone, Debug, Deserialize)]
+struct MapDefinition {
+    name: String,
+    terrain: Vec<String>,
+    #[serde(default)]
+    objects: Vec<PlacedObject>,
+    starts: Vec<(usize, usize)>,
+    /// The player's stockpile on day one.
+    #[serde(default)]
+    resources: Resources,
+}
+
+/// The world outside battles: terrain, what stands on it and where heroes begin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdventureMap {
+    pub name: String,
+    width: usize,
+    height: usize,
+    terrain: Vec<Terrain>,
+    objects: BTreeMap<usize, MapObject>,
+    starts: Vec<usize>,
+    resources: Resources,
+}
+
+impl AdventureMap {
+    pub const WORLD: &'static str = "data/maps/world.ron";
+
+    pub fn from_ron(source: &str) -> Result<Self> {
+        let definition: MapDefinition = ron::from_str(source).map_err(|error| Error::Serialization(error.to_string()))?;
+        let height = definition.terrain.len();
+        let width = definition.terrain.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(Error::rules(format!("Map {} has no terrain", definition.name)));
+        }
+        let mut terrain = Vec::with_capacity(width * height);
+        for (row, line) in definition.terrain.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(Error::rules(format!("Map row {} is not {} tiles wide", row, width)));
+            }
+            for (col, c) in line.chars().enumerate() {
+                let tile = Terrain::from_char(c)
+                    .ok_or_else(|| Error::rules(format!("Unknown terrain {:?} at {}, {}", c, row, col)))?;
+                terrain.push(tile);
+            }
+        }
+        let index = |(row, col): (usize, usize)| {
+            (row < height && col < width)
+                .then_some(row * width + col)
+                .ok_or_else(|| Error::rules(format!("Tile {}, {} is outside the map", row, col)))
+        };
+        let mut objects = BTreeMap::new();
+        for placed in definition.objects {
+            objects.insert(index(placed.at)?, placed.object);
+        }
+        let starts = definition.starts.into_iter().map(index).collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name: definition.name,
+            width,
+            height,
+            terrain,
+            objects,
+            starts,
+            resources: definition.resources,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn terrain(&self, index: usize) -> Option<Terrain> {
+        self.terrain.get(index).copied()
+    }
+
+    pub fn object(&self, index: usize) -> Option<&MapObject> {
+        self.objects.get(&index)
+    }
+
+    pub fn object_mut(&mut self, index: usize) -> Option<&mut MapObject> {
+        self.objects.get_mut(&index)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (usize, &MapObject)> {
+        self.objects.iter().map(|(index, object)| (*index, object))
+    }
+
+    pub fn remove_object(&mut self, index: usize) -> Option<MapObject> {
+        self.objects.remove(&index)
+    }
+
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Tiles around `index`, diagonals included.
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + use<> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let (row, col) = ((index as isize) / width, (index as isize) % width);
+        (-1..=1)
+            .flat_map(move |d_row| (-1..=1).map(move |d_col| (row + d_row, col + d_col)))
+            .filter(move |&(r, c)| (r, c) != (row, col) && (0..height).contains(&r) && (0..width).contains(&c))
+            .map(move |(r, c)| (r * width + c) as usize)
+    }
+
+    /// Points spent stepping onto `index`, `None` for impassable tiles.
+    pub fn cost(&self, index: usize) -> Option<u32> {
+        self.terrain(index)?.cost()
+    }
+
+    /// Cheapest way from `from` to `to`, without the start tile. Monsters block the way and
+    /// can only be the destination.
+    pub fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        if from == to || self.cost(to).is_none() {
+            return None;
+        }
+        let mut best = vec![u32::MAX; self.terrain.len()];
+        let mut previous = vec![None; self.terrain.len()];
+        let mut queue = BinaryHeap::from([Reverse((0, from))]);
+        best[from] = 0;
+        while let Some(Reverse((spent, index))) = queue.pop() {
+            if index == to {
+                break;
+            }
+            if spent > best[index] || (index != from && matches!(self.object(index), Some(MapObject::Monster { .. }))) {
+                continue;
+            }
+            for next in self.neighbours(index) {
+                let Some(cost) = self.cost(next) else {
+                    continue;
+                };
+                if spent + cost < best[next] {
+                    best[next] = spent + cost;
+                    previous[next] = Some(index);
+                    queue.push(Reverse((spent + cost, next)));
+                }
+            }
+        }
+        let mut path = vec![to];
+        while let Some(step) = previous[*path.last()?] {
+            if step == from {
+                path.reverse();
+                return Some(path);
+            }
+            path.push(step);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = r#"(
+        name: "Test",
+        terrain: [
+            ".=~..",
+            ".=~F.",
+            ".===.",
+        ],
+        objects: [
+            (at: (0, 4), object: Monster(unit: "peasant", count: 10)),
+            (at: (2, 0), object: Resource(kind: Gold, amount: 500)),
+        ],
+        starts: [(0, 0)],
+    )"#;
+
+    #[test]
+    fn test_bundled_world_parses() {
+        let map = AdventureMap::load(AdventureMap::WORLD).unwrap();
+        assert!(!map.starts().is_empty());
+        assert!(map.objects().any(|(_, object)| matches!(object, MapObject::Monster { .. })));
+    }
+
+    #[test]
+    fn test_rejects_ragged_rows_and_unknown_terrain() {
+        assert!(AdventureMap::from_ron(r#"(name: "x", terrain: ["..", "."], starts: [])"#).is_err());
+        assert!(AdventureMap::from_ron(r#"(name: "x", terrain: [".?"], starts: [])"#).is_err());
+        assert!(AdventureMap::from_ron(r#"(name: "x", terrain: [".."], starts: [(3, 0)])"#).is_err());
+    }
+
+    #[test]
+    fn test_path_prefers_roads_and_avoids_water() {
+        let map = AdventureMap::from_ron(SMALL).unwrap();
+        let path = map.path(0, 9).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(map.terrain(path[0]), Some(Terrain::Road));
+        assert!(path.iter().all(|&index| map.cost(index).is_some()));
+        assert_eq!(path.last(), Some(&9));
+    }
+
+    #[test]
+    fn test_monsters_end_a_path() {
+        let map = AdventureMap::from_ron(SMALL).unwrap();
+        assert_eq!(map.path(0, 4).and_then(|path| path.last().copied()), Some(4));
+        assert!(map.path(0, 2).is_none());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod adventure;
+pub mod economy;
+pub mod map;
+pub mod town;
+
+pub use adventure::{Adventure, Encounter, Foe, MapHero};
+pub use economy::{Calendar, Resource, Resources};
+pub use map::{AdventureMap, MapObject, Terrain};
+pub use town::{Building, Recruit, Town, TownDefinition};
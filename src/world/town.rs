@@ -0,0 +1,256 @@
+use crate::battle::{Army, Roster};
+use crate::display::tile::Unit;
+use crate::error::{Error, Result};
+use crate::world::economy::Resources;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Building {
+    pub id: String,
+    pub name: String,
+    pub cost: Resources,
+    /// What the building adds to the kingdom's income every day.
+    #[serde(default)]
+    pub income: Resources,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Creature level whose units this building lets the town grow and recruit.
+    #[serde(default)]
+    pub dwelling: Option<u8>,
+}
+
+/// Weekly growth and price of one creature of the faction's roster.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Recruit {
+    pub unit: String,
+    pub growth: u32,
+    pub cost: Resources,
+}
+
+/// Build tree and creatures of a faction's towns.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TownDefinition {
+    pub name: String,
+    /// Buildings every town of the faction starts with.
+    pub starting: Vec<String>,
+    pub buildings: Vec<Building>,
+    pub recruits: Vec<Recruit>,
+}
+
+impl TownDefinition {
+    pub const KINGDOM: &'static str = "data/towns/kingdom.ron";
+
+    pub fn from_ron(source: &str) -> std::result::Result<Self, ron::error::SpannedError> {
+        ron::from_str(source)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let source = fs::read_to_string(path).map_err(|error| Error::asset(path, error))?;
+        Self::from_ron(&source).map_err(|error| Error::asset(path, error))
+    }
+
+    pub fn building(&self, id: &str) -> Option<&Building> {
+        self.buildings.iter().find(|building| building.id == id)
+    }
+
+    pub fn recruit(&self, unit: &str) -> Option<&Recruit> {
+        self.recruits.iter().find(|recruit| recruit.unit == unit)
+    }
+}
+
+/// A town on the adventure map: what has been built, creatures waiting to be hired and the
+/// garrison that defends the walls.
+#[derive(Clone, Debug)]
+pub struct Town {
+    pub name: String,
+    /// Whether the player holds the town; any other has to be taken by beating its garrison.
+    pub owned: bool,
+    built: BTreeSet<String>,
+    built_today: bool,
+    /// Creatures ready to be recruited, by unit id.
+    available: BTreeMap<String, u32>,
+    pub garrison: Army,
+}
+
+impl Town {
+    pub fn new(name: &str, owned: bool, definition: &TownDefinition) -> Self {
+        Self {
+            name: name.to_string(),
+            owned,
+            built: definition.starting.iter().cloned().collect(),
+            built_today: false,
+            available: BTreeMap::new(),
+            garrison: Army::new(None),
+        }
+    }
+
+    pub fn is_built(&self, id: &str) -> bool {
+        self.built.contains(id)
+    }
+
+    pub fn built_today(&self) -> bool {
+        self.built_today
+    }
+
+    /// The building `id` if the town may raise it today, leaving the price aside.
+    pub fn can_build<'a>(&self, definition: &'a TownDefinition, id: &str) -> Result<&'a Building> {
+        let building = definition
+            .building(id)
+            .ok_or_else(|| Error::rules(format!("{} towns have no {}", definition.name, id)))?;
+        if self.is_built(id) {
+            return Err(Error::rules(format!("{} already stands in {}", building.name, self.name)));
+        }
+        if self.built_today {
+            return Err(Error::rules(format!("{} has already built today", self.name)));
+        }
+        if let Some(missing) = building.requires.iter().find(|required| !self.is_built(required)) {
+            return Err(Error::rules(format!("{} needs {} first", building.name, missing)));
+        }
+        Ok(building)
+    }
+
+    /// Raises building `id`, paying for it out of `stock`; one building a day.
+    pub fn build(&mut self, definition: &TownDefinition, id: &str, stock: &mut Resources) -> Result<()> {
+        let building = self.can_build(definition, id)?;
+        stock.pay(&building.cost, 1)?;
+        log::info!("{} built in {}", building.name, self.name);
+        self.built.insert(building.id.clone());
+        self.built_today = true;
+        Ok(())
+    }
+
+    /// Daily income of everything built in the town.
+    pub fn income(&self, definition: &TownDefinition) -> Resources {
+        let mut income = Resources::new();
+        for building in definition.buildings.iter().filter(|building| self.is_built(&building.id)) {
+            income.add_all(&building.income);
+        }
+        income
+    }
+
+    pub fn new_day(&mut self) {
+        self.built_today = false;
+    }
+
+    /// Whether a dwelling for `unit`'s level stands in the town.
+    pub fn has_dwelling(&self, definition: &TownDefinition, roster: &Roster, unit: &str) -> bool {
+        let Some(level) = roster.get(unit).map(|unit| unit.level) else {
+            return false;
+        };
+        definition
+            .buildings
+            .iter()
+            .any(|building| building.dwelling == Some(level) && self.is_built(&building.id))
+    }
+
+    /// Creatures the town's dwellings let it hire, in the faction's order.
+    pub fn recruits<'a>(&self, definition: &'a TownDefinition, roster: &Roster) -> Vec<&'a Recruit> {
+        definition
+            .recruits
+            .iter()
+            .filter(|recruit| self.has_dwelling(definition, roster, &recruit.unit))
+            .collect()
+    }
+
+    /// Every dwelling adds its creatures' weekly growth to those waiting.
+    pub fn new_week(&mut self, definition: &TownDefinition, roster: &Roster) {
+        for recruit in self.recruits(definition, roster) {
+            *self.available.entry(recruit.unit.clone()).or_default() += recruit.growth;
+        }
+    }
+
+    pub fn available(&self, unit: &str) -> u32 {
+        self.available.get(unit).copied().unwrap_or_default()
+    }
+
+    /// Hires `count` of `unit`, paying out of `stock`; the stack is the caller's to place.
+    pub fn recruit(
+        &mut self,
+        definition: &TownDefinition,
+        roster: &Roster,
+        unit: &str,
+        count: u32,
+        stock: &mut Resources,
+    ) -> Result<Unit> {
+        let recruit = definition
+            .recruit(unit)
+            .filter(|_| self.has_dwelling(definition, roster, unit))
+            .ok_or_else(|| Error::rules(format!("{} can't recruit {}", self.name, unit)))?;
+        if count == 0 || count > self.available(unit) {
+            return Err(Error::rules(format!("{} has {} {} to recruit", self.name, self.available(unit), unit)));
+        }
+        let stack = roster.spawn(unit, 0, count)?;
+        stock.pay(&recruit.cost, count)?;
+        *self.available.entry(unit.to_string()).or_default() -= count;
+        Ok(stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::economy::Resource;
+
+    fn kingdom() -> (TownDefinition, Roster) {
+        (
+            TownDefinition::load(TownDefinition::KINGDOM).unwrap(),
+            Roster::load(Roster::KINGDOM).unwrap(),
+        )
+    }
+
+    fn treasury(gold: u32) -> Resources {
+        Resources::from([(Resource::Gold, gold), (Resource::Wood, 20), (Resource::Ore, 20)])
+    }
+
+    #[test]
+    fn test_kingdom_recruits_every_roster_unit() {
+        let (definition, roster) = kingdom();
+        for unit in roster.units() {
+            assert!(definition.recruit(&unit.id).is_some(), "{} can't be recruited", unit.id);
+            assert!(definition.buildings.iter().any(|building| building.dwelling == Some(unit.level)));
+        }
+    }
+
+    #[test]
+    fn test_build_tree_one_building_a_day() {
+        let (definition, _) = kingdom();
+        let mut town = Town::new("Rolandgard", true, &definition);
+        let mut stock = treasury(5000);
+        assert!(town.build(&definition, "guardhouse", &mut stock).is_err());
+
+        town.build(&definition, "fort", &mut stock).unwrap();
+        assert_eq!(stock.get(Resource::Gold), 4000);
+        assert!(town.build(&definition, "guardhouse", &mut stock).is_err());
+
+        town.new_day();
+        town.build(&definition, "guardhouse", &mut stock).unwrap();
+        assert!(town.is_built("guardhouse"));
+    }
+
+    #[test]
+    fn test_dwellings_grow_weekly_and_recruiting_pays() {
+        let (definition, roster) = kingdom();
+        let mut town = Town::new("Rolandgard", true, &definition);
+        let mut stock = treasury(5000);
+        town.build(&definition, "fort", &mut stock).unwrap();
+        town.new_day();
+        town.build(&definition, "guardhouse", &mut stock).unwrap();
+        town.new_week(&definition, &roster);
+        assert_eq!((town.available("peasant"), town.available("archer")), (14, 9));
+        assert_eq!(town.available("knight"), 0);
+
+        let archers = town.recruit(&definition, &roster, "archer", 9, &mut stock).unwrap();
+        assert_eq!((archers.count, archers.kind.as_deref()), (9, Some("archer")));
+        assert_eq!(stock.get(Resource::Gold), 3500 - 900);
+        assert_eq!(town.available("archer"), 0);
+        assert!(town.recruit(&definition, &roster, "archer", 1, &mut stock).is_err());
+        assert!(town.recruit(&definition, &roster, "knight", 1, &mut stock).is_err());
+
+        let mut poor = treasury(50);
+        assert!(town.recruit(&definition, &roster, "peasant", 3, &mut poor).is_err());
+        assert_eq!((poor.get(Resource::Gold), town.available("peasant")), (50, 14));
+        assert_eq!(poor.affordable(&definition.recruit("peasant").unwrap().cost), 2);
+    }
+}